@@ -1,17 +1,23 @@
-use std::{borrow::Cow, iter::repeat_with};
+use std::{borrow::Cow, hash::Hash, iter::repeat_with};
 
 use egui::{Response, Sense, Widget};
 use egui_data_table::{
-    viewer::{default_hotkeys, CellWriteContext, DecodeErrorBehavior, RowCodec, UiActionContext},
-    RowViewer,
+    model::TableModel,
+    viewer::{
+        default_hotkeys, CellStyle, CellWriteContext, ClipboardFormat, CommitPolicy,
+        DecodeErrorBehavior, EditorFocus, EmptyRowCreateContext, PasteDecision, PasteSummary,
+        RowCodec, RowStatus, TableChange, UiActionContext, UndoKind, ValidationError,
+    },
+    ImportPreview, ResortPolicy, RowViewer,
 };
 use log::info;
 
 /* ----------------------------------------- Data Scheme ---------------------------------------- */
 
 struct Viewer {
-    filter: String,
+    model: Model,
     row_protection: bool,
+    summary_row: bool,
     hotkeys: Vec<(egui::KeyboardShortcut, egui_data_table::UiAction)>,
 }
 
@@ -26,12 +32,23 @@ enum Grade {
     F,
 }
 
+#[derive(Debug, Clone)]
+enum Cell {
+    Text(String),
+    Int(i32),
+    Bool(bool),
+    Grade(Grade),
+}
+
 /* -------------------------------------------- Codec ------------------------------------------- */
 
 struct Codec;
 
 impl RowCodec<Row> for Codec {
-    type DeserializeError = &'static str;
+    fn clipboard_format(&self) -> ClipboardFormat {
+        // Compatible with the spreadsheet applications.
+        ClipboardFormat::SPREADSHEET
+    }
 
     fn encode_column(&mut self, src_row: &Row, column: usize, dst: &mut String) {
         match column {
@@ -78,11 +95,40 @@ impl RowCodec<Row> for Codec {
     }
 }
 
+/* -------------------------------------------- Model ------------------------------------------- */
+
+#[derive(Default)]
+struct Model {
+    filter: String,
+}
+
+impl TableModel<Row> for Model {
+    fn compare_cell(&self, row_l: &Row, row_r: &Row, column: usize) -> std::cmp::Ordering {
+        match column {
+            0 => row_l.0.cmp(&row_r.0),
+            1 => row_l.1.cmp(&row_r.1),
+            2 => unreachable!(),
+            3 => row_l.3.cmp(&row_r.3),
+            _ => unreachable!(),
+        }
+    }
+
+    fn row_filter_hash(&mut self, mut state: &mut dyn std::hash::Hasher) {
+        self.filter.hash(&mut state);
+    }
+
+    fn filter_row(&mut self, row: &Row) -> bool {
+        row.0.contains(&self.filter)
+    }
+}
+
 /* ------------------------------------ Viewer Implementation ----------------------------------- */
 
 impl RowViewer<Row> for Viewer {
-    fn try_create_codec(&mut self, _: bool) -> Option<impl RowCodec<Row>> {
-        Some(Codec)
+    type CellValue = Cell;
+
+    fn try_create_codec(&mut self, _: bool) -> Option<Box<dyn RowCodec<Row> + '_>> {
+        Some(Box::new(Codec))
     }
 
     fn num_columns(&mut self) -> usize {
@@ -103,20 +149,126 @@ impl RowViewer<Row> for Viewer {
         [true, true, false, true][column]
     }
 
-    fn compare_cell(&self, row_l: &Row, row_r: &Row, column: usize) -> std::cmp::Ordering {
-        match column {
-            0 => row_l.0.cmp(&row_r.0),
-            1 => row_l.1.cmp(&row_r.1),
-            2 => unreachable!(),
-            3 => row_l.3.cmp(&row_r.3),
+    // Name column stays as the leading, visible one.
+    fn is_reorderable_column(&mut self, column: usize) -> bool {
+        column != 0
+    }
+
+    fn is_hidable_column(&mut self, column: usize) -> bool {
+        column != 0
+    }
+
+    fn get_cell(&mut self, row: &Row, column: usize) -> Option<Cell> {
+        Some(match column {
+            0 => Cell::Text(row.0.clone()),
+            1 => Cell::Int(row.1),
+            2 => Cell::Bool(row.2),
+            3 => Cell::Grade(row.3),
             _ => unreachable!(),
+        })
+    }
+
+    fn convert_cell(&mut self, _: usize, dst: usize, value: Cell) -> Option<Cell> {
+        Some(match (dst, value) {
+            (0, Cell::Int(x)) => Cell::Text(x.to_string()),
+            (0, Cell::Bool(x)) => Cell::Text(x.to_string()),
+            (0, Cell::Grade(x)) => Cell::Text(format!("{x:?}")),
+            (1, Cell::Text(x)) => Cell::Int(x.trim().parse().ok()?),
+            (2, Cell::Int(x)) => Cell::Bool(x != 0),
+            (_, value) => value,
+        })
+    }
+
+    fn set_cell(&mut self, row: &mut Row, column: usize, value: Cell) -> bool {
+        match (column, value) {
+            (0, Cell::Text(x)) => row.0 = x,
+            (1, Cell::Int(x)) => row.1 = x,
+            (2, Cell::Bool(x)) => row.2 = x,
+            (3, Cell::Grade(x)) => row.3 = x,
+            _ => return false,
         }
+
+        true
+    }
+
+    fn extend_cell_context_menu(
+        &mut self,
+        ui: &mut egui::Ui,
+        row: &Row,
+        _column: usize,
+        actions: &mut Vec<egui_data_table::UiAction>,
+    ) {
+        ui.separator();
+
+        if ui.button("Log Row").clicked() {
+            info!("context menu: {row:?}");
+            ui.close_menu();
+        }
+
+        if ui.button("Select All").clicked() {
+            actions.push(egui_data_table::UiAction::SelectAll);
+            ui.close_menu();
+        }
+    }
+
+    fn model(&mut self) -> Option<&mut dyn TableModel<Row>> {
+        Some(&mut self.model)
     }
 
     fn new_empty_row(&mut self) -> Row {
         Row("".to_string(), 0, false, Grade::F)
     }
 
+    fn new_empty_row_for(&mut self, context: EmptyRowCreateContext<Row>) -> Row {
+        match context {
+            // Inherit the age and grade of the row above.
+            EmptyRowCreateContext::InsertBelow { source_row } => {
+                Row("".to_string(), source_row.1, false, source_row.3)
+            }
+            _ => self.new_empty_row(),
+        }
+    }
+
+    fn default_cell_value(
+        &mut self,
+        column: usize,
+        context: EmptyRowCreateContext<Row>,
+    ) -> Option<Cell> {
+        // New students start with grade C, unless inherited from the row above.
+        match (column, context) {
+            (3, EmptyRowCreateContext::InsertNewLine) => Some(Cell::Grade(Grade::C)),
+            _ => None,
+        }
+    }
+
+    fn cell_editor_focus(&mut self, _row: &Row, column: usize) -> EditorFocus {
+        // Keep the age draggable, instead of turning it into text input.
+        match column {
+            0 => EditorFocus::SelectAll,
+            1 => EditorFocus::None,
+            _ => EditorFocus::Immediate,
+        }
+    }
+
+    fn column_commit_policy(&mut self, column: usize) -> CommitPolicy {
+        // Age is edited by dragging; merge repeated adjustments into single undo step.
+        if column == 1 {
+            CommitPolicy::Coalesce { window: 2.0 }
+        } else {
+            CommitPolicy::Separate
+        }
+    }
+
+    fn row_templates(&mut self) -> Vec<(Cow<'static, str>, Row)> {
+        vec![
+            ("Student".into(), Row("student".into(), 15, true, Grade::C)),
+            (
+                "Graduate".into(),
+                Row("graduate".into(), 25, false, Grade::B),
+            ),
+        ]
+    }
+
     fn set_cell_value(&mut self, src: &Row, dst: &mut Row, column: usize) {
         match column {
             0 => dst.0.clone_from(&src.0),
@@ -141,6 +293,39 @@ impl RowViewer<Row> for Viewer {
         !current.2
     }
 
+    fn validate_cell(&mut self, row: &Row, column: usize) -> Result<(), ValidationError> {
+        match column {
+            0 if row.0.trim().is_empty() => Err(ValidationError::new("Name is empty")),
+            1 if row.1 < 0 => Err(ValidationError::new("Age can't be negative")),
+            _ => Ok(()),
+        }
+    }
+
+    fn selection_summary(
+        &mut self,
+        rows: &[&Row],
+        columns: &[usize],
+    ) -> Vec<(Cow<'static, str>, String)> {
+        if !columns.contains(&1) {
+            return Vec::new();
+        }
+
+        let sum: i32 = rows.iter().map(|x| x.1).sum();
+        let avg = sum as f64 / rows.len() as f64;
+        vec![
+            ("Age Sum".into(), sum.to_string()),
+            ("Age Avg".into(), format!("{avg:.1}")),
+        ]
+    }
+
+    fn row_status(&mut self, row: &Row) -> RowStatus {
+        if row.0.trim().is_empty() || row.1 < 0 {
+            RowStatus::Error
+        } else {
+            RowStatus::None
+        }
+    }
+
     fn confirm_row_deletion_by_ui(&mut self, row: &Row) -> bool {
         if !self.row_protection {
             return true;
@@ -149,6 +334,14 @@ impl RowViewer<Row> for Viewer {
         !row.2
     }
 
+    fn confirm_paste(&mut self, summary: &PasteSummary) -> PasteDecision {
+        if summary.cells > 20 || summary.skipped_rows > 0 {
+            PasteDecision::Confirm
+        } else {
+            PasteDecision::Apply
+        }
+    }
+
     fn show_cell_view(&mut self, ui: &mut egui::Ui, row: &Row, column: usize) {
         let _ = match column {
             0 => ui.label(&row.0),
@@ -165,6 +358,13 @@ impl RowViewer<Row> for Viewer {
         };
     }
 
+    fn cell_style(&mut self, row: &Row, column: usize) -> Option<CellStyle> {
+        (column == 3 && row.3 == Grade::F).then(|| CellStyle {
+            fg: Some(egui::Color32::RED),
+            ..Default::default()
+        })
+    }
+
     fn on_cell_view_response(
         &mut self,
         _row: &Row,
@@ -206,12 +406,34 @@ impl RowViewer<Row> for Viewer {
         .into()
     }
 
-    fn row_filter_hash(&mut self) -> &impl std::hash::Hash {
-        &self.filter
+    fn has_column_filters(&mut self) -> bool {
+        true
     }
 
-    fn filter_row(&mut self, row: &Row) -> bool {
-        row.0.contains(&self.filter)
+    fn show_column_filter(&mut self, ui: &mut egui::Ui, column: usize) -> Option<egui::Response> {
+        (column == 0)
+            .then(|| ui.add(egui::TextEdit::singleline(&mut self.model.filter).hint_text("Filter")))
+    }
+
+    fn has_column_summary(&mut self) -> bool {
+        self.summary_row
+    }
+
+    fn show_column_summary(
+        &mut self,
+        ui: &mut egui::Ui,
+        column: usize,
+        rows: &mut dyn Iterator<Item = &Row>,
+    ) {
+        match column {
+            0 => ui.label(format!("{} rows", rows.count())),
+            1 => {
+                let (n, sum) = rows.fold((0, 0i64), |(n, sum), row| (n + 1, sum + row.1 as i64));
+                ui.label(format!("avg {:.1}", sum as f64 / n.max(1) as f64))
+            }
+            2 => ui.label(format!("{} students", rows.filter(|row| row.2).count())),
+            _ => return,
+        };
     }
 
     fn hotkeys(
@@ -227,6 +449,15 @@ impl RowViewer<Row> for Viewer {
         true
     }
 
+    fn minimap_row_marker(&mut self, row: &Row) -> Option<egui::Color32> {
+        // Mark the oldest ones.
+        (row.1 == 30).then_some(egui::Color32::LIGHT_BLUE)
+    }
+
+    fn on_undo_applied(&mut self, kind: UndoKind, changes: &[TableChange]) {
+        info!("{kind:?} applied {} changes", changes.len());
+    }
+
     fn on_highlight_change(&mut self, highlighted: &[&Row], unhighlighted: &[&Row]) {
         info!("highlight {:?}", highlighted);
         info!("unhighlight {:?}", unhighlighted);
@@ -239,8 +470,16 @@ struct DemoApp {
     table: egui_data_table::DataTable<Row>,
     viewer: Viewer,
     style_override: egui_data_table::Style,
+    loading: bool,
+    fit_columns: bool,
+    import: Option<ImportPreview<Row>>,
 }
 
+/// Stands in for the contents of an imported file; a row with an invalid age, a blank line and
+/// a row without name.
+const IMPORT_SAMPLE: &str =
+    "Ada\t36\tfalse\tA\nBob\tforty\ttrue\tB\n\n\t21\ttrue\tC\nCho\t19\ttrue\t\n";
+
 impl Default for DemoApp {
     fn default() -> Self {
         Self {
@@ -265,11 +504,15 @@ impl Default for DemoApp {
             .take(100000)
             .collect(),
             viewer: Viewer {
-                filter: String::new(),
+                model: Model::default(),
                 hotkeys: Vec::new(),
                 row_protection: false,
+                summary_row: false,
             },
             style_override: Default::default(),
+            loading: false,
+            fit_columns: false,
+            import: None,
         }
     }
 }
@@ -300,8 +543,11 @@ impl eframe::App for DemoApp {
 
                 ui.separator();
 
-                ui.label("Name Filter");
-                ui.text_edit_singleline(&mut self.viewer.filter);
+                if let Some((row, col)) = self.table.interactive_cell() {
+                    let name = self.viewer.column_name(col);
+                    ui.monospace(format!("R{row}:C{col} - {name}"));
+                    ui.separator();
+                }
 
                 ui.add(egui::Button::new("Drag me and drop on any cell").sense(Sense::drag()))
                     .on_hover_text(
@@ -323,9 +569,120 @@ impl eframe::App for DemoApp {
                     )
                     .on_hover_text("If checked, cells will be edited with a single click.");
 
+                    ui.checkbox(
+                        &mut self.style_override.row_edit_session,
+                        "Row Edit Session",
+                    )
+                    .on_hover_text("If checked, Tab moves the editor within the row.");
+
+                    ui.checkbox(&mut self.viewer.summary_row, "Summary Row");
+                    ui.checkbox(&mut self.style_override.high_contrast, "High Contrast")
+                        .on_hover_text("Use color-blind-safe selection palette.");
+
+                    let mut animate = self.style_override.row_animation_time.is_some();
+                    if ui.checkbox(&mut animate, "Animate Rows").changed() {
+                        self.style_override.row_animation_time = animate.then_some(0.3);
+                    }
+
+                    let mut flash = self.style_override.cell_flash_time.is_some();
+                    if ui.checkbox(&mut flash, "Flash Changed Cells").changed() {
+                        self.style_override.cell_flash_time = flash.then_some(1.0);
+                    }
+
+                    let mut minimap = self.style_override.minimap_width.is_some();
+                    if ui.checkbox(&mut minimap, "Minimap").changed() {
+                        self.style_override.minimap_width = minimap.then_some(18.0);
+                    }
+
+                    let mut frozen = self.style_override.frozen_columns > 0;
+                    if ui.checkbox(&mut frozen, "Freeze Name Column").changed() {
+                        self.style_override.frozen_columns = frozen as usize;
+                    }
+
+                    ui.checkbox(&mut self.style_override.soft_delete, "Soft Delete")
+                        .on_hover_text("If checked, deleted rows are kept in the trash.");
+
+                    ui.checkbox(
+                        &mut self.style_override.compress_undo_history,
+                        "Compress Undo History",
+                    )
+                    .on_hover_text("If checked, undo history stores rows encoded by the codec.");
+
+                    ui.checkbox(
+                        &mut self.style_override.new_row_placeholder,
+                        "New Row Placeholder",
+                    )
+                    .on_hover_text("If checked, clicking the last row appends a new row.");
+
+                    ui.checkbox(
+                        &mut self.style_override.sticky_horizontal_scrollbar,
+                        "Sticky Horizontal Scrollbar",
+                    );
+
+                    ui.checkbox(
+                        &mut self.style_override.detached_editing,
+                        "Detached Editing",
+                    )
+                    .on_hover_text("If checked, edit requests are logged instead of editing.");
+
+                    ui.checkbox(&mut self.style_override.status_bar, "Status Bar");
+                    ui.checkbox(&mut self.style_override.deferred_cut, "Deferred Cut");
+
+                    let mut manual_resort =
+                        self.style_override.resort_policy == ResortPolicy::Manual;
+                    if ui.checkbox(&mut manual_resort, "Manual Re-sort").changed() {
+                        self.style_override.resort_policy = match manual_resort {
+                            true => ResortPolicy::Manual,
+                            false => ResortPolicy::Auto,
+                        };
+                    }
+
+                    if ui.button("Fit Columns to Content").clicked() {
+                        self.fit_columns = true;
+                    }
+
+                    if ui.button("Import Sample Rows").clicked() {
+                        self.import = ImportPreview::parse(&mut self.viewer, IMPORT_SAMPLE);
+                    }
+
+                    if ui.button("Empty Trash").clicked() {
+                        let removed = self.table.empty_trash();
+                        info!("removed {} rows from trash", removed.len());
+                    }
+
+                    ui.checkbox(&mut self.loading, "Loading")
+                        .on_hover_text("Show the loading overlay over the table.");
+
+                    ui.horizontal(|ui| {
+                        let depth = self.table.undo_depth();
+                        if ui.button(format!("Undo ({depth})")).clicked() {
+                            self.table.undo(&mut self.viewer);
+                        }
+
+                        if ui.button("Redo").clicked() {
+                            self.table.redo(&mut self.viewer);
+                        }
+                    });
+
                     if ui.button("Shuffle Rows").clicked() {
                         fastrand::shuffle(&mut self.table);
                     }
+
+                    if ui.button("Selection: Age + 1").clicked() {
+                        self.table
+                            .for_each_selected_mut(&mut self.viewer, |row| row.1 += 1);
+                    }
+
+                    let age_sum: i32 = self
+                        .table
+                        .selected_cell_values(&mut self.viewer)
+                        .into_iter()
+                        .filter_map(|(.., value)| match value {
+                            Cell::Int(age) => Some(age),
+                            _ => None,
+                        })
+                        .sum();
+                    ui.label(format!("Selected Age Sum: {age_sum}"));
                 })
             })
         });
@@ -348,11 +705,43 @@ impl eframe::App for DemoApp {
                 });
             });
 
+        if let Some(preview) = &mut self.import {
+            let decision = egui::Window::new("Import Preview")
+                .default_size([480., 240.])
+                .show(ctx, |ui| preview.show(ui))
+                .and_then(|x| x.inner.flatten());
+
+            if let Some(confirmed) = decision {
+                let preview = self.import.take().unwrap();
+
+                if confirmed {
+                    let n = preview.commit(&mut self.table, &mut self.viewer);
+                    info!("imported {n} rows");
+                }
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.add(
-                egui_data_table::Renderer::new(&mut self.table, &mut self.viewer)
-                    .with_style(self.style_override),
-            )
+            let mut renderer = egui_data_table::Renderer::new(&mut self.table, &mut self.viewer)
+                .with_style(self.style_override)
+                .with_loading(self.loading, |ui| {
+                    ui.spinner();
+                    ui.label("Loading...");
+                });
+
+            if std::mem::take(&mut self.fit_columns) {
+                renderer = renderer.auto_fit_columns();
+            }
+
+            if self.style_override.frozen_columns > 0 {
+                egui::ScrollArea::horizontal().show(ui, |ui| ui.add(renderer));
+            } else {
+                ui.add(renderer);
+            }
+
+            if let Some((row, column)) = self.table.take_edit_request() {
+                info!("edit requested: row {row}, column {column}");
+            }
         });
     }
 }
@@ -0,0 +1,580 @@
+//! [`RowCodec`] for row structs implementing `serde` traits, which maps the columns to the fields
+//! of the struct.
+
+use std::{borrow::Cow, fmt::Display, marker::PhantomData};
+
+use serde::{
+    de::{self, DeserializeOwned, IntoDeserializer, Visitor},
+    forward_to_deserialize_any,
+    ser::{self, Impossible, Serialize},
+};
+
+use crate::viewer::{ClipboardFormat, DecodeErrorBehavior, RowCodec};
+
+/// Codec of row structs which serialize each field into plain text; numbers, booleans, strings,
+/// options and unit enum variants. Column `i` is mapped to the `i`-th field of the struct, unless
+/// remapped by [`Self::with_fields`].
+///
+/// ```ignore
+/// fn try_create_codec(&mut self, _: bool) -> Option<Box<dyn RowCodec<Row> + '_>> {
+///     Some(Box::new(SerdeRowCodec::new()))
+/// }
+/// ```
+pub struct SerdeRowCodec<R> {
+    fields: Option<Vec<Cow<'static, str>>>,
+    format: ClipboardFormat,
+    on_error: DecodeErrorBehavior,
+    _row: PhantomData<fn() -> R>,
+}
+
+impl<R> Default for SerdeRowCodec<R> {
+    fn default() -> Self {
+        Self {
+            fields: None,
+            format: ClipboardFormat::default(),
+            on_error: DecodeErrorBehavior::default(),
+            _row: PhantomData,
+        }
+    }
+}
+
+impl<R> SerdeRowCodec<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps column `i` to the field named `fields[i]`, instead of the declaration order.
+    pub fn with_fields(
+        mut self,
+        fields: impl IntoIterator<Item = impl Into<Cow<'static, str>>>,
+    ) -> Self {
+        self.fields = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_clipboard_format(mut self, format: ClipboardFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Behavior on the cells failed to be parsed into the field.
+    pub fn with_decode_error_behavior(mut self, on_error: DecodeErrorBehavior) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Position of the column within the serialized fields.
+    fn field_index(&self, fields: &[(&'static str, String)], column: usize) -> Option<usize> {
+        match &self.fields {
+            Some(names) => {
+                let name = names.get(column)?;
+                fields.iter().position(|(x, _)| x == name)
+            }
+            None => (column < fields.len()).then_some(column),
+        }
+    }
+}
+
+impl<R: Serialize + DeserializeOwned + Default> RowCodec<R> for SerdeRowCodec<R> {
+    fn clipboard_format(&self) -> ClipboardFormat {
+        self.format
+    }
+
+    fn create_empty_decoded_row(&mut self) -> R {
+        R::default()
+    }
+
+    fn encode_column(&mut self, src_row: &R, column: usize, dst: &mut String) {
+        let Ok(fields) = src_row.serialize(FieldsSerializer) else {
+            return;
+        };
+
+        if let Some(index) = self.field_index(&fields, column) {
+            dst.push_str(&fields[index].1);
+        }
+    }
+
+    fn decode_column(
+        &mut self,
+        src_data: &str,
+        column: usize,
+        dst_row: &mut R,
+    ) -> Result<(), DecodeErrorBehavior> {
+        // Other fields are kept by round-tripping the whole row.
+        let mut fields = dst_row
+            .serialize(FieldsSerializer)
+            .map_err(|_| self.on_error)?;
+        let index = self
+            .field_index(&fields, column)
+            .ok_or(DecodeErrorBehavior::SkipCell)?;
+
+        fields[index].1 = src_data.to_owned();
+        *dst_row = R::deserialize(FieldsDeserializer(fields)).map_err(|_| self.on_error)?;
+
+        Ok(())
+    }
+}
+
+/* ------------------------------------------- Errors ------------------------------------------- */
+
+/// Error of the row which can't be represented as plain text fields.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct SerdeCodecError(String);
+
+impl ser::Error for SerdeCodecError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl de::Error for SerdeCodecError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+fn unsupported<T>() -> Result<T, SerdeCodecError> {
+    Err(SerdeCodecError(
+        "only structs of plain fields are supported".into(),
+    ))
+}
+
+/* ----------------------------------------- Serializer ----------------------------------------- */
+
+/// Serializes a struct into its fields as text.
+struct FieldsSerializer;
+
+macro_rules! unsupported_ser {
+    ($($method:ident($($ty:ty),*);)*) => {$(
+        fn $method(self, $(_: $ty),*) -> Result<Self::Ok, Self::Error> {
+            unsupported()
+        }
+    )*};
+}
+
+impl ser::Serializer for FieldsSerializer {
+    type Ok = Vec<(&'static str, String)>;
+    type Error = SerdeCodecError;
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = SerializeFields;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(SerializeFields(Vec::with_capacity(len)))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    unsupported_ser! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        unsupported()
+    }
+}
+
+struct SerializeFields(Vec<(&'static str, String)>);
+
+impl ser::SerializeStruct for SerializeFields {
+    type Ok = Vec<(&'static str, String)>;
+    type Error = SerdeCodecError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.0.push((key, value.serialize(TextSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+/// Serializes a field value into text.
+struct TextSerializer;
+
+macro_rules! display_ser {
+    ($($method:ident($ty:ty);)*) => {$(
+        fn $method(self, value: $ty) -> Result<Self::Ok, Self::Error> {
+            Ok(value.to_string())
+        }
+    )*};
+}
+
+impl ser::Serializer for TextSerializer {
+    type Ok = String;
+    type Error = SerdeCodecError;
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    display_ser! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(String::new())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(String::new())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(String::new())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        unsupported()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        unsupported()
+    }
+}
+
+/* ---------------------------------------- Deserializer ---------------------------------------- */
+
+/// Deserializes a struct from its fields as text.
+struct FieldsDeserializer(Vec<(&'static str, String)>);
+
+impl<'de> de::Deserializer<'de> for FieldsDeserializer {
+    type Error = SerdeCodecError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let fields = self
+            .0
+            .into_iter()
+            .map(|(key, text)| (key, TextDeserializer(text)));
+
+        visitor.visit_map(de::value::MapDeserializer::new(fields))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Deserializes a field value from text.
+struct TextDeserializer(String);
+
+impl IntoDeserializer<'_, SerdeCodecError> for TextDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! parse_de {
+    ($($method:ident => $visit:ident;)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            visitor.$visit(self.0.trim().parse().map_err(de::Error::custom)?)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for TextDeserializer {
+    type Error = SerdeCodecError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.0)
+    }
+
+    parse_de! {
+        deserialize_bool => visit_bool;
+        deserialize_i8 => visit_i8;
+        deserialize_i16 => visit_i16;
+        deserialize_i32 => visit_i32;
+        deserialize_i64 => visit_i64;
+        deserialize_u8 => visit_u8;
+        deserialize_u16 => visit_u16;
+        deserialize_u32 => visit_u32;
+        deserialize_u64 => visit_u64;
+        deserialize_f32 => visit_f32;
+        deserialize_f64 => visit_f64;
+        deserialize_char => visit_char;
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.0.trim().into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::SerdeRowCodec;
+    use crate::viewer::{DecodeErrorBehavior, RowCodec};
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    enum Grade {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Row {
+        name: String,
+        age: i32,
+        score: Option<f32>,
+        grade: Grade,
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut codec = SerdeRowCodec::<Row>::new();
+        let mut row = codec.create_empty_decoded_row();
+
+        codec.decode_column("Tom", 0, &mut row).unwrap();
+        codec.decode_column(" 42", 1, &mut row).unwrap();
+        codec.decode_column("1.5", 2, &mut row).unwrap();
+        codec.decode_column("B", 3, &mut row).unwrap();
+
+        let expected = Row {
+            name: "Tom".into(),
+            age: 42,
+            score: Some(1.5),
+            grade: Grade::B,
+        };
+        assert_eq!(row, expected);
+
+        let mut buf = String::new();
+        codec.encode_column(&row, 2, &mut buf);
+        assert_eq!(buf, "1.5");
+
+        assert_eq!(
+            codec.decode_column("x", 1, &mut row),
+            Err(DecodeErrorBehavior::Abort)
+        );
+        codec.decode_column("", 2, &mut row).unwrap();
+        assert_eq!(row.score, None);
+    }
+
+    #[test]
+    fn named_fields() {
+        let mut codec = SerdeRowCodec::<Row>::new().with_fields(["grade", "name"]);
+        let mut row = Row::default();
+
+        codec.decode_column("Jerry", 1, &mut row).unwrap();
+        assert_eq!(row.name, "Jerry");
+
+        let mut buf = String::new();
+        codec.encode_column(&row, 0, &mut buf);
+        assert_eq!(buf, "A");
+    }
+}
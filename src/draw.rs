@@ -1,13 +1,21 @@
-use std::mem::{replace, take};
+use std::{
+    hash::{Hash, Hasher},
+    mem::{replace, take},
+};
 
 use egui::{
-    Align, Color32, Event, Layout, PointerButton, Rect, Response, RichText, Sense, Stroke, Widget,
+    Align, Color32, Event, Layout, PointerButton, Rect, Response, RichText, Sense, Stroke,
+    UiBuilder, Widget,
 };
 use egui_extras::Column;
 use tap::prelude::{Pipe, Tap};
 
 use crate::{
-    viewer::{EmptyRowCreateContext, RowViewer},
+    viewer::{
+        CellGeometry, CellStyle, ColumnSpec, EditorFocus, EmptyRowCreateContext, RowStatus,
+        RowViewer, SortDirection, VisibleCell,
+    },
+    widgets::Sparkline,
     DataTable, UiAction,
 };
 
@@ -15,14 +23,29 @@ use self::state::*;
 
 use format as f;
 
+mod delimited;
+mod export;
+mod import;
+mod json;
+mod lazy;
+mod pivot;
+mod series;
 pub(crate) mod state;
-mod tsv;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+
+#[cfg(feature = "xlsx")]
+pub(crate) use export::table_xlsx;
+pub use export::ImageExportOptions;
+pub(crate) use export::{pretty_table, render_table};
+pub use import::{ImportPreview, ImportStatus};
+pub use lazy::LazyRenderer;
 
 /* -------------------------------------------- Style ------------------------------------------- */
 
 /// Style configuration for the table.
 // TODO: Implement more style configurations.
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 #[non_exhaustive]
 pub struct Style {
     /// Background color override for selection. Default uses `visuals.selection.bg_fill`.
@@ -34,6 +57,10 @@ pub struct Style {
     /// Foreground color for cells that are going to be selected when mouse is dropped.
     pub fg_drag_selection: Option<egui::Color32>,
 
+    /// Outline color of cells reported as invalid by [`RowViewer::validate_cell`]. Default uses
+    /// `visuals.error_fg_color`.
+    pub fg_invalid_cell: Option<egui::Color32>,
+
     /* ·························································································· */
     /// Maximum number of undo history. This is applied when actual action is performed.
     pub max_undo_history: usize,
@@ -41,39 +68,515 @@ pub struct Style {
     /// If specify this as [`None`], the heterogeneous row height will be used.
     pub table_row_height: Option<f32>,
 
+    /// Mouse button which selects cells by click or drag, edits them by click, and sorts columns
+    /// by clicking the header. Default is [`egui::PointerButton::Primary`].
+    pub selection_button: egui::PointerButton,
+
+    /// Mouse button which opens context menus of cells and headers. Default is
+    /// [`egui::PointerButton::Secondary`]. Long press on touch screens opens them regardless.
+    pub context_menu_button: egui::PointerButton,
+
     /// When enabled, single click on a cell will start editing mode. Default is `false` where
     /// double action(click 1: select, click 2: edit) is required.
     pub single_click_edit_mode: bool,
+
+    /// Use a color-blind-safe selection palette with thicker focus outlines. Explicit color
+    /// overrides above still take precedence over this palette.
+    pub high_contrast: bool,
+
+    /// Duration in seconds of row insertion/removal animations; inserted rows fade in and the
+    /// gap of removed rows collapses. [`None`] disables the animation.
+    pub row_animation_time: Option<f32>,
+
+    /// Duration in seconds of the background flash on cells changed by edit, paste, undo or
+    /// redo. [`None`] disables the flash.
+    pub cell_flash_time: Option<f32>,
+
+    /// Background color of flashing cells. Default uses `visuals.warn_fg_color`.
+    pub bg_flash_cell: Option<egui::Color32>,
+
+    /// Background color of cells matching the search bar query. Default uses
+    /// `visuals.warn_fg_color`.
+    pub bg_search_match: Option<egui::Color32>,
+
+    /// Background of cells highlighted by [`UiAction::HighlightDuplicates`]. Default uses
+    /// `visuals.error_fg_color`.
+    pub bg_duplicate_cell: Option<egui::Color32>,
+
+    /// Row background of the lowest value under the heat coloring, which is toggled from the
+    /// header context menu of a sortable column. Default is a translucent blue.
+    pub bg_heat_low: Option<egui::Color32>,
+
+    /// Row background of the highest value under the heat coloring. Default is a translucent
+    /// red.
+    pub bg_heat_high: Option<egui::Color32>,
+
+    /// When enabled, moving the editor left/right within the same row, e.g. by `Tab`, keeps
+    /// editing the row without committing. The whole row is committed as a single undo step
+    /// when the edition ends.
+    pub row_edit_session: bool,
+
+    /// When enabled, moving the interactive cell left/right toward a hidden column, which is
+    /// adjacent in data order, reveals the column and moves onto it.
+    pub reveal_hidden_columns_on_move: bool,
+
+    /// Width of the overview strip placed right of the table, which marks modified rows, rows
+    /// matching the search, selected rows and [`RowViewer::minimap_row_marker`]. [`None`] hides
+    /// the strip.
+    pub minimap_width: Option<f32>,
+
+    /// Number of leading visible columns which stay pinned, along with the row header, to the
+    /// left edge of the visible area while the table is scrolled horizontally.
+    pub frozen_columns: usize,
+
+    /// Background color of frozen columns. Default uses `visuals.panel_fill`.
+    pub bg_frozen_column: Option<egui::Color32>,
+
+    /// When enabled, [`UiAction::DeleteRow`] moves rows into the trash instead of removing
+    /// them. Trashed rows are hidden but kept in the table; restore them with
+    /// [`UiAction::RestoreTrash`] or [`DataTable::restore_trashed`], and remove them for good
+    /// with [`DataTable::empty_trash`].
+    pub soft_delete: bool,
+
+    /// When enabled, rows recorded in the undo history are encoded with the viewer's codec
+    /// instead of cloned, and decoded back on undo/redo. Reduces memory of the history for big
+    /// rows, at the cost of encoding on every change. Requires [`RowViewer::try_create_codec`],
+    /// and the codec must encode rows losslessly.
+    pub compress_undo_history: bool,
+
+    /// Copy, cut and paste of cells. When disabled, the table keeps no clipboard, and leaves
+    /// the clipboard events and hotkeys to the surrounding app. Default is `true`.
+    pub enable_clipboard: bool,
+
+    /// Undo history of the table. When disabled, nothing is recorded into the history, and
+    /// the undo/redo hotkeys are left to the surrounding app. Default is `true`.
+    pub enable_undo: bool,
+
+    /// Render a placeholder row at the end of the table. Clicking its cell appends a new row
+    /// created with [`EmptyRowCreateContext::InsertNewLine`], then starts editing the cell.
+    pub new_row_placeholder: bool,
+
+    /// Order of sort directions which clicking a column header cycles through; can be
+    /// overridden per column by [`RowViewer::default_sort_direction`].
+    pub sort_cycle: SortCycle,
+
+    /// Always show the horizontal scroll bar, docked to the bottom of the available area rather
+    /// than below the last row.
+    pub sticky_horizontal_scrollbar: bool,
+
+    /// Instead of opening the cell editor, the table records the cell to edit, which is taken
+    /// by [`DataTable::take_edit_request`]; e.g. to edit the row in a side panel of the host,
+    /// then commit it through [`DataTable::apply_edit`].
+    pub detached_editing: bool,
+
+    /// What happens to the cell being edited when the user clicks outside the table.
+    pub edit_commit_policy: EditCommitPolicy,
+
+    /// How cut and deletion apply to the selection, of which some rows got hidden by the filter
+    /// changed after selecting them.
+    pub hidden_selection_policy: HiddenSelectionPolicy,
+
+    /// Show a status bar under the table, with the number of selected cells and rows, and the
+    /// aggregates given by [`RowViewer::selection_summary`].
+    pub status_bar: bool,
+
+    /// Cut leaves the source cells marked with the moving dashed border, instead of clearing
+    /// them right away; they're cleared once the paste lands. Escape cancels the cut.
+    pub deferred_cut: bool,
+
+    /// When the rows edited while sorted move into the sort order.
+    pub resort_policy: ResortPolicy,
+
+    /// Cells can be selected, copied and searched, but not edited; the actions which modify
+    /// rows are disabled, as well as the fill handle.
+    pub read_only: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            bg_selected_cell: None,
+            bg_selected_highlight_cell: None,
+            fg_drag_selection: None,
+            fg_invalid_cell: None,
+            max_undo_history: 0,
+            table_row_height: None,
+            selection_button: PointerButton::Primary,
+            context_menu_button: PointerButton::Secondary,
+            single_click_edit_mode: false,
+            high_contrast: false,
+            row_animation_time: None,
+            cell_flash_time: None,
+            bg_flash_cell: None,
+            bg_search_match: None,
+            bg_duplicate_cell: None,
+            bg_heat_low: None,
+            bg_heat_high: None,
+            row_edit_session: false,
+            reveal_hidden_columns_on_move: false,
+            minimap_width: None,
+            frozen_columns: 0,
+            bg_frozen_column: None,
+            soft_delete: false,
+            compress_undo_history: false,
+            enable_clipboard: true,
+            enable_undo: true,
+            new_row_placeholder: false,
+            sort_cycle: SortCycle::AscendingFirst,
+            sticky_horizontal_scrollbar: false,
+            detached_editing: false,
+            edit_commit_policy: Default::default(),
+            hidden_selection_policy: Default::default(),
+            status_bar: false,
+            deferred_cut: false,
+            resort_policy: Default::default(),
+            read_only: false,
+        }
+    }
+}
+
+impl Style {
+    /// Whether the action is allowed by [`Self::enable_clipboard`], [`Self::enable_undo`] and
+    /// [`Self::read_only`].
+    fn is_action_enabled(&self, action: UiAction) -> bool {
+        if self.read_only && action.is_modifying() {
+            return false;
+        }
+
+        match action {
+            UiAction::CopySelection
+            | UiAction::CutSelection
+            | UiAction::PasteInPlace
+            | UiAction::PasteInsert => self.enable_clipboard,
+            UiAction::Undo | UiAction::Redo => self.enable_undo,
+            _ => true,
+        }
+    }
+
+    /// Selection colors from Okabe-Ito palette, which stays distinguishable under the common
+    /// color-vision deficiencies.
+    const HC_SELECTION: Color32 = Color32::from_rgb(0, 114, 178);
+    const HC_HIGHLIGHT: Color32 = Color32::from_rgb(86, 180, 233);
+    const HC_DRAG: Color32 = Color32::from_rgb(230, 159, 0);
+
+    fn selection_colors(&self, visual: &egui::Visuals) -> SelectionColors {
+        if self.high_contrast {
+            SelectionColors {
+                selected: self
+                    .bg_selected_cell
+                    .unwrap_or(Self::HC_SELECTION.gamma_multiply(0.6)),
+                highlight: self
+                    .bg_selected_highlight_cell
+                    .unwrap_or(Self::HC_HIGHLIGHT),
+                drag: self.fg_drag_selection.unwrap_or(Self::HC_DRAG),
+                outline: visual.strong_text_color(),
+                outline_width: 2.,
+                drag_width: 3.,
+            }
+        } else {
+            SelectionColors {
+                selected: self
+                    .bg_selected_cell
+                    .unwrap_or(visual.selection.bg_fill.gamma_multiply(0.5)),
+                highlight: self
+                    .bg_selected_highlight_cell
+                    .unwrap_or(visual.selection.bg_fill),
+                drag: self.fg_drag_selection.unwrap_or(visual.selection.bg_fill),
+                outline: visual.warn_fg_color.gamma_multiply(0.5),
+                outline_width: 1.,
+                drag_width: 2.,
+            }
+        }
+    }
+}
+
+/// Resolved selection visuals for a single frame.
+struct SelectionColors {
+    selected: Color32,
+    highlight: Color32,
+    drag: Color32,
+
+    /// Outline of the interactive row.
+    outline: Color32,
+    outline_width: f32,
+    drag_width: f32,
+}
+
+/// Resolved cell visuals for a single frame, shared by the cells scrolled in place and the
+/// frozen or pinned cells rendered over them.
+struct CellColors<'a> {
+    sel: &'a SelectionColors,
+    flash: Option<&'a CellFlashFrame>,
+    bg_flash: Color32,
+    bg_search_match: Color32,
+    bg_duplicate: Color32,
+    bg_heat: (Color32, Color32),
+    invalid: Stroke,
+    text: Color32,
+    high_contrast: bool,
+}
+
+/// Position and selection state of a body cell.
+struct CellState {
+    row_id: RowIdx,
+    vis_row: VisRowPos,
+    vis_col: VisColumnPos,
+    col: ColumnIdx,
+    selected: bool,
+    cci_selected: bool,
+    interactive: bool,
+
+    /// Outline of the interactive row, unless it's being edited.
+    row_outline: bool,
+}
+
+impl CellState {
+    fn new<R>(s: &UiState<R>, row_id: RowIdx, vis_row: VisRowPos, vis_col: VisColumnPos) -> Self {
+        let interactive_row = s.is_interactive_row(vis_row);
+
+        Self {
+            row_id,
+            vis_row,
+            vis_col,
+            col: s.vis_cols()[vis_col.0],
+            selected: s.is_selected(vis_row, vis_col),
+            cci_selected: s.is_selected_cci(vis_row, vis_col),
+            interactive: interactive_row == Some(vis_col),
+            row_outline: interactive_row.is_some() && !s.is_editing(),
+        }
+    }
+}
+
+impl CellColors<'_> {
+    /// Paints the backgrounds of the cell filling `ui`, then `contents`, then the outlines.
+    fn show<R>(
+        &self,
+        ui: &mut egui::Ui,
+        s: &mut UiState<R>,
+        cell: &CellState,
+        bg: Option<Color32>,
+        contents: impl FnOnce(&mut egui::Ui, &mut UiState<R>),
+    ) {
+        let rect = ui.max_rect();
+        let painter = ui.painter().clone();
+        let no_rounding = egui::Rounding::ZERO;
+
+        if let Some(rank) = s.heat_rank(cell.row_id) {
+            let bg = self.bg_heat.0.lerp_to_gamma(self.bg_heat.1, rank);
+            painter.rect_filled(rect, no_rounding, bg);
+        }
+
+        if let Some(bg) = bg {
+            painter.rect_filled(rect, no_rounding, bg);
+        }
+
+        if let Some(flash) = self.flash {
+            let intensity = flash.intensity(cell.row_id, cell.col);
+            if intensity > 0. {
+                let bg = self.bg_flash.gamma_multiply(intensity * 0.5);
+                painter.rect_filled(rect, no_rounding, bg);
+            }
+        }
+
+        if s.is_duplicate_cell(cell.row_id, cell.col) {
+            painter.rect_filled(rect, no_rounding, self.bg_duplicate);
+        }
+
+        if s.is_search_match(cell.vis_row, cell.vis_col) {
+            painter.rect_filled(rect, no_rounding, self.bg_search_match);
+        }
+
+        let sel = self.sel;
+        if cell.cci_selected {
+            painter.rect_stroke(rect, no_rounding, Stroke::new(sel.drag_width, sel.drag));
+        }
+
+        if cell.interactive {
+            painter.rect_filled(rect.expand(2.), no_rounding, sel.highlight);
+
+            if self.high_contrast {
+                let stroke = Stroke::new(sel.outline_width, sel.outline);
+                painter.rect_stroke(rect, no_rounding, stroke);
+            }
+        } else if cell.selected {
+            painter.rect_filled(rect.expand(1.), no_rounding, sel.selected);
+        }
+
+        // Actual widget rendering happens within this line.
+        ui.style_mut()
+            .visuals
+            .widgets
+            .noninteractive
+            .fg_stroke
+            .color = self.text;
+
+        contents(ui, s);
+
+        if s.cell_error(cell.row_id, cell.col).is_some() {
+            painter.rect_stroke(rect.shrink(1.), no_rounding, self.invalid);
+        }
+
+        if cell.row_outline {
+            let st = Stroke::new(sel.outline_width, sel.outline);
+            painter.hline(rect.x_range(), rect.top(), st);
+            painter.hline(rect.x_range(), rect.bottom(), st);
+        }
+    }
+}
+
+/// Order of sort directions which clicking a column header cycles through.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortCycle {
+    /// Ascending, descending, then unsorted.
+    #[default]
+    AscendingFirst,
+
+    /// Descending, ascending, then unsorted; e.g. for timestamps, where the latest is usually
+    /// wanted on top.
+    DescendingFirst,
+}
+
+impl SortCycle {
+    fn first(self) -> SortDirection {
+        match self {
+            Self::AscendingFirst => SortDirection::Ascending,
+            Self::DescendingFirst => SortDirection::Descending,
+        }
+    }
+}
+
+/// What happens to the cell being edited when the user clicks outside the table; see
+/// [`Style::edit_commit_policy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EditCommitPolicy {
+    /// Commit the edit.
+    CommitOnFocusLoss,
+
+    /// Cancel the edit, restoring the previous value.
+    CancelOnFocusLoss,
+
+    /// Keep editing; the edit ends only by keyboard or by interacting with the table.
+    #[default]
+    IgnoreOutsideClicks,
+}
+
+/// How cut and deletion apply to the selection spanning the rows hidden by the filter; see
+/// [`Style::hidden_selection_policy`]. The hidden rows are never affected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HiddenSelectionPolicy {
+    /// Ask the user before applying to the visible rows of the selection.
+    #[default]
+    Confirm,
+
+    /// Apply to the visible rows of the selection without asking.
+    VisibleOnly,
+}
+
+/// When the rows edited while sorted move into the sort order; see [`Style::resort_policy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResortPolicy {
+    /// Shortly after the edition ends.
+    #[default]
+    Auto,
+
+    /// Edited rows stay put, while the header shows a chip to re-sort them on click.
+    Manual,
+}
+
+/// Whether the column is sorted ascending by the first click on its header.
+fn is_ascending_first<R>(
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    cycle: SortCycle,
+    col: ColumnIdx,
+) -> IsAscending {
+    let first = viewer.default_sort_direction(col.0);
+    IsAscending(first.unwrap_or(cycle.first()) == SortDirection::Ascending)
+}
+
+/* -------------------------------------------- Macro ------------------------------------------- */
+
+/// How to replay the recorded keyboard macro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroReplay {
+    /// Replay the macro given times in a row, continuing from where the last replay ended.
+    Times(usize),
+
+    /// Replay the macro once for each selected row, starting from the interactive column.
+    OverSelectedRows,
 }
 
 /* ------------------------------------------ Rendering ----------------------------------------- */
 
-pub struct Renderer<'a, R, V: RowViewer<R>> {
+pub struct Renderer<'a, R, V: RowViewer<R> + ?Sized> {
     table: &'a mut DataTable<R>,
-    viewer: &'a mut V,
+    viewer: ViewerSlot<'a, V>,
     state: Option<Box<UiState<R>>>,
     style: Style,
+
+    /// Contents of the loading overlay, if the table is busy.
+    loading: Option<LoadingContents<'a>>,
+
+    /// Filter given by [`Self::with_extra_filter`], with the hash of its key.
+    extra_filter: Option<(u64, ExtraFilter<R>)>,
+
+    /// Fit every visible column to its content on this frame; see [`Self::auto_fit_columns`].
+    auto_fit_columns: bool,
+}
+
+type LoadingContents<'a> = Box<dyn FnOnce(&mut egui::Ui) + 'a>;
+
+/// Viewer borrowed from the caller, or built by [`Renderer::with_columns`].
+enum ViewerSlot<'a, V: ?Sized> {
+    Borrowed(&'a mut V),
+    Owned(Box<V>),
+}
+
+impl<V: ?Sized> std::ops::Deref for ViewerSlot<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        match self {
+            Self::Borrowed(viewer) => viewer,
+            Self::Owned(viewer) => viewer,
+        }
+    }
+}
+
+impl<V: ?Sized> std::ops::DerefMut for ViewerSlot<'_, V> {
+    fn deref_mut(&mut self) -> &mut V {
+        match self {
+            Self::Borrowed(viewer) => viewer,
+            Self::Owned(viewer) => viewer,
+        }
+    }
 }
 
-impl<R, V: RowViewer<R>> egui::Widget for Renderer<'_, R, V> {
+impl<R, V: RowViewer<R> + ?Sized> egui::Widget for Renderer<'_, R, V> {
     fn ui(self, ui: &mut egui::Ui) -> Response {
         self.show(ui)
     }
 }
 
-impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
+impl<'a, R, V: RowViewer<R> + ?Sized> Renderer<'a, R, V> {
     pub fn new(table: &'a mut DataTable<R>, viewer: &'a mut V) -> Self {
+        Self::with_viewer(table, ViewerSlot::Borrowed(viewer))
+    }
+
+    fn with_viewer(table: &'a mut DataTable<R>, mut viewer: ViewerSlot<'a, V>) -> Self {
         if table.rows.is_empty() {
             table.push(viewer.new_empty_row_for(EmptyRowCreateContext::InsertNewLine));
         }
 
         Self {
             state: Some(table.ui.take().unwrap_or_default().tap_mut(|state| {
-                state.validate_identity(viewer);
+                state.validate_identity(&mut *viewer);
             })),
             table,
             viewer,
             style: Default::default(),
+            loading: None,
+            extra_filter: None,
+            auto_fit_columns: false,
         }
     }
 
@@ -97,160 +600,732 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
         self
     }
 
-    pub fn show(self, ui: &mut egui::Ui) -> Response {
-        egui::ScrollArea::horizontal()
-            .show(ui, |ui| self.impl_show(ui))
-            .inner
+    /// Hides the rows for which `filter` returns false, on top of [`RowViewer::model`]; e.g. for
+    /// transient toggles of the app which don't belong to the viewer. Rows are filtered again
+    /// whenever the hash of `key` changes, so `key` should capture every input of `filter`.
+    ///
+    /// The filter is applied only while it's given to the renderer on every frame.
+    pub fn with_extra_filter(
+        mut self,
+        key: impl Hash,
+        filter: impl Fn(&R) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        let hash = egui::ahash::AHasher::default().pipe(|mut hsh| {
+            key.hash(&mut hsh);
+            hsh.finish()
+        });
+        self.extra_filter = Some((hash, Box::new(filter)));
+        self
     }
 
-    fn impl_show(mut self, ui: &mut egui::Ui) -> Response {
-        let ctx = &ui.ctx().clone();
-        let ui_id = ui.id();
-        let style = ui.style().clone();
-        let painter = ui.painter().clone();
-        let visual = &style.visuals;
-        let viewer = &mut *self.viewer;
-        let s = self.state.as_mut().unwrap();
-        let mut resp_total = None::<Response>;
-        let mut resp_ret = None::<Response>;
-        let mut commands = Vec::<Command<R>>::new();
-        let ui_layer_id = ui.layer_id();
+    /// While `loading` is true, the table is dimmed and disabled, and `contents` are shown at
+    /// the center of it; e.g. a spinner while the host fetches data. Scroll position and
+    /// selections are kept.
+    pub fn with_loading(
+        mut self,
+        loading: bool,
+        contents: impl FnOnce(&mut egui::Ui) + 'a,
+    ) -> Self {
+        self.loading = loading.then(|| Box::new(contents) as _);
+        self
+    }
 
-        // NOTE: unlike RED and YELLOW which can be acquirable through 'error_bg_color' and
-        // 'warn_bg_color', there's no 'green' color which can be acquired from inherent theme.
-        // Following logic simply gets 'green' color from current background's brightness.
-        let green = if visual.window_fill.g() > 128 {
-            Color32::DARK_GREEN
-        } else {
-            Color32::GREEN
+    /// Fits the width of every visible column to its content on this frame, measured over the
+    /// header and the visible rows; e.g. once after loading new data. Like resizing, the fitted
+    /// widths are kept until the columns are resized again.
+    ///
+    /// Don't call this on every frame, as the columns won't be resizable by the user.
+    pub fn auto_fit_columns(mut self) -> Self {
+        self.auto_fit_columns = true;
+        self
+    }
+
+    pub fn show(mut self, ui: &mut egui::Ui) -> Response {
+        let Some(loading) = self.loading.take() else {
+            return self.impl_show_table(ui);
         };
 
-        let mut builder = egui_extras::TableBuilder::new(ui).column(Column::auto());
+        let inner = ui.add_enabled_ui(false, |ui| self.impl_show_table(ui));
+        let (resp, rect) = (inner.inner, inner.response.rect);
 
-        let iter_vis_cols_with_flag = s
-            .vis_cols()
-            .iter()
-            .enumerate()
-            .map(|(index, column)| (column, index + 1 == s.vis_cols().len()));
+        // Blocks pointer interactions with the table beneath.
+        ui.interact(rect, resp.id.with("loading"), Sense::click_and_drag());
+        ui.painter().rect_filled(
+            rect,
+            egui::Rounding::ZERO,
+            ui.visuals().extreme_bg_color.gamma_multiply(0.6),
+        );
 
-        for (column, flag) in iter_vis_cols_with_flag {
-            builder = builder.column(viewer.column_render_config(column.0, flag));
+        egui::Area::new(resp.id.with("loading-contents"))
+            .order(ui.layer_id().order)
+            .fixed_pos(rect.center())
+            .pivot(egui::Align2::CENTER_CENTER)
+            .show(ui.ctx(), loading);
+
+        resp
+    }
+
+    fn impl_show_table(&mut self, ui: &mut egui::Ui) -> Response {
+        if self.state.as_ref().is_some_and(|s| s.search.is_some()) {
+            self.impl_show_search_bar(ui);
         }
 
-        if replace(&mut s.cci_want_move_scroll, false) {
-            let interact_row = s.interactive_cell().0;
-            builder = builder.scroll_to_row(interact_row.0, None);
+        if !self.style.status_bar {
+            return self.impl_show_table_and_minimap(ui);
         }
 
-        builder
-            .columns(Column::auto(), s.num_columns() - s.vis_cols().len())
-            .drag_to_scroll(false) // Drag is used for selection;
-            .striped(true)
-            .max_scroll_height(f32::MAX)
-            .sense(Sense::click_and_drag().tap_mut(|s| s.focusable = true))
-            .header(20., |mut h| {
-                h.col(|_ui| {
-                    // TODO: Add `Configure Sorting` button
-                });
+        // Leave a line for the status bar below.
+        let avail = ui.available_rect_before_wrap();
+        let line = ui.spacing().interact_size.y + ui.spacing().item_spacing.y;
+        let table_rect = avail.with_max_y((avail.bottom() - line).max(avail.top()));
 
-                let has_any_hidden_col = s.vis_cols().len() != s.num_columns();
+        let resp = ui.allocate_new_ui(UiBuilder::new().max_rect(table_rect), |ui| {
+            self.impl_show_table_and_minimap(ui)
+        });
 
-                for (vis_col, &col) in s.vis_cols().iter().enumerate() {
-                    let vis_col = VisColumnPos(vis_col);
-                    let mut painter = None;
-                    let (col_rect, resp) = h.col(|ui| {
-                        ui.horizontal_centered(|ui| {
-                            if let Some(pos) = s.sort().iter().position(|(c, ..)| c == &col) {
-                                let is_asc = s.sort()[pos].1 .0 as usize;
+        self.impl_show_status_bar(ui);
+        resp.inner
+    }
 
-                                ui.colored_label(
-                                    [green, Color32::RED][is_asc],
-                                    RichText::new(format!("{}{}", ["↘", "↗"][is_asc], pos + 1,))
-                                        .monospace(),
-                                );
-                            } else {
-                                ui.monospace(" ");
-                            }
+    fn impl_show_table_and_minimap(&mut self, ui: &mut egui::Ui) -> Response {
+        let Some(minimap_width) = self.style.minimap_width else {
+            return self.impl_show_scroll_area(ui);
+        };
 
-                            egui::Label::new(viewer.column_name(col.0))
-                                .selectable(false)
-                                .ui(ui);
-                        });
+        let avail = ui.available_rect_before_wrap();
+        let (table_rect, minimap_rect) = avail.split_left_right_at_x(avail.right() - minimap_width);
 
-                        painter = Some(ui.painter().clone());
-                    });
+        let resp = ui.allocate_new_ui(UiBuilder::new().max_rect(table_rect), |ui| {
+            self.impl_show_scroll_area(ui)
+        });
 
-                    // Set drag payload for column reordering.
-                    resp.dnd_set_drag_payload(vis_col);
+        let minimap_rect = minimap_rect.with_max_y(resp.response.rect.bottom());
+        self.impl_show_minimap(ui, minimap_rect);
 
-                    if resp.dragged() {
-                        egui::popup::show_tooltip_text(
-                            ctx,
-                            ui_layer_id,
-                            "_EGUI_DATATABLE__COLUMN_MOVE__".into(),
-                            viewer.column_name(col.0),
-                        );
-                    }
+        resp.inner
+    }
 
-                    if resp.hovered() && viewer.is_sortable_column(col.0) {
-                        if let Some(p) = &painter {
-                            p.rect_filled(
-                                col_rect,
-                                egui::Rounding::ZERO,
-                                visual.selection.bg_fill.gamma_multiply(0.2),
-                            );
-                        }
-                    }
+    /// Number of selected cells and rows, followed by the aggregates of the viewer.
+    fn impl_show_status_bar(&mut self, ui: &mut egui::Ui) {
+        let s = self.state.as_mut().unwrap();
+        let status = s.selection_status(self.table, &mut *self.viewer);
 
-                    if viewer.is_sortable_column(col.0) && resp.clicked_by(PointerButton::Primary) {
-                        let mut sort = s.sort().to_owned();
-                        match sort.iter_mut().find(|(c, ..)| c == &col) {
-                            Some((_, asc)) => match asc.0 {
-                                true => asc.0 = false,
-                                false => sort.retain(|(c, ..)| c != &col),
-                            },
-                            None => {
-                                sort.push((col, IsAscending(true)));
-                            }
-                        }
+        ui.horizontal(|ui| {
+            ui.label(f!("{} cells", status.cells));
+            ui.separator();
+            ui.label(f!("{} rows", status.rows));
 
-                        commands.push(Command::SetColumnSort(sort));
-                    }
+            for (label, value) in &status.summary {
+                ui.separator();
+                ui.label(f!("{label}: {value}"));
+            }
+        });
+    }
 
-                    if resp.dnd_hover_payload::<VisColumnPos>().is_some() {
-                        if let Some(p) = &painter {
-                            p.rect_filled(
-                                col_rect,
-                                egui::Rounding::ZERO,
-                                visual.selection.bg_fill.gamma_multiply(0.5),
-                            );
-                        }
-                    }
+    /// Horizontal scroll area of the table, of which offset is kept in the state.
+    fn impl_show_scroll_area(&mut self, ui: &mut egui::Ui) -> Response {
+        let s = self.state.as_mut().unwrap();
+        let mut area = egui::ScrollArea::horizontal();
 
-                    if let Some(payload) = resp.dnd_release_payload::<VisColumnPos>() {
-                        commands.push(Command::CcReorderColumn {
-                            from: *payload,
-                            to: vis_col
-                                .0
-                                .pipe(|v| v + (payload.0 < v) as usize)
-                                .pipe(VisColumnPos),
-                        })
-                    }
+        if let Some(offset) = s.cci_want_scroll_x.take() {
+            area = area.horizontal_scroll_offset(offset);
+        }
 
-                    resp.context_menu(|ui| {
-                        if ui.button("Hide").clicked() {
-                            commands.push(Command::CcHideColumn(col));
-                            ui.close_menu();
-                        }
+        if self.style.sticky_horizontal_scrollbar {
+            // Fill the available height, so that the scroll bar stays at the bottom.
+            area = area
+                .auto_shrink([true, false])
+                .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible);
+        }
 
-                        if !s.sort().is_empty() && ui.button("Clear Sort").clicked() {
-                            commands.push(Command::SetColumnSort(Vec::new()));
-                            ui.close_menu();
-                        }
+        let output = area.show(ui, |ui| self.impl_show_with_summary(ui));
+        self.state.as_mut().unwrap().cci_scroll_x = output.state.offset.x;
+        output.inner
+    }
 
-                        if has_any_hidden_col {
-                            ui.separator();
-                            ui.label("Hidden");
+    fn impl_show_with_summary(&mut self, ui: &mut egui::Ui) -> Response {
+        if !self.viewer.has_column_summary() {
+            return self.impl_show(ui);
+        }
+
+        // Leave a line for the summary row below, which scrolls along with the columns.
+        let avail = ui.available_rect_before_wrap();
+        let line = ui.spacing().interact_size.y + ui.spacing().item_spacing.y;
+        let table_rect = avail.with_max_y((avail.bottom() - line).max(avail.top()));
+
+        let resp = ui.allocate_new_ui(UiBuilder::new().max_rect(table_rect), |ui| {
+            self.impl_show(ui)
+        });
+
+        self.impl_show_summary_row(ui);
+        resp.inner
+    }
+
+    /// Summaries of the visible columns over the filtered rows, aligned to the column headers.
+    fn impl_show_summary_row(&mut self, ui: &mut egui::Ui) {
+        let s = self.state.as_mut().unwrap();
+        let viewer = &mut *self.viewer;
+        let rows = &self.table.rows;
+
+        let Some(right) = s.cci_column_spans.iter().map(|x| x.1.max).reduce(f32::max) else {
+            return;
+        };
+
+        let left = ui.cursor().left();
+        let height = ui.spacing().interact_size.y;
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(right - left, height), Sense::hover());
+
+        let visual = ui.visuals();
+        ui.painter()
+            .rect_filled(rect, egui::Rounding::ZERO, visual.faint_bg_color);
+        ui.painter().hline(
+            rect.x_range(),
+            rect.top(),
+            visual.widgets.noninteractive.bg_stroke,
+        );
+
+        for &(col, span) in &s.cci_column_spans {
+            let cell_rect = Rect::from_x_y_ranges(span, rect.y_range());
+            let mut cell = ui.new_child(
+                UiBuilder::new()
+                    .max_rect(cell_rect)
+                    .layout(Layout::left_to_right(Align::Center)),
+            );
+            cell.set_clip_rect(cell_rect.intersect(ui.clip_rect()));
+
+            let mut iter = s.visible_rows().map(|row| &rows[row.0]);
+            viewer.show_column_summary(&mut cell, col.0, &mut iter);
+        }
+    }
+
+    fn impl_show_search_bar(&mut self, ui: &mut egui::Ui) {
+        let viewer = &mut *self.viewer;
+        let table = &mut *self.table;
+        let s = self.state.as_mut().unwrap();
+        let mut commands = Vec::new();
+        let mut close = false;
+
+        ui.horizontal(|ui| {
+            let search = s.search.as_mut().unwrap();
+            let focus_query = replace(&mut search.focus_query, false);
+            let resp = ui.add(
+                egui::TextEdit::singleline(&mut search.query)
+                    .hint_text("Find")
+                    .desired_width(200.),
+            );
+
+            if focus_query {
+                resp.request_focus();
+            }
+
+            if resp.changed() {
+                // Matches are recomputed with the table; count should follow on the next frame.
+                search.invalidate();
+                ui.ctx().request_repaint();
+            }
+
+            let (mut next, mut prev) = (false, false);
+            if resp.lost_focus() {
+                ui.input(|i| {
+                    if i.key_pressed(egui::Key::Escape) {
+                        close = true;
+                    } else if i.key_pressed(egui::Key::Enter) {
+                        prev = i.modifiers.shift;
+                        next = !prev;
+                    }
+                });
+
+                // Keep typing after navigation with enter key.
+                if next || prev {
+                    resp.request_focus();
+                }
+            }
+
+            next |= ui.button("⏷").on_hover_text("Next Match").clicked();
+            prev |= ui.button("⏶").on_hover_text("Previous Match").clicked();
+
+            let n_matches = search.num_matches();
+            let replace_mode = &mut search.replace_mode;
+            *replace_mode &= !self.style.read_only;
+
+            if !self.style.read_only {
+                ui.toggle_value(replace_mode, "Replace");
+            }
+
+            if let Some(current) = s.current_search_match() {
+                ui.label(f!("{}/{}", current + 1, n_matches));
+            } else {
+                ui.label(f!("{n_matches} matches"));
+            }
+
+            close |= ui.button("🗙").on_hover_text("Close").clicked();
+
+            if next || prev {
+                commands.extend(s.search_move_commands(next));
+            }
+        });
+
+        if s.search.as_ref().is_some_and(|x| x.replace_mode) {
+            ui.horizontal(|ui| {
+                let search = s.search.as_mut().unwrap();
+                ui.add(
+                    egui::TextEdit::singleline(&mut search.replacement)
+                        .hint_text("Replace with")
+                        .desired_width(200.),
+                );
+
+                if ui.button("Replace").clicked() {
+                    commands.extend(s.search_replace_commands(table, viewer, false));
+                }
+
+                if ui.button("Replace All").clicked() {
+                    commands.extend(s.search_replace_commands(table, viewer, true));
+                }
+            });
+        }
+
+        if close {
+            s.search = None;
+        }
+
+        for cmd in commands {
+            s.push_new_command(table, viewer, cmd, self.style.max_undo_history);
+        }
+    }
+
+    fn impl_show_minimap(&mut self, ui: &mut egui::Ui, rect: Rect) {
+        let viewer = &mut *self.viewer;
+        let table = &mut *self.table;
+        let s = self.state.as_mut().unwrap();
+        let resp = ui.allocate_rect(rect, Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        let visual = &ui.style().visuals;
+
+        painter.rect_filled(rect, egui::Rounding::ZERO, visual.extreme_bg_color);
+
+        let n_rows = s.cc_rows.len();
+        if n_rows == 0 {
+            return;
+        }
+
+        let row_to_y = |row: usize| rect.top() + rect.height() * row as f32 / n_rows as f32;
+
+        // Currently visible range.
+        painter.rect_filled(
+            Rect::from_x_y_ranges(
+                rect.x_range(),
+                row_to_y(s.cci_first_visible_row.0)
+                    ..=row_to_y(s.cci_first_visible_row.0 + s.cci_page_row_count)
+                        .max(row_to_y(s.cci_first_visible_row.0) + 2.),
+            ),
+            egui::Rounding::ZERO,
+            visual.widgets.inactive.bg_fill,
+        );
+
+        // Markers are bucketed per pixel, to deal with huge number of rows. Each lane is
+        // (modified, search match, viewer defined, selected) from left to right.
+        let n_buckets = (rect.height().ceil() as usize).max(1);
+        let lane_width = rect.width() / 4.;
+        let search_color = self
+            .style
+            .bg_search_match
+            .unwrap_or(visual.warn_fg_color.gamma_multiply(0.6));
+        let bucket_height = rect.height() / n_buckets as f32;
+
+        for (bucket, markers) in s
+            .minimap_markers(table, viewer, n_buckets)
+            .iter()
+            .enumerate()
+        {
+            let y = rect.top() + bucket_height * bucket as f32;
+            let lanes = [
+                markers.modified.then_some(visual.warn_fg_color),
+                markers.search_match.then_some(search_color),
+                markers.marker,
+                markers.selected.then_some(visual.selection.bg_fill),
+            ];
+
+            for (lane_idx, color) in lanes.into_iter().enumerate() {
+                let Some(color) = color else { continue };
+                let x = rect.left() + lane_width * lane_idx as f32;
+
+                painter.rect_filled(
+                    Rect::from_min_size(
+                        egui::pos2(x, y),
+                        egui::vec2(lane_width, bucket_height.max(1.)),
+                    ),
+                    egui::Rounding::ZERO,
+                    color,
+                );
+            }
+        }
+
+        // Navigate to the row under the pointer.
+        if let Some(pos) = resp
+            .interact_pointer_pos()
+            .filter(|_| resp.is_pointer_button_down_on())
+        {
+            let row = ((pos.y - rect.top()) / rect.height() * n_rows as f32) as usize;
+            let row = VisRowPos(row.min(n_rows - 1));
+            let (_, ic_c) = s.interactive_cell();
+            let idx = row.linear_index(s.vis_cols().len(), ic_c);
+
+            s.cci_want_move_scroll = true;
+            s.push_new_command(
+                table,
+                viewer,
+                Command::CcSetSelection(vec![VisSelection(idx, idx)]),
+                self.style.max_undo_history,
+            );
+        }
+    }
+
+    fn impl_show(&mut self, ui: &mut egui::Ui) -> Response {
+        let ctx = &ui.ctx().clone();
+        let ui_id = ui.id();
+        let style = ui.style().clone();
+        let painter = ui.painter().clone();
+        let visual = &style.visuals;
+        let viewer = &mut *self.viewer;
+        let s = self.state.as_mut().unwrap();
+        let mut resp_total = None::<Response>;
+        let mut resp_ret = None::<Response>;
+        let mut commands = Vec::<Command<R>>::new();
+        let mut actions = Vec::<UiAction>::new();
+        let ui_layer_id = ui.layer_id();
+
+        // NOTE: unlike RED and YELLOW which can be acquirable through 'error_bg_color' and
+        // 'warn_bg_color', there's no 'green' color which can be acquired from inherent theme.
+        // Following logic simply gets 'green' color from current background's brightness.
+        let green = if visual.window_fill.g() > 128 {
+            Color32::DARK_GREEN
+        } else {
+            Color32::GREEN
+        };
+
+        // Green/red pair is hard to distinguish under color-vision deficiency.
+        let (sort_desc_color, sort_asc_color) = if self.style.high_contrast {
+            (Style::HC_SELECTION, Style::HC_DRAG)
+        } else {
+            (green, Color32::RED)
+        };
+
+        let has_filter_row = viewer.has_column_filters();
+        let header_height = if has_filter_row {
+            20. + ui.spacing().interact_size.y + ui.spacing().item_spacing.y
+        } else {
+            20.
+        };
+
+        let mut builder = egui_extras::TableBuilder::new(ui).column(Column::auto());
+        let fit_columns = take(&mut s.cci_fit_columns);
+
+        let iter_vis_cols_with_flag = s
+            .vis_cols()
+            .iter()
+            .enumerate()
+            .map(|(index, column)| (column, index + 1 == s.vis_cols().len()));
+
+        for (column, flag) in iter_vis_cols_with_flag {
+            let fit = self.auto_fit_columns || fit_columns.contains(column);
+            builder = builder.column(
+                viewer
+                    .column_render_config(column.0, flag)
+                    .auto_size_this_frame(fit),
+            );
+        }
+
+        if replace(&mut s.cci_want_move_scroll, false) {
+            let interact_row = s.selection_extent().0;
+            builder = builder.scroll_to_row(interact_row.0, None);
+        }
+
+        builder
+            .columns(Column::auto(), s.num_columns() - s.vis_cols().len())
+            .drag_to_scroll(false) // Drag is used for selection, by mouse and touch alike;
+            .striped(true)
+            .max_scroll_height(f32::MAX)
+            .sense(Sense::click_and_drag().tap_mut(|s| s.focusable = true))
+            .header(header_height, |mut h| {
+                let has_row_label = viewer.row_label_column().is_some();
+                let has_frozen = self.style.frozen_columns > 0 || has_row_label;
+                let bg_frozen = self.style.bg_frozen_column.unwrap_or(visual.panel_fill);
+
+                // Frozen titles stick to the left edge, as the body does. They're rendered after
+                // the scrolled titles, over them, taking the pointer interaction in their place.
+                let pinned_header_ui = |rect: Rect, clip: Rect, layer, col: Option<ColumnIdx>| {
+                    let id = ui_id.with("frozen-header").with(col);
+                    let mut ui = egui::Ui::new(
+                        ctx.clone(),
+                        id,
+                        egui::UiBuilder::new()
+                            .layer_id(layer)
+                            .max_rect(rect)
+                            .layout(Layout::left_to_right(Align::Center)),
+                    );
+                    ui.set_clip_rect(rect.intersect(clip));
+
+                    // The `Ui` keeps `id` to itself, with a hover sense.
+                    let resp = ui.interact(rect, id.with("hit"), Sense::click_and_drag());
+                    ui.painter()
+                        .rect_filled(rect, egui::Rounding::ZERO, bg_frozen);
+                    (ui, resp)
+                };
+
+                let show_corner =
+                    |ui: &mut egui::Ui,
+                     viewer: &mut V,
+                     s: &mut UiState<R>,
+                     commands: &mut Vec<Command<R>>| {
+                        let sortable_cols = (0..s.num_columns())
+                            .filter(|x| viewer.is_sortable_column(*x))
+                            .map(ColumnIdx)
+                            .collect::<Vec<_>>();
+
+                        if !sortable_cols.is_empty() {
+                            ui.menu_button("⇅", |ui| {
+                                if let Some(sort) = show_sort_config(
+                                    ui,
+                                    viewer,
+                                    &self.style,
+                                    s.sort(),
+                                    &sortable_cols,
+                                ) {
+                                    commands.push(Command::SetColumnSort(sort));
+                                }
+                            })
+                            .response
+                            .on_hover_text("Configure Sorting");
+                        }
+
+                        ui.menu_button("☰", |ui| {
+                            if let Some(cols) =
+                                show_column_picker(ui, viewer, s.vis_cols(), s.num_columns())
+                            {
+                                commands.push(Command::SetVisibleColumns(cols));
+                            }
+                        })
+                        .response
+                        .on_hover_text("Columns…");
+
+                        if s.is_recording_macro() {
+                            ui.colored_label(visual.error_fg_color, "⏺")
+                                .on_hover_text("Recording macro");
+                        }
+
+                        if s.is_order_stale()
+                            && ui
+                                .small_button("⟳")
+                                .on_hover_text("Order out of date – click to re-sort")
+                                .clicked()
+                        {
+                            s.resort_stale_rows_now();
+                        }
+                    };
+
+                let show_title =
+                    |ui: &mut egui::Ui, viewer: &mut V, s: &UiState<R>, col: ColumnIdx| {
+                        let mut filter_changed = false;
+                        let column_name = viewer.column_name(col.0);
+                        let title = |ui: &mut egui::Ui| {
+                            if let Some(pos) = s.sort().iter().position(|(c, ..)| c == &col) {
+                                let is_asc = s.sort()[pos].1 .0 as usize;
+
+                                ui.colored_label(
+                                    [sort_desc_color, sort_asc_color][is_asc],
+                                    RichText::new(format!("{}{}", ["↘", "↗"][is_asc], pos + 1,))
+                                        .monospace(),
+                                );
+                            } else {
+                                ui.monospace(" ");
+                            }
+
+                            egui::Label::new(column_name).selectable(false).ui(ui);
+                        };
+
+                        if has_filter_row {
+                            ui.vertical(|ui| {
+                                ui.horizontal(title);
+
+                                if let Some(resp) = viewer.show_column_filter(ui, col.0) {
+                                    filter_changed = resp.changed();
+                                }
+                            });
+                        } else {
+                            ui.horizontal_centered(title);
+                        }
+
+                        filter_changed
+                    };
+
+                let mut pinned = None;
+                h.col(|ui| {
+                    let (rect, clip) = (ui.max_rect(), ui.clip_rect());
+                    let dx = (clip.left() - rect.left()).max(0.);
+
+                    // Pinned contents still lay out here, unseen, to keep the column widths.
+                    if has_frozen && dx > 0. {
+                        pinned = Some((dx, rect, clip, ui.layer_id()));
+                        ui.set_invisible();
+                    }
+
+                    show_corner(ui, viewer, s, &mut commands);
+                });
+
+                let has_any_hidden_col = s.vis_cols().len() != s.num_columns();
+                let pin_dx = pinned.map_or(0., |x| x.0);
+                let mut column_spans = Vec::new();
+                let mut filter_changed = false;
+                let mut heat_toggle = None;
+                let mut pivot_group = None;
+                let mut fit_column = None;
+                let mut titles = Vec::new();
+
+                for (vis_col, &col) in s.vis_cols().iter().enumerate() {
+                    let vis_col = VisColumnPos(vis_col);
+                    let is_pinned = pin_dx > 0. && vis_col.0 < self.style.frozen_columns;
+                    let mut painter = None;
+                    let (col_rect, resp) = h.col(|ui| {
+                        if is_pinned {
+                            ui.set_invisible();
+                        }
+
+                        filter_changed |= show_title(ui, viewer, s, col);
+
+                        painter = Some(ui.painter().clone());
+                    });
+
+                    column_spans.push((col, col_rect.x_range()));
+                    titles.push((vis_col, col, col_rect, resp, painter));
+                }
+
+                if let Some((dx, corner_rect, clip, layer)) = pinned {
+                    let rect = corner_rect.translate(egui::vec2(dx, 0.));
+                    let (mut ui, _) = pinned_header_ui(rect, clip, layer, None);
+                    show_corner(&mut ui, viewer, s, &mut commands);
+
+                    for (_, col, col_rect, resp, painter) in
+                        titles.iter_mut().take(self.style.frozen_columns)
+                    {
+                        *col_rect = col_rect.translate(egui::vec2(dx, 0.));
+                        let (mut ui, pinned_resp) =
+                            pinned_header_ui(*col_rect, clip, layer, Some(*col));
+
+                        filter_changed |= show_title(&mut ui, viewer, s, *col);
+                        *resp = pinned_resp;
+                        *painter = Some(ui.painter().clone());
+                    }
+                }
+
+                for (vis_col, col, col_rect, resp, painter) in titles {
+                    // Set drag payload for column reordering.
+                    let is_reorderable = viewer.is_reorderable_column(col.0);
+                    if is_reorderable {
+                        resp.dnd_set_drag_payload(vis_col);
+                    }
+
+                    // Sortable headers are clicked rather than grabbed.
+                    if resp.hovered() && is_reorderable && !viewer.is_sortable_column(col.0) {
+                        ctx.set_cursor_icon(egui::CursorIcon::Grab);
+                    }
+
+                    if resp.dragged() && is_reorderable {
+                        egui::popup::show_tooltip_text(
+                            ctx,
+                            ui_layer_id,
+                            "_EGUI_DATATABLE__COLUMN_MOVE__".into(),
+                            viewer.column_name(col.0),
+                        );
+                    }
+
+                    if resp.hovered() && viewer.is_sortable_column(col.0) {
+                        if let Some(p) = &painter {
+                            p.rect_filled(
+                                col_rect,
+                                egui::Rounding::ZERO,
+                                visual.selection.bg_fill.gamma_multiply(0.2),
+                            );
+                        }
+                    }
+
+                    if viewer.is_sortable_column(col.0)
+                        && resp.clicked_by(self.style.selection_button)
+                    {
+                        let first = is_ascending_first(viewer, self.style.sort_cycle, col);
+                        let mut sort = s.sort().to_owned();
+                        match sort.iter_mut().find(|(c, ..)| c == &col) {
+                            Some((_, asc)) if *asc == first => asc.0 = !first.0,
+                            Some(_) => sort.retain(|(c, ..)| c != &col),
+                            None => {
+                                sort.push((col, first));
+                            }
+                        }
+
+                        commands.push(Command::SetColumnSort(sort));
+                    }
+
+                    if resp.dnd_hover_payload::<VisColumnPos>().is_some() {
+                        if let Some(p) = &painter {
+                            p.rect_filled(
+                                col_rect,
+                                egui::Rounding::ZERO,
+                                visual.selection.bg_fill.gamma_multiply(0.5),
+                            );
+                        }
+                    }
+
+                    if let Some(payload) = resp.dnd_release_payload::<VisColumnPos>() {
+                        commands.push(Command::CcReorderColumn {
+                            from: *payload,
+                            to: vis_col
+                                .0
+                                .pipe(|v| v + (payload.0 < v) as usize)
+                                .pipe(VisColumnPos),
+                        })
+                    }
+
+                    context_menu_by(&resp, self.style.context_menu_button, |ui| {
+                        if viewer.is_hidable_column(col.0) && ui.button("Hide").clicked() {
+                            commands.push(Command::CcHideColumn(col));
+                            ui.close_menu();
+                        }
+
+                        if !s.sort().is_empty() && ui.button("Clear Sort").clicked() {
+                            commands.push(Command::SetColumnSort(Vec::new()));
+                            ui.close_menu();
+                        }
+
+                        if ui.button("Fit Width to Content").clicked() {
+                            fit_column = Some(col);
+                            ui.close_menu();
+                        }
+
+                        if viewer.is_sortable_column(col.0) {
+                            let is_heat = s.heat_column() == Some(col);
+                            let label = ["Heat Coloring", "Clear Heat Coloring"][is_heat as usize];
+
+                            if ui.button(label).clicked() {
+                                heat_toggle = Some((!is_heat).then_some(col));
+                                ui.close_menu();
+                            }
+                        }
+
+                        let is_dup = s.duplicates_column() == Some(col);
+                        let label = ["Highlight Duplicates", "Clear Duplicates"][is_dup as usize];
+
+                        if ui.button(label).clicked() {
+                            actions.push(UiAction::HighlightDuplicates(col.0));
+                            ui.close_menu();
+                        }
+
+                        if ui.button("Group Summary…").clicked() {
+                            pivot_group = Some(col);
+                            ui.close_menu();
+                        }
+
+                        if has_any_hidden_col {
+                            ui.separator();
+                            ui.label("Hidden");
 
                             for col in (0..s.num_columns()).map(ColumnIdx) {
                                 if !s.vis_cols().contains(&col)
@@ -264,9 +1339,32 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
                                 }
                             }
                         }
+
+                        viewer.extend_header_context_menu(ui, col.0, &mut actions);
                     });
                 }
 
+                // Filter widgets may not be reflected in the viewer's filter hash.
+                if filter_changed {
+                    s.cc_mark_dirty();
+                }
+
+                if let Some(column) = heat_toggle {
+                    s.set_heat_column(column);
+                }
+
+                if let Some(group) = pivot_group {
+                    s.open_pivot(group);
+                }
+
+                s.cci_column_spans = column_spans;
+
+                // Columns are measured while being built, on the next frame.
+                if let Some(column) = fit_column {
+                    s.cci_fit_columns.push(column);
+                    ctx.request_repaint();
+                }
+
                 // Account for header response to calculate total response.
                 resp_total = Some(h.response());
             })
@@ -274,9 +1372,9 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
                 table.ui_mut().separator();
             })
             .body(|body: egui_extras::TableBody<'_>| {
-                resp_ret = Some(
-                    self.impl_show_body(body, painter, commands, ctx, &style, ui_id, resp_total),
-                );
+                resp_ret = Some(self.impl_show_body(
+                    body, painter, commands, actions, ctx, &style, ui_id, resp_total,
+                ));
             });
 
         resp_ret.unwrap_or_else(|| ui.label("??"))
@@ -285,9 +1383,10 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
     #[allow(clippy::too_many_arguments)]
     fn impl_show_body(
         &mut self,
-        body: egui_extras::TableBody<'_>,
+        mut body: egui_extras::TableBody<'_>,
         mut _painter: egui::Painter,
         mut commands: Vec<Command<R>>,
+        mut actions: Vec<UiAction>,
         ctx: &egui::Context,
         style: &egui::Style,
         ui_id: egui::Id,
@@ -299,20 +1398,28 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
         let visual = &style.visuals;
         let visible_cols = s.vis_cols().clone();
         let no_rounding = egui::Rounding::ZERO;
+        let sel_colors = self.style.selection_colors(visual);
 
-        let mut actions = Vec::<UiAction>::new();
         let mut edit_started = false;
-        let hotkeys = viewer.hotkeys(&s.ui_action_context());
+        let hotkeys = viewer
+            .hotkeys(&s.ui_action_context())
+            .tap_mut(|x| x.retain(|(_, action)| self.style.is_action_enabled(*action)));
+        let ui_enabled = body.ui_mut().is_enabled();
 
         // Preemptively consume all hotkeys.
         'detect_hotkey: {
             // Detect hotkey inputs only when the table has focus. While editing, let the
-            // editor consume input.
-            if !s.cci_has_focus {
+            // editor consume input. Pending paste confirmation takes the keys as well.
+            if !s.cci_has_focus
+                || !ui_enabled
+                || s.pending_paste().is_some()
+                || s.pending_all_data().is_some()
+                || s.pending_hidden_selection().is_some()
+            {
                 break 'detect_hotkey;
             }
 
-            if !s.is_editing() {
+            if !s.is_editing() && self.style.enable_clipboard {
                 ctx.input_mut(|i| {
                     i.events.retain(|x| {
                         match x {
@@ -360,35 +1467,191 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
 
         // Validate ui state. Defer this as late as possible; since it may not be
         // called if the table area is out of the visible space.
-        s.validate_cc(&mut table.rows, viewer);
+        s.set_undo_capacity(self.style.max_undo_history);
+        s.set_undo_enabled(self.style.enable_undo);
+        s.set_soft_delete(self.style.soft_delete);
+        s.set_detached_editing(self.style.detached_editing);
+        s.set_deferred_cut(self.style.deferred_cut);
+        s.set_manual_resort(self.style.resort_policy == ResortPolicy::Manual);
+        s.set_confirm_hidden_selection(
+            self.style.hidden_selection_policy == HiddenSelectionPolicy::Confirm,
+        );
+        s.set_compress_undo_history(self.style.compress_undo_history);
+        s.set_extra_filter(self.extra_filter.take());
+        s.set_frame_time(ctx.input(|i| i.time));
+        s.handle_macro_replay(table, viewer, self.style.max_undo_history);
+        s.validate_cc(table, viewer);
+        s.validate_search(table, viewer);
+        s.validate_heat(table, viewer);
+        s.validate_duplicates(table, viewer);
+        s.validate_pivot(table, viewer);
 
         // Checkout `cc_rows` to satisfy borrow checker. We need to access to
         // state mutably within row rendering; therefore, we can't simply borrow
         // `cc_rows` during the whole logic!
         let cc_row_heights = take(&mut s.cc_row_heights);
 
+        // Fixed row height multiplied by the line count of each row, if any row has many.
+        let line_heights = self.style.table_row_height.and_then(|height| {
+            let heights = s
+                .cc_rows
+                .iter()
+                .map(|row| viewer.row_line_count(&table.rows[row.0]).max(1) as f32 * height)
+                .collect::<Vec<_>>();
+
+            heights.iter().any(|x| *x != height).then_some(heights)
+        });
+
+        let row_anim = s.row_animation_frame(ctx, ui_id, self.style.row_animation_time);
+        let cell_flash = s.cell_flash_frame(ctx, ui_id, self.style.cell_flash_time);
+        let cell_colors = CellColors {
+            sel: &sel_colors,
+            flash: cell_flash.as_ref(),
+            bg_flash: self.style.bg_flash_cell.unwrap_or(visual.warn_fg_color),
+            bg_search_match: self
+                .style
+                .bg_search_match
+                .unwrap_or(visual.warn_fg_color.gamma_multiply(0.3)),
+            bg_duplicate: self
+                .style
+                .bg_duplicate_cell
+                .unwrap_or(visual.error_fg_color.gamma_multiply(0.25)),
+            bg_heat: (
+                self.style
+                    .bg_heat_low
+                    .unwrap_or(Color32::from_rgb(0x30, 0x78, 0xd0).gamma_multiply(0.3)),
+                self.style
+                    .bg_heat_high
+                    .unwrap_or(Color32::from_rgb(0xd8, 0x40, 0x30).gamma_multiply(0.3)),
+            ),
+            invalid: Stroke {
+                width: 1.5,
+                color: self.style.fg_invalid_cell.unwrap_or(visual.error_fg_color),
+            },
+            text: visual.strong_text_color(),
+            high_contrast: self.style.high_contrast,
+        };
+
         let mut row_height_updates = Vec::new();
+
+        // Editor is anchored after the layout of every row, onto the cell rect of this frame.
+        let mut editor_anchor = None;
+        let mut editor_rect = None;
         let vis_row_digits = s.cc_rows.len().max(1).ilog10();
         let row_id_digits = table.len().max(1).ilog10();
 
         let body_max_rect = body.max_rect();
+        let overlay_painter = body.ui_mut().painter().clone();
         let has_any_sort = !s.sort().is_empty();
 
         let pointer_interact_pos = ctx.input(|i| i.pointer.latest_pos().unwrap_or_default());
-        let pointer_primary_down = ctx.input(|i| i.pointer.button_down(PointerButton::Primary));
+        let selection_button = self.style.selection_button;
+        let pointer_select_down = ctx.input(|i| i.pointer.button_down(selection_button));
+        let touch_screen = ctx.input(|i| i.has_touch_screen());
+
+        if !pointer_select_down {
+            s.cci_touch_hold = false;
+        }
+
+        // On touch screens, a single tap only selects; editing takes a double tap.
+        let edit_clicked = |resp: &Response, is_interactive_cell: bool| {
+            if self.style.read_only {
+                false
+            } else if self.style.single_click_edit_mode || !touch_screen {
+                resp.clicked_by(selection_button)
+                    && (self.style.single_click_edit_mode || is_interactive_cell)
+            } else {
+                resp.double_clicked_by(selection_button)
+            }
+        };
 
         s.cci_page_row_count = 0;
 
+        let mut overlay_cells = Vec::new();
+
+        let frozen_columns = self.style.frozen_columns.min(visible_cols.len());
+        let row_label_column = viewer.row_label_column();
+        let body_clip = overlay_painter.clip_rect();
+        let bg_frozen = self.style.bg_frozen_column.unwrap_or(visual.panel_fill);
+        let mut pinned_area = Rect::NOTHING;
+
+        // Frozen cells and cells of the pinned rows are rendered after the cells they cover, in
+        // place of them; each takes the pointer interaction over the area it covers.
+        let pinned_cell_ui = |rect: Rect, vis_row: VisRowPos, vis_col: Option<VisColumnPos>| {
+            let id = ui_id.with("frozen").with(vis_row).with(vis_col);
+            let mut ui = egui::Ui::new(
+                ctx.clone(),
+                id,
+                egui::UiBuilder::new()
+                    .layer_id(overlay_painter.layer_id())
+                    .max_rect(rect)
+                    .layout(Layout::left_to_right(Align::Center)),
+            );
+            ui.set_clip_rect(rect.intersect(body_clip));
+
+            // The `Ui` keeps `id` to itself, with a hover sense.
+            let resp = ui.interact(rect, id.with("hit"), Sense::click_and_drag());
+            ui.painter().rect_filled(rect, no_rounding, bg_frozen);
+            if vis_row.0 % 2 == 1 {
+                ui.painter()
+                    .rect_filled(rect, no_rounding, visual.faint_bg_color);
+            }
+
+            (ui, resp)
+        };
+
+        // Pinned rows stick to the top of the body, covering the scrolled rows beneath.
+        let num_pinned_rows = s.num_pinned_rows();
+        let pinned_heights = (0..num_pinned_rows)
+            .map(|vis| match (&line_heights, self.style.table_row_height) {
+                (Some(heights), _) => heights[vis],
+                (None, Some(height)) => height,
+                (None, None) => cc_row_heights[vis],
+            })
+            .collect::<Vec<_>>();
+        let pinned_rows_bottom = body_clip.top() + pinned_heights.iter().sum::<f32>();
+        let mut pinned_row_tops = vec![None; num_pinned_rows];
+
+        // Header rectangle, cell rectangles and frozen offset of a rendered row, to lay out the
+        // pinned rows which are scrolled out.
+        let mut row_layout = None::<(Rect, Vec<Rect>, f32)>;
+
         /* ----------------------------- Primary Rendering Function ----------------------------- */
         // - Extracted as a closure to differentiate behavior based on row height
         //   configuration. (heterogeneous or homogeneous row heights)
 
         let render_fn = |mut row: egui_extras::TableRow| {
+            let vis_row = VisRowPos(row.index());
+
+            if vis_row.0 == s.cc_rows.len() {
+                // Placeholder of new row, which follows every visible row.
+                row.col(|ui| {
+                    ui.add_enabled(false, egui::Label::new(RichText::new(" +").monospace()));
+                });
+
+                for vis_col in (0..visible_cols.len()).map(VisColumnPos) {
+                    let (_, resp) = row.col(|ui| {
+                        if vis_col.0 == 0 {
+                            ui.add_enabled(false, egui::Label::new("New row"));
+                        }
+                    });
+
+                    if ui_enabled && resp.clicked_by(selection_button) {
+                        commands.extend(s.append_new_row(table, viewer, vis_col));
+                    }
+                }
+
+                return;
+            }
+            if s.cci_page_row_count == 0 {
+                s.cci_first_visible_row = vis_row;
+            }
+
             s.cci_page_row_count += 1;
 
-            let vis_row = VisRowPos(row.index());
             let row_id = s.cc_rows[vis_row.0];
             let prev_row_height = cc_row_heights[vis_row.0];
+            let row_opacity = row_anim.as_ref().and_then(|x| x.opacity(row_id));
 
             let mut row_elem_start = Default::default();
 
@@ -400,15 +1663,22 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
             let check_mouse_dragging_selection = {
                 let s_cci_has_focus = s.cci_has_focus;
                 let s_cci_has_selection = s.has_cci_selection();
+                let s_pointer_blocked = !ui_enabled
+                    || s.is_filling()
+                    || s.cci_touch_hold
+                    || s.cci_fill_handle
+                        .is_some_and(|x| x.contains(pointer_interact_pos));
+
+                move |hit_rect: Rect, hovered: bool| {
+                    if s_pointer_blocked {
+                        return false;
+                    }
 
-                move |rect: &Rect, resp: &egui::Response| {
                     let cci_hovered: bool = s_cci_has_focus
                         && s_cci_has_selection
-                        && rect
-                            .with_max_x(resp.rect.right())
-                            .contains(pointer_interact_pos);
-                    let sel_drag = cci_hovered && pointer_primary_down;
-                    let sel_click = !s_cci_has_selection && resp.hovered() && pointer_primary_down;
+                        && hit_rect.contains(pointer_interact_pos);
+                    let sel_drag = cci_hovered && pointer_select_down;
+                    let sel_click = !s_cci_has_selection && hovered && pointer_select_down;
 
                     sel_drag || sel_click
                 }
@@ -420,153 +1690,264 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
             row.set_selected(edit_state.is_some());
 
             // Render row header button
-            let (head_rect, head_resp) = row.col(|ui| {
+            let mut head_max_rect = Rect::NOTHING;
+            let mut pin_dx = 0.;
+            let row_label = row_label_column
+                .and_then(|col| viewer.cell_display_text(&table.rows[row_id.0], col));
+            let row_status = viewer.row_status(&table.rows[row_id.0]);
+            let (head_rect, mut head_resp) = row.col(|ui| {
                 // Calculate the position where values start.
                 row_elem_start = ui.max_rect().right_top();
+                head_max_rect = ui.max_rect();
 
-                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                    ui.separator();
+                // Horizontal offset which sticks the frozen columns to the left edge of visible
+                // area. Frozen header and cells are rendered there, after the row.
+                if frozen_columns > 0 || row_label_column.is_some() {
+                    pin_dx = (body_clip.left() - head_max_rect.left()).max(0.);
+                }
 
-                    if has_any_sort {
-                        ui.monospace(
-                            RichText::from(f!(
-                                "{:·>width$}",
-                                row_id.0,
-                                width = row_id_digits as usize
-                            ))
-                            .strong(),
-                        );
-                    } else {
-                        ui.monospace(
-                            RichText::from(f!("{:>width$}", "", width = row_id_digits as usize))
-                                .strong(),
-                        );
-                    }
+                // Pinned contents still lay out here, unseen, to keep the column widths.
+                if pin_dx > 0. {
+                    ui.set_invisible();
+                }
 
-                    ui.monospace(
-                        RichText::from(f!(
-                            "{:·>width$}",
-                            vis_row.0 + 1,
-                            width = vis_row_digits as usize
-                        ))
-                        .weak(),
-                    );
-                });
+                show_row_header(
+                    ui,
+                    row_id,
+                    vis_row,
+                    has_any_sort,
+                    row_id_digits,
+                    vis_row_digits,
+                    row_label.as_deref(),
+                    row_status,
+                );
             });
 
-            if check_mouse_dragging_selection(&head_rect, &head_resp) {
-                s.cci_sel_update_row(vis_row);
+            let pin = |rect: Rect| rect.translate(egui::vec2(pin_dx, 0.));
+
+            if let Some(top) = pinned_row_tops.get_mut(vis_row.0) {
+                *top = Some(head_max_rect.top());
             }
 
+            let covered_by_pinned_rows =
+                vis_row.0 >= num_pinned_rows && pointer_interact_pos.y < pinned_rows_bottom;
+
             /* -------------------------------- Columns Rendering ------------------------------- */
 
             // Overridable maximum height
             let mut new_maximum_height = 0.;
 
+            // Texts overflowing into the empty cells on the right, painted after the row.
+            let mut spills = Vec::new();
+            let mut row_cell_rects = Vec::with_capacity(visible_cols.len());
+            let mut cell_resps = Vec::with_capacity(visible_cols.len());
+
             // Render cell contents regardless of the edition state.
             for (vis_col, col) in visible_cols.iter().enumerate() {
                 let vis_col = VisColumnPos(vis_col);
-                let linear_index = vis_row.linear_index(visible_cols.len(), vis_col);
-                let selected = s.is_selected(vis_row, vis_col);
-                let cci_selected = s.is_selected_cci(vis_row, vis_col);
-                let is_editing = edit_state.is_some();
-                let is_interactive_cell = interactive_row.is_some_and(|x| x == vis_col);
-                let mut response_consumed = s.is_editing();
+                let cell = CellState::new(s, row_id, vis_row, vis_col);
+                let is_pinned = pin_dx > 0. && vis_col.0 < frozen_columns;
+                let mut cell_rect = Rect::NOTHING;
 
                 let (rect, resp) = row.col(|ui| {
-                    let ui_max_rect = ui.max_rect();
-
-                    if cci_selected {
-                        ui.painter().rect_stroke(
-                            ui_max_rect,
-                            no_rounding,
-                            Stroke {
-                                width: 2.,
-                                color: self
-                                    .style
-                                    .fg_drag_selection
-                                    .unwrap_or(visual.selection.bg_fill),
-                            },
-                        );
+                    cell_rect = ui.max_rect();
+
+                    let row = &table.rows[row_id.0];
+                    let cell_style = viewer.cell_style(row, col.0).unwrap_or_default();
+
+                    if is_pinned {
+                        ui.set_invisible();
+                        if vis_col.0 == 0 {
+                            show_tree_toggle(ui, s, row_id);
+                        }
+
+                        show_cell_content(ui, viewer, row, *col, &cell_style);
+                        return;
                     }
 
-                    if is_interactive_cell {
-                        ui.painter().rect_filled(
-                            ui_max_rect.expand(2.),
-                            no_rounding,
-                            self.style
-                                .bg_selected_highlight_cell
-                                .unwrap_or(visual.selection.bg_fill),
-                        );
-                    } else if selected {
-                        ui.painter().rect_filled(
-                            ui_max_rect.expand(1.),
-                            no_rounding,
-                            self.style
-                                .bg_selected_cell
-                                .unwrap_or(visual.selection.bg_fill.gamma_multiply(0.5)),
-                        );
+                    if let Some(opacity) = row_opacity {
+                        ui.multiply_opacity(opacity);
                     }
 
-                    // Actual widget rendering happens within this line.
+                    cell_colors.show(ui, s, &cell, cell_style.bg, |ui, s| {
+                        // FIXME: After egui 0.27, now the widgets spawned inside this closure
+                        // intercepts interactions, which is basically natural behavior(Upper
+                        // layer widgets). However, this change breaks current implementation
+                        // which relies on the previous table behavior.
+                        if vis_col.0 == 0 {
+                            show_tree_toggle(ui, s, row_id);
+                        }
 
-                    // ui.set_enabled(false);
-                    ui.style_mut()
-                        .visuals
-                        .widgets
-                        .noninteractive
-                        .fg_stroke
-                        .color = visual.strong_text_color();
+                        let spill = viewer
+                            .is_spillover_column(col.0)
+                            .then(|| viewer.cell_text(row, col.0))
+                            .flatten();
 
-                    // FIXME: After egui 0.27, now the widgets spawned inside this closure
-                    // intercepts interactions, which is basically natural behavior(Upper layer
-                    // widgets). However, this change breaks current implementation which relies on
-                    // the previous table behavior.
-                    ui.add_enabled_ui(false, |ui| {
-                        viewer.show_cell_view(ui, &table.rows[row_id.0], col.0);
+                        if let Some(text) = spill {
+                            spills.push((vis_col, text.into_owned(), cell_style.clone()));
+                        } else {
+                            show_cell_content(ui, viewer, row, *col, &cell_style);
+                        }
                     });
+                });
 
-                    #[cfg(any())]
-                    if selected {
-                        ui.painter().rect_stroke(
-                            ui_max_rect,
-                            no_rounding,
-                            Stroke {
-                                width: 1.,
-                                color: visual.weak_text_color(),
-                            },
-                        );
-                    }
+                new_maximum_height = rect.height().max(new_maximum_height);
+                row_cell_rects.push(cell_rect);
+                cell_resps.push((rect, resp));
 
-                    if interactive_row.is_some() && !is_editing {
-                        let st = Stroke {
-                            width: 1.,
-                            color: visual.warn_fg_color.gamma_multiply(0.5),
-                        };
+                if edit_state.is_some_and(|(_, vis)| vis == vis_col) {
+                    editing_cell_rect = if is_pinned { pin(cell_rect) } else { cell_rect };
+                }
+            }
+
+            if row_layout.is_none() {
+                row_layout = Some((head_max_rect, row_cell_rects.clone(), pin_dx));
+            }
+
+            /* --------------------------------- Text Spillover --------------------------------- */
 
-                        let xr = ui_max_rect.x_range();
-                        let yr = ui_max_rect.y_range();
-                        ui.painter().hline(xr, yr.min, st);
-                        ui.painter().hline(xr, yr.max, st);
+            for (vis_col, text, cell_style) in spills {
+                let mut clip = row_cell_rects[vis_col.0];
+
+                // Extends over the following empty cells, stopping at the first occupied one.
+                for (next, col) in visible_cols.iter().enumerate().skip(vis_col.0 + 1) {
+                    let is_empty = viewer
+                        .cell_text(&table.rows[row_id.0], col.0)
+                        .is_some_and(|x| x.is_empty());
+
+                    if !is_empty || next < frozen_columns {
+                        break;
                     }
 
-                    if edit_state.is_some_and(|(_, vis)| vis == vis_col) {
-                        editing_cell_rect = ui_max_rect;
+                    clip = clip.union(row_cell_rects[next]);
+                }
+
+                let font = cell_style
+                    .font
+                    .unwrap_or_else(|| egui::TextStyle::Body.resolve(style));
+                let color = cell_style.fg.unwrap_or(visual.strong_text_color());
+                let galley = overlay_painter.layout_no_wrap(text, font, color);
+                let pos = clip.left_center() - egui::vec2(0., galley.size().y / 2.);
+
+                overlay_painter
+                    .with_clip_rect(clip.intersect(body_clip))
+                    .galley(pos, galley, color);
+            }
+
+            /* ------------------------------ Frozen Cells Rendering ----------------------------- */
+
+            let mut pin_right = f32::NEG_INFINITY;
+
+            if pin_dx > 0. {
+                let rect = pin(head_max_rect);
+                let (mut ui, resp) = pinned_cell_ui(rect, vis_row, None);
+                show_row_header(
+                    &mut ui,
+                    row_id,
+                    vis_row,
+                    has_any_sort,
+                    row_id_digits,
+                    vis_row_digits,
+                    row_label.as_deref(),
+                    row_status,
+                );
+
+                head_resp = resp;
+                pinned_area = pinned_area.union(rect);
+                pin_right = rect.right();
+            }
+
+            for vis_col in (0..frozen_columns)
+                .filter(|_| pin_dx > 0.)
+                .map(VisColumnPos)
+            {
+                let rect = pin(row_cell_rects[vis_col.0]);
+                let (mut ui, resp) = pinned_cell_ui(rect, vis_row, Some(vis_col));
+
+                if let Some(opacity) = row_opacity {
+                    ui.multiply_opacity(opacity);
+                }
+
+                let cell = CellState::new(s, row_id, vis_row, vis_col);
+                let row = &table.rows[row_id.0];
+                let cell_style = viewer.cell_style(row, cell.col.0).unwrap_or_default();
+
+                cell_colors.show(&mut ui, s, &cell, cell_style.bg, |ui, s| {
+                    if vis_col.0 == 0 {
+                        show_tree_toggle(ui, s, row_id);
                     }
+
+                    show_cell_content(ui, viewer, row, cell.col, &cell_style);
                 });
 
-                new_maximum_height = rect.height().max(new_maximum_height);
+                new_maximum_height = ui.min_rect().height().max(new_maximum_height);
+                cell_resps[vis_col.0] = (rect, resp);
+                pinned_area = pinned_area.union(rect);
+                pin_right = pin_right.max(rect.right());
+            }
+
+            /* -------------------------------- Cells Interaction ------------------------------- */
+
+            let head_hit = head_rect.with_max_x(head_resp.rect.right());
+            let head_hit = if pin_dx > 0. {
+                pin(head_max_rect)
+            } else {
+                head_hit
+            };
+            let head_hovered = head_resp.hovered() && !covered_by_pinned_rows;
+
+            if check_mouse_dragging_selection(head_hit, head_hovered) {
+                s.cci_sel_update_row(vis_row);
+            }
+
+            for (vis_col, col) in visible_cols.iter().enumerate() {
+                let vis_col = VisColumnPos(vis_col);
+                let (rect, resp) = &cell_resps[vis_col.0];
+                let (rect, resp) = (*rect, resp.clone());
+                let linear_index = vis_row.linear_index(visible_cols.len(), vis_col);
+                let selected = s.is_selected(vis_row, vis_col);
+                let is_interactive_cell = interactive_row.is_some_and(|x| x == vis_col);
+                let mut response_consumed = s.is_editing();
+                let is_pinned = pin_dx > 0. && vis_col.0 < frozen_columns;
+                let cell_rect = if is_pinned {
+                    rect
+                } else {
+                    row_cell_rects[vis_col.0]
+                };
+
+                if let Some(err) = s.cell_error(row_id, *col) {
+                    resp.clone().on_hover_text(err.message.as_ref());
+                } else if resp.hovered() {
+                    if let Some(tooltip) = viewer.cell_tooltip(&table.rows[row_id.0], col.0) {
+                        resp.clone().on_hover_text(tooltip);
+                    }
+                }
+
+                // Cells hidden behind the frozen ones must not take any pointer interaction.
+                let hidden_by_pin =
+                    (pin_dx > 0. && !is_pinned && pointer_interact_pos.x < pin_right)
+                        || covered_by_pinned_rows;
+                let (hit_rect, hovered) = if hidden_by_pin {
+                    (Rect::NOTHING, false)
+                } else {
+                    (rect.with_max_x(resp.rect.right()), resp.hovered())
+                };
+
+                overlay_cells.push(VisibleCell {
+                    row: row_id.0,
+                    column: col.0,
+                    vis_row: vis_row.0,
+                    rect: cell_rect,
+                });
 
                 // -- Mouse Actions --
-                if check_mouse_dragging_selection(&rect, &resp) {
+                if check_mouse_dragging_selection(hit_rect, hovered) {
                     // Expand cci selection
                     response_consumed = true;
                     s.cci_sel_update(linear_index);
                 }
 
-                if resp.clicked_by(PointerButton::Primary)
-                    && (self.style.single_click_edit_mode || is_interactive_cell)
-                {
+                if edit_clicked(&resp, is_interactive_cell) {
                     response_consumed = true;
                     commands.push(Command::CcEditStart(
                         row_id,
@@ -578,7 +1959,12 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
 
                 /* --------------------------- Context Menu Rendering --------------------------- */
 
-                (resp.clone() | head_resp.clone()).context_menu(|ui| {
+                if resp.long_touched() || head_resp.long_touched() {
+                    s.cci_touch_hold = true;
+                }
+
+                let menu_button = self.style.context_menu_button;
+                context_menu_by(&(resp.clone() | head_resp.clone()), menu_button, |ui| {
                     response_consumed = true;
                     ui.set_min_size(egui::vec2(250., 10.));
 
@@ -608,14 +1994,23 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
 
                     let cursor_x = ui.cursor().min.x;
                     let clip = s.has_clipboard_contents();
+                    let clip_preview = s.clipboard_preview(viewer, 5, 4);
+                    let b_copy = selected && self.style.enable_clipboard;
                     let b_undo = s.has_undo();
                     let b_redo = s.has_redo();
+                    let b_trash = table.trashed_rows().next().is_some();
+                    let b_dup = s.has_duplicates();
+                    let b_compare = s.collect_selected_rows().len() == 2;
+                    let show_only_label = match s.is_showing_selected_only() {
+                        true => "Row: Show All",
+                        false => "Selection: Show Only",
+                    };
                     let mut n_sep_menu = 0;
                     let mut draw_sep = false;
 
                     [
-                        Some((selected, "🖻", "Selection: Copy", UiAction::CopySelection)),
-                        Some((selected, "🖻", "Selection: Cut", UiAction::CutSelection)),
+                        Some((b_copy, "🖻", "Selection: Copy", UiAction::CopySelection)),
+                        Some((b_copy, "🖻", "Selection: Cut", UiAction::CutSelection)),
                         Some((selected, "🗙", "Selection: Clear", UiAction::DeleteSelection)),
                         Some((
                             sel_multi_row,
@@ -623,17 +2018,42 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
                             "Selection: Fill",
                             UiAction::SelectionDuplicateValues,
                         )),
+                        Some((
+                            sel_multi_row,
+                            "⬇",
+                            "Selection: Fill Series",
+                            UiAction::FillSeries,
+                        )),
+                        Some((
+                            b_dup,
+                            "⚠",
+                            "Selection: Duplicates",
+                            UiAction::SelectDuplicates,
+                        )),
+                        Some((true, "👁", show_only_label, UiAction::ToggleShowSelectedOnly)),
+                        None,
+                        #[cfg(feature = "xlsx")]
+                        Some((
+                            selected,
+                            "🖹",
+                            "Selection: Export",
+                            UiAction::ExportSelection,
+                        )),
                         None,
                         Some((clip, "➿", "Clipboard: Paste", UiAction::PasteInPlace)),
                         Some((clip, "🛠", "Clipboard: Insert", UiAction::PasteInsert)),
                         None,
+                        Some((true, "🗋", "Row: Insert Below", UiAction::InsertRowBelow)),
                         Some((true, "🗐", "Row: Duplicate", UiAction::DuplicateRow)),
+                        Some((b_compare, "⇄", "Row: Compare", UiAction::CompareRows)),
                         Some((true, "🗙", "Row: Delete", UiAction::DeleteRow)),
+                        Some((b_trash, "🗑", "Row: Restore Deleted", UiAction::RestoreTrash)),
                         None,
                         Some((b_undo, "⎗", "Undo", UiAction::Undo)),
                         Some((b_redo, "⎘", "Redo", UiAction::Redo)),
                     ]
-                    .map(|opt| {
+                    .into_iter()
+                    .for_each(|opt| {
                         if let Some((icon, label, action)) =
                             opt.filter(|x| x.0).map(|x| (x.1, x.2, x.3))
                         {
@@ -646,105 +2066,405 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
                                 .iter()
                                 .find_map(|(k, a)| (a == &action).then(|| ctx.format_shortcut(k)));
 
-                            ui.horizontal(|ui| {
-                                ui.monospace(icon);
-                                ui.add_space(cursor_x + 20. - ui.cursor().min.x);
+                            ui.horizontal(|ui| {
+                                ui.monospace(icon);
+                                ui.add_space(cursor_x + 20. - ui.cursor().min.x);
+
+                                let btn = egui::Button::new(label)
+                                    .shortcut_text(hotkey.unwrap_or_else(|| "🗙".into()));
+                                let mut r = ui.centered_and_justified(|ui| ui.add(btn)).inner;
+
+                                if let (
+                                    UiAction::PasteInPlace | UiAction::PasteInsert,
+                                    Some(preview),
+                                ) = (action, &clip_preview)
+                                {
+                                    r = r.on_hover_ui(|ui| show_clipboard_preview(ui, preview));
+                                }
+
+                                if r.clicked() {
+                                    actions.push(action);
+                                    ui.close_menu();
+                                }
+                            });
+
+                            n_sep_menu += 1;
+                        } else if n_sep_menu > 0 {
+                            n_sep_menu = 0;
+                            draw_sep = true;
+                        }
+                    });
+
+                    let templates = viewer.row_templates();
+                    if !templates.is_empty() {
+                        ui.separator();
+                        ui.menu_button("🗋 New row from template…", |ui| {
+                            for (label, template) in templates {
+                                if ui.button(label).clicked() {
+                                    let pos = s.insertion_pos(table, vis_row);
+                                    commands.push(Command::InsertRows(pos, [template].into()));
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    }
+
+                    viewer.extend_cell_context_menu(ui, &table.rows[row_id.0], col.0, &mut actions);
+                });
+
+                // Forward DnD event if not any event was consumed by the response.
+
+                // FIXME: Upgrading egui 0.29 make interaction rectangle of response object
+                // larger(in y axis) than actually visible column cell size. To deal with this,
+                // I've used returned content area rectangle instead, expanding its width to
+                // response size.
+
+                let drop_area_rect = rect.with_max_x(resp.rect.max.x);
+                let contains_pointer = ctx
+                    .pointer_hover_pos()
+                    .is_some_and(|pos| drop_area_rect.contains(pos));
+
+                if !response_consumed && contains_pointer && !hidden_by_pin {
+                    if let Some(new_value) =
+                        viewer.on_cell_view_response(&table.rows[row_id.0], col.0, &resp)
+                    {
+                        commands.push(Command::SetCells {
+                            slab: vec![*new_value].into_boxed_slice(),
+                            values: vec![(row_id, *col, RowSlabIndex(0))].into_boxed_slice(),
+                        });
+                    }
+                }
+            }
+
+            if let Some((should_focus, vis_column)) = edit_state {
+                editor_anchor = Some((
+                    vis_row,
+                    row_id,
+                    vis_column,
+                    should_focus,
+                    editing_cell_rect,
+                    prev_row_height,
+                    new_maximum_height,
+                ));
+            }
+
+            // Accumulate response
+            if let Some(resp) = &mut resp_total {
+                *resp = resp.union(row.response());
+            } else {
+                resp_total = Some(row.response());
+            }
+
+            // Update row height cache if necessary; the editing row is updated by the editor.
+            if self.style.table_row_height.is_none()
+                && edit_state.is_none()
+                && prev_row_height != new_maximum_height
+            {
+                row_height_updates.push((vis_row, new_maximum_height));
+            }
+        }; // ~ render_fn
+
+        // Actual rendering
+        let placeholder = self.style.new_row_placeholder;
+        let placeholder_height = placeholder.then_some(20.);
+
+        if let (Some(heights), Some(height)) = (line_heights, self.style.table_row_height) {
+            let placeholder_height = placeholder.then_some(height);
+            body.heterogeneous_rows(heights.into_iter().chain(placeholder_height), render_fn);
+        } else if let Some(height) = self.style.table_row_height {
+            body.rows(
+                height,
+                cc_row_heights.len() + placeholder as usize,
+                render_fn,
+            );
+        } else if let Some(anim) = &row_anim {
+            let heights = cc_row_heights.iter().enumerate();
+            let heights = heights.map(|(i, h)| h + anim.extra_height(VisRowPos(i)));
+            body.heterogeneous_rows(heights.chain(placeholder_height), render_fn);
+        } else {
+            let heights = cc_row_heights.iter().cloned();
+            body.heterogeneous_rows(heights.chain(placeholder_height), render_fn);
+        }
+
+        /* ----------------------------------- Editor Rendering ---------------------------------- */
+
+        if let Some((vis_row, row_id, vis_column, should_focus, cell_rect, prev_height, height)) =
+            editor_anchor
+        {
+            let column = s.vis_cols()[vis_column.0];
+            let mut new_maximum_height = height;
+
+            let editor = egui::Window::new("")
+                .id(ui_id.with(row_id).with(column))
+                .constrain_to(body_max_rect)
+                .fixed_pos(cell_rect.min)
+                .auto_sized()
+                .min_size(cell_rect.size())
+                .max_width(cell_rect.width())
+                .title_bar(false)
+                .frame(egui::Frame::none().rounding(egui::Rounding::same(3.)))
+                .show(ctx, |ui| {
+                    ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
+                        let focus = should_focus.then(|| {
+                            viewer.cell_editor_focus(s.unwrap_editing_row_data(), column.0)
+                        });
+
+                        if let Some(resp) =
+                            viewer.show_cell_editor(ui, s.unwrap_editing_row_data(), column.0)
+                        {
+                            match focus {
+                                Some(EditorFocus::Immediate) => resp.request_focus(),
+                                Some(EditorFocus::SelectAll) => {
+                                    resp.request_focus();
+                                    select_all_text(ui.ctx(), resp.id);
+                                }
+                                Some(EditorFocus::None) | None => {}
+                            }
+
+                            new_maximum_height = resp.rect.height().max(new_maximum_height);
+                        } else {
+                            commands.push(Command::CcCommitEdit);
+                        }
+                    });
+                });
+
+            editor_rect = editor.map(|x| x.response.rect);
+
+            if self.style.table_row_height.is_none() && prev_height != new_maximum_height {
+                row_height_updates.push((vis_row, new_maximum_height));
+            }
+        }
+
+        /* -------------------------------- Pinned Rows Rendering ------------------------------- */
+
+        if let Some((head_rect, cell_rects, pin_dx)) = row_layout.filter(|_| num_pinned_rows > 0) {
+            let mut top = body_clip.top();
+
+            for (vis_row, height) in pinned_heights.iter().enumerate() {
+                let y_range = egui::Rangef::new(top, top + height);
+                top += height;
+
+                // Rendered in place; not scrolled out yet.
+                if pinned_row_tops[vis_row].is_some_and(|x| x >= y_range.min - 0.5) {
+                    continue;
+                }
+
+                let vis_row = VisRowPos(vis_row);
+                let row_id = s.cc_rows[vis_row.0];
+                let pin = |rect: Rect, frozen: bool| {
+                    let rect = Rect::from_x_y_ranges(rect.x_range(), y_range);
+                    rect.translate(egui::vec2(if frozen { pin_dx } else { 0. }, 0.))
+                };
+
+                // Scrolled cells first, then the frozen ones over them.
+                let order = (frozen_columns..cell_rects.len())
+                    .map(Some)
+                    .chain(std::iter::once(None))
+                    .chain((0..frozen_columns).map(Some));
+
+                for vis_col in order.map(|x| x.map(VisColumnPos)) {
+                    let Some(vis_col) = vis_col else {
+                        let rect = pin(head_rect, true);
+                        let (mut ui, _) = pinned_cell_ui(rect, vis_row, None);
+                        let row_label = row_label_column
+                            .and_then(|col| viewer.cell_display_text(&table.rows[row_id.0], col));
+
+                        show_row_header(
+                            &mut ui,
+                            row_id,
+                            vis_row,
+                            has_any_sort,
+                            row_id_digits,
+                            vis_row_digits,
+                            row_label.as_deref(),
+                            viewer.row_status(&table.rows[row_id.0]),
+                        );
+
+                        // Separator is drawn right of the frozen columns only, not of the
+                        // pinned rows.
+                        if frozen_columns > 0 || row_label_column.is_some() {
+                            pinned_area = pinned_area.union(rect);
+                        }
+
+                        continue;
+                    };
 
-                                let btn = egui::Button::new(label)
-                                    .shortcut_text(hotkey.unwrap_or_else(|| "🗙".into()));
-                                let r = ui.centered_and_justified(|ui| ui.add(btn)).inner;
+                    let frozen = vis_col.0 < frozen_columns;
+                    let rect = pin(cell_rects[vis_col.0], frozen);
+                    let (mut ui, resp) = pinned_cell_ui(rect, vis_row, Some(vis_col));
 
-                                if r.clicked() {
-                                    actions.push(action);
-                                    ui.close_menu();
-                                }
-                            });
+                    if frozen {
+                        pinned_area = pinned_area.union(rect);
+                    }
 
-                            n_sep_menu += 1;
-                        } else if n_sep_menu > 0 {
-                            n_sep_menu = 0;
-                            draw_sep = true;
-                        }
-                    });
-                });
+                    let cell = CellState::new(s, row_id, vis_row, vis_col);
+                    let row = &table.rows[row_id.0];
+                    let cell_style = viewer.cell_style(row, cell.col.0).unwrap_or_default();
 
-                // Forward DnD event if not any event was consumed by the response.
+                    cell_colors.show(&mut ui, s, &cell, cell_style.bg, |ui, s| {
+                        if vis_col.0 == 0 {
+                            show_tree_toggle(ui, s, row_id);
+                        }
 
-                // FIXME: Upgrading egui 0.29 make interaction rectangle of response object
-                // larger(in y axis) than actually visible column cell size. To deal with this,
-                // I've used returned content area rectangle instead, expanding its width to
-                // response size.
+                        show_cell_content(ui, viewer, row, cell.col, &cell_style);
+                    });
 
-                let drop_area_rect = rect.with_max_x(resp.rect.max.x);
-                let contains_pointer = ctx
-                    .pointer_hover_pos()
-                    .is_some_and(|pos| drop_area_rect.contains(pos));
+                    if let Some(err) = s.cell_error(row_id, cell.col) {
+                        resp.clone().on_hover_text(err.message.as_ref());
+                    } else if resp.hovered() {
+                        if let Some(tooltip) = viewer.cell_tooltip(row, cell.col.0) {
+                            resp.clone().on_hover_text(tooltip);
+                        }
+                    }
 
-                if !response_consumed && contains_pointer {
-                    if let Some(new_value) =
-                        viewer.on_cell_view_response(&table.rows[row_id.0], col.0, &resp)
-                    {
-                        commands.push(Command::SetCells {
-                            slab: vec![*new_value].into_boxed_slice(),
-                            values: vec![(row_id, *col, RowSlabIndex(0))].into_boxed_slice(),
-                        });
+                    if edit_clicked(&resp, cell.interactive) {
+                        commands.push(Command::CcEditStart(
+                            row_id,
+                            vis_col,
+                            viewer.clone_row(row).into(),
+                        ));
+                        edit_started = true;
                     }
                 }
             }
+        }
 
-            /* -------------------------------- Editor Rendering -------------------------------- */
-            if let Some((should_focus, vis_column)) = edit_state {
-                let column = s.vis_cols()[vis_column.0];
-
-                egui::Window::new("")
-                    .id(ui_id.with(row_id).with(column))
-                    .constrain_to(body_max_rect)
-                    .fixed_pos(editing_cell_rect.min)
-                    .auto_sized()
-                    .min_size(editing_cell_rect.size())
-                    .max_width(editing_cell_rect.width())
-                    .title_bar(false)
-                    .frame(egui::Frame::none().rounding(egui::Rounding::same(3.)))
-                    .show(ctx, |ui| {
-                        ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
-                            if let Some(resp) =
-                                viewer.show_cell_editor(ui, s.unwrap_editing_row_data(), column.0)
-                            {
-                                if should_focus {
-                                    resp.request_focus()
-                                }
+        if pinned_area.is_positive() {
+            overlay_painter.vline(
+                pinned_area.right(),
+                pinned_area.y_range(),
+                visual.widgets.noninteractive.bg_stroke,
+            );
+        }
 
-                                new_maximum_height = resp.rect.height().max(new_maximum_height);
-                            } else {
-                                commands.push(Command::CcCommitEdit);
-                            }
-                        });
+        viewer.paint_overlay(
+            &overlay_painter,
+            CellGeometry {
+                body_rect: overlay_painter.clip_rect().intersect(body_max_rect),
+                cells: &overlay_cells,
+            },
+        );
+
+        /* ------------------------------------- Fill Handle ------------------------------------ */
+
+        s.cci_fill_handle = None;
+        let ncol = visible_cols.len();
+        let corner_cell = s
+            .fill_source()
+            .filter(|_| ui_enabled && !s.is_editing() && !self.style.read_only)
+            .map(|sel| sel.1.row_col(ncol))
+            .and_then(|(r, c)| {
+                overlay_cells
+                    .iter()
+                    .find(|x| x.vis_row == r.0 && x.column == visible_cols[c.0].0)
+            });
+
+        if let Some(corner_cell) = corner_cell {
+            let handle =
+                Rect::from_center_size(corner_cell.rect.right_bottom(), egui::vec2(6., 6.));
+            let id = ui_id.with("fill-handle");
+            let mut ui = egui::Ui::new(
+                ctx.clone(),
+                id,
+                egui::UiBuilder::new()
+                    .layer_id(overlay_painter.layer_id())
+                    .max_rect(handle.expand(2.)),
+            );
+            ui.set_clip_rect(body_clip);
+
+            let resp = ui
+                .interact(handle.expand(2.), id, Sense::drag())
+                .on_hover_cursor(egui::CursorIcon::Crosshair);
+            overlay_painter.rect_filled(handle, no_rounding, sel_colors.outline);
+            s.cci_fill_handle = Some(handle.expand(2.));
+
+            if resp.dragged() {
+                let cell = overlay_cells
+                    .iter()
+                    .find(|x| x.rect.contains(pointer_interact_pos))
+                    .and_then(|x| {
+                        let vis_col = visible_cols.iter().position(|c| c.0 == x.column)?;
+                        Some(VisRowPos(x.vis_row).linear_index(ncol, VisColumnPos(vis_col)))
                     });
-            }
 
-            // Accumulate response
-            if let Some(resp) = &mut resp_total {
-                *resp = resp.union(row.response());
-            } else {
-                resp_total = Some(row.response());
+                if let Some(cell) = cell {
+                    s.cci_fill_update(cell);
+                }
             }
 
-            // Update row height cache if necessary.
-            if self.style.table_row_height.is_none() && prev_row_height != new_maximum_height {
-                row_height_updates.push((vis_row, new_maximum_height));
+            if resp.drag_stopped() {
+                commands.extend(s.cci_take_fill(table, viewer));
             }
-        }; // ~ render_fn
+        } else if s.is_filling() && !pointer_select_down {
+            // Handle went out of the view during the drag.
+            commands.extend(s.cci_take_fill(table, viewer));
+        }
 
-        // Actual rendering
-        if let Some(height) = self.style.table_row_height {
-            body.rows(height, cc_row_heights.len(), render_fn);
-        } else {
-            body.heterogeneous_rows(cc_row_heights.iter().cloned(), render_fn);
+        if let Some(target) = s.cci_fill_target() {
+            let rect = overlay_cells
+                .iter()
+                .filter(|x| {
+                    visible_cols
+                        .iter()
+                        .position(|c| c.0 == x.column)
+                        .is_some_and(|c| {
+                            target.contains(ncol, VisRowPos(x.vis_row), VisColumnPos(c))
+                        })
+                })
+                .fold(Rect::NOTHING, |acc, x| acc.union(x.rect));
+
+            overlay_painter.rect_stroke(
+                rect,
+                no_rounding,
+                Stroke {
+                    width: sel_colors.drag_width,
+                    color: sel_colors.drag,
+                },
+            );
+        }
+
+        // Marching ants around the source cells of the deferred cut.
+        let cut_rect = (overlay_cells.iter())
+            .filter(|x| s.is_cut_source(VisRowPos(x.vis_row), ColumnIdx(x.column)))
+            .fold(Rect::NOTHING, |acc, x| acc.union(x.rect));
+
+        if cut_rect.is_positive() {
+            const DASH: f32 = 4.;
+
+            let time = ctx.input(|i| i.time) as f32;
+            let [lt, rb] = [cut_rect.left_top(), cut_rect.right_bottom()];
+            let path = [lt, cut_rect.right_top(), rb, cut_rect.left_bottom(), lt];
+
+            overlay_painter.extend(egui::Shape::dashed_line_with_offset(
+                &path,
+                Stroke {
+                    width: sel_colors.drag_width,
+                    color: sel_colors.drag,
+                },
+                &[DASH],
+                &[DASH],
+                (time * DASH * 4.) % (DASH * 2.),
+            ));
+
+            ctx.request_repaint();
+        }
+
+        s.cci_visible_cells = overlay_cells;
+
+        /* ------------------------------------ Cursor Icons ------------------------------------ */
+        // Column boundaries show the resize cursor by `egui_extras` itself.
+
+        if s.is_filling() {
+            ctx.set_cursor_icon(egui::CursorIcon::Copy);
+        } else if egui::DragAndDrop::has_payload_of_type::<VisColumnPos>(ctx) {
+            ctx.set_cursor_icon(egui::CursorIcon::Grabbing);
+        } else if pointer_select_down && s.is_range_selecting() {
+            ctx.set_cursor_icon(egui::CursorIcon::Crosshair);
         }
 
         /* ----------------------------------- Event Handling ----------------------------------- */
 
-        if ctx.input(|i| i.pointer.button_released(PointerButton::Primary)) {
+        if ctx.input(|i| i.pointer.button_released(selection_button)) {
             let mods = ctx.input(|i| i.modifiers);
             if let Some(sel) = s.cci_take_selection(mods).filter(|_| !edit_started) {
                 commands.push(Command::CcSetSelection(sel));
@@ -757,26 +2477,291 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
                 s.cci_has_focus = true;
             } else if resp.clicked_elsewhere() {
                 s.cci_has_focus = false;
+
+                // Clicks on the editor, or on its popups, are not outside.
+                let pos = ctx.input(|i| i.pointer.interact_pos());
+                let on_editor = editor_rect.is_some_and(|x| pos.is_some_and(|p| x.contains(p)))
+                    || ctx.memory(|m| m.any_popup_open());
+
+                if s.is_editing() && !on_editor {
+                    match self.style.edit_commit_policy {
+                        EditCommitPolicy::CommitOnFocusLoss => commands.push(Command::CcCommitEdit),
+                        EditCommitPolicy::CancelOnFocusLoss => commands.push(Command::CcCancelEdit),
+                        EditCommitPolicy::IgnoreOutsideClicks => {}
+                    }
+                }
             }
         }
 
         // Check in borrowed `cc_rows` back to state.
-        s.cc_row_heights = cc_row_heights.tap_mut(|values| {
-            if !row_height_updates.is_empty() {
-                ctx.request_repaint();
+        s.cc_row_heights = cc_row_heights;
+
+        if !row_height_updates.is_empty() {
+            ctx.request_repaint();
+        }
+
+        for (row_index, row_height) in row_height_updates {
+            s.set_row_height(row_index, row_height);
+        }
+
+        // Handle queued actions
+        actions.retain(|x| self.style.is_action_enabled(*x));
+        commands.extend(actions.into_iter().flat_map(|action| {
+            s.record_macro_action(viewer, action);
+
+            if self.style.row_edit_session {
+                if let Some(cmds) = s.try_move_row_editor(viewer, action) {
+                    return cmds;
+                }
             }
 
-            for (row_index, row_height) in row_height_updates {
-                values[row_index.0] = row_height;
+            if self.style.reveal_hidden_columns_on_move {
+                if let Some(cmds) = s.try_reveal_hidden_column(action) {
+                    return cmds;
+                }
             }
-        });
 
-        // Handle queued actions
-        commands.extend(
-            actions
-                .into_iter()
-                .flat_map(|action| s.try_apply_ui_action(table, viewer, action)),
-        );
+            s.try_apply_ui_action(table, viewer, action)
+        }));
+
+        /* ---------------------------------- Paste Confirmation --------------------------------- */
+
+        if let Some(summary) = s.pending_paste().copied() {
+            let mut apply = None;
+            let modal = egui::Modal::new(ui_id.with("paste-confirm")).show(ctx, |ui| {
+                ui.strong(if summary.insert {
+                    "Insert pasted rows?"
+                } else {
+                    "Apply paste?"
+                });
+
+                ui.label(f!(
+                    "{} cells in {} rows will be changed.",
+                    summary.cells,
+                    summary.rows
+                ));
+
+                if summary.skipped_rows > 0 {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        f!(
+                            "{} rows are skipped due to decode errors.",
+                            summary.skipped_rows
+                        ),
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() || ui.input(|i| i.key_pressed(egui::Key::Enter))
+                    {
+                        apply = Some(true);
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        apply = Some(false);
+                    }
+                });
+            });
+
+            if modal.should_close() {
+                apply.get_or_insert(false);
+            }
+
+            if let Some(apply) = apply {
+                commands.extend(s.resolve_pending_paste(apply));
+            }
+        }
+
+        /* ------------------------------ Whole Data Confirmation ------------------------------- */
+
+        if let Some(action) = s.pending_all_data() {
+            let mut apply = None;
+            let modal = egui::Modal::new(ui_id.with("all-data-confirm")).show(ctx, |ui| {
+                ui.strong(match action {
+                    UiAction::CopySelection => "Copy all rows?",
+                    UiAction::CutSelection => "Cut all rows?",
+                    UiAction::DeleteSelection => "Clear all rows?",
+                    _ => "Delete all rows?",
+                });
+
+                let total = table.len() - table.trashed_rows().count();
+                ui.label(f!(
+                    "{} rows, including {} filtered out, will be affected.",
+                    total,
+                    total.saturating_sub(s.cc_rows.len())
+                ));
+
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() || ui.input(|i| i.key_pressed(egui::Key::Enter))
+                    {
+                        apply = Some(true);
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        apply = Some(false);
+                    }
+                });
+            });
+
+            if modal.should_close() {
+                apply.get_or_insert(false);
+            }
+
+            if let Some(apply) = apply {
+                commands.extend(s.resolve_pending_all_data(table, viewer, apply));
+            }
+        }
+
+        /* --------------------------- Hidden Selection Confirmation ---------------------------- */
+
+        if let Some((action, num_hidden)) = s.pending_hidden_selection() {
+            let mut apply = None;
+            let modal = egui::Modal::new(ui_id.with("hidden-confirm")).show(ctx, |ui| {
+                ui.strong(match action {
+                    UiAction::CutSelection => "Cut visible selection?",
+                    UiAction::DeleteSelection => "Clear visible selection?",
+                    _ => "Delete visible rows?",
+                });
+
+                ui.label(f!(
+                    "{num_hidden} selected rows are hidden by the filter, and will be left unchanged."
+                ));
+
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() || ui.input(|i| i.key_pressed(egui::Key::Enter))
+                    {
+                        apply = Some(true);
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        apply = Some(false);
+                    }
+                });
+            });
+
+            if modal.should_close() {
+                apply.get_or_insert(false);
+            }
+
+            if let Some(apply) = apply {
+                commands.extend(s.resolve_pending_hidden_selection(table, viewer, apply));
+            }
+        }
+
+        /* ---------------------------------- Row Comparison ---------------------------------- */
+
+        if let Some(compared) = s.compared_rows() {
+            let columns = s.differing_columns(table, viewer, compared);
+            let mut open = true;
+
+            egui::Window::new("Compare Rows")
+                .id(ui_id.with("row-compare"))
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    if columns.is_empty() {
+                        ui.label("Rows are identical.");
+                        return;
+                    }
+
+                    egui::Grid::new(ui_id.with("row-compare-grid"))
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Column");
+                            for row_id in compared {
+                                ui.strong(f!("Row {}", row_id.0 + 1));
+                            }
+                            ui.end_row();
+
+                            for col in columns {
+                                ui.label(viewer.column_name(col.0));
+                                for row_id in compared {
+                                    viewer.show_cell_view(ui, &table.rows[row_id.0], col.0);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            if !open {
+                s.close_row_comparison();
+            }
+        }
+
+        /* ---------------------------------- Group Summary ----------------------------------- */
+
+        if let Some((mut group, mut value)) = s.pivot_columns() {
+            let mut open = true;
+
+            egui::Window::new("Group Summary")
+                .id(ui_id.with("pivot"))
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    egui::Grid::new(ui_id.with("pivot-columns")).show(ui, |ui| {
+                        ui.label("Group by");
+                        egui::ComboBox::from_id_salt(ui_id.with("pivot-group"))
+                            .selected_text(viewer.column_name(group.0))
+                            .show_ui(ui, |ui| {
+                                for col in (0..s.num_columns()).map(ColumnIdx) {
+                                    ui.selectable_value(&mut group, col, viewer.column_name(col.0));
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Sum of");
+                        egui::ComboBox::from_id_salt(ui_id.with("pivot-value"))
+                            .selected_text(
+                                value.map_or("(none)".into(), |c| viewer.column_name(c.0)),
+                            )
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut value, None, "(none)");
+                                for col in (0..s.num_columns()).map(ColumnIdx) {
+                                    ui.selectable_value(
+                                        &mut value,
+                                        Some(col),
+                                        viewer.column_name(col.0),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+                    });
+
+                    ui.separator();
+
+                    egui::ScrollArea::vertical()
+                        .max_height(300.)
+                        .show(ui, |ui| {
+                            egui::Grid::new(ui_id.with("pivot-rows"))
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.strong(viewer.column_name(group.0));
+                                    ui.strong("Count");
+                                    if value.is_some() {
+                                        ui.strong("Sum");
+                                    }
+                                    ui.end_row();
+
+                                    for row in s.pivot_rows() {
+                                        ui.label(&row.group);
+                                        ui.monospace(row.count.to_string());
+                                        if value.is_some() {
+                                            ui.monospace(
+                                                row.sum.map_or(String::new(), |x| x.to_string()),
+                                            );
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                });
+
+            if open {
+                s.set_pivot_columns(group, value);
+            } else {
+                s.close_pivot();
+            }
+        }
 
         // Handle queued commands
         for cmd in commands {
@@ -801,13 +2786,324 @@ impl<'a, R, V: RowViewer<R>> Renderer<'a, R, V> {
             }
         }
 
+        if s.has_queued_macro_replay() {
+            ctx.request_repaint();
+        }
+
         // Total response
         resp_total.unwrap()
     }
 }
 
-impl<R, V: RowViewer<R>> Drop for Renderer<'_, R, V> {
+impl<'a, R: Default + 'static> Renderer<'a, R, ColumnSpec<R>> {
+    /// Renders the table with a viewer of plain field columns, built by `columns`; see
+    /// [`ColumnSpec`].
+    ///
+    /// ```ignore
+    /// Renderer::with_columns(&mut table, |cols| {
+    ///     cols.text("Name", |x| &x.name, |x| &mut x.name)
+    ///         .drag_i32("Age", |x| &x.age, |x| &mut x.age)
+    ///         .bool("Student", |x| &x.enrolled, |x| &mut x.enrolled)
+    /// })
+    /// ```
+    pub fn with_columns(
+        table: &'a mut DataTable<R>,
+        columns: impl FnOnce(ColumnSpec<R>) -> ColumnSpec<R>,
+    ) -> Self {
+        Self::with_viewer(
+            table,
+            ViewerSlot::Owned(Box::new(columns(ColumnSpec::new()))),
+        )
+    }
+}
+
+impl<R, V: RowViewer<R> + ?Sized> Drop for Renderer<'_, R, V> {
     fn drop(&mut self) {
         self.table.ui = self.state.take();
     }
 }
+
+/// [`Response::context_menu`] opened by `button`, instead of the secondary one.
+fn context_menu_by(
+    resp: &Response,
+    button: PointerButton,
+    add_contents: impl FnOnce(&mut egui::Ui),
+) {
+    // egui opens context menus only by secondary clicks or long presses; pass the click of the
+    // given button off as the latter.
+    let mut resp = resp.clone();
+    resp.long_touched |= resp.clicked_by(button);
+    resp.clicked &= resp.long_touched;
+    resp.context_menu(add_contents);
+}
+
+/// Selects whole text of the text editor of `id`; no-op for other widgets.
+fn select_all_text(ctx: &egui::Context, id: egui::Id) {
+    use egui::text::{CCursor, CCursorRange};
+
+    if let Some(mut state) = egui::TextEdit::load_state(ctx, id) {
+        // The end is clamped to the length of the text.
+        let range = CCursorRange::two(CCursor::new(0), CCursor::new(usize::MAX));
+        state.cursor.set_char_range(Some(range));
+        state.store(ctx, id);
+    }
+}
+
+/// Row header contents; visual row number, the row index while sorted, and the status badge.
+#[allow(clippy::too_many_arguments)]
+fn show_row_header(
+    ui: &mut egui::Ui,
+    row_id: RowIdx,
+    vis_row: VisRowPos,
+    has_any_sort: bool,
+    row_id_digits: u32,
+    vis_row_digits: u32,
+    label: Option<&str>,
+    status: RowStatus,
+) {
+    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+        ui.separator();
+
+        if let Some(label) = label {
+            egui::Label::new(label).selectable(false).ui(ui);
+        }
+
+        if has_any_sort {
+            ui.monospace(
+                RichText::from(f!("{:·>width$}", row_id.0, width = row_id_digits as usize))
+                    .strong(),
+            );
+        } else {
+            ui.monospace(
+                RichText::from(f!("{:>width$}", "", width = row_id_digits as usize)).strong(),
+            );
+        }
+
+        ui.monospace(
+            RichText::from(f!(
+                "{:·>width$}",
+                vis_row.0 + 1,
+                width = vis_row_digits as usize
+            ))
+            .weak(),
+        );
+
+        let badge = match status {
+            RowStatus::None => None,
+            RowStatus::Modified => Some(("●", ui.visuals().warn_fg_color)),
+            RowStatus::Error => Some(("⚠", ui.visuals().error_fg_color)),
+            RowStatus::Custom(icon, color) => Some((icon, color)),
+        };
+
+        if let Some((icon, color)) = badge {
+            egui::Label::new(RichText::new(icon).color(color))
+                .selectable(false)
+                .ui(ui);
+        }
+    });
+}
+
+/// Dimensions and the leading cells of the clipboard contents to paste.
+fn show_clipboard_preview(ui: &mut egui::Ui, preview: &ClipboardPreview) {
+    const MAX_CHARS: usize = 24;
+
+    ui.label(f!("{} × {} cells", preview.rows, preview.columns));
+
+    egui::Grid::new("clipboard-preview")
+        .striped(true)
+        .show(ui, |ui| {
+            for name in &preview.header {
+                ui.strong(name.as_ref());
+            }
+
+            if preview.columns > preview.header.len() {
+                ui.weak("…");
+            }
+
+            ui.end_row();
+
+            for row in &preview.cells {
+                for cell in row {
+                    match cell {
+                        Some(text) => {
+                            let mut line: String = (text.chars())
+                                .map(|x| if x.is_control() { ' ' } else { x })
+                                .take(MAX_CHARS)
+                                .collect();
+
+                            if text.chars().nth(MAX_CHARS).is_some() {
+                                line.push('…');
+                            }
+
+                            ui.label(line)
+                        }
+                        None => ui.weak("—"),
+                    };
+                }
+
+                ui.end_row();
+            }
+
+            if preview.rows > preview.cells.len() {
+                ui.weak("…");
+                ui.end_row();
+            }
+        });
+}
+
+/// Indents the first visible column of the tree row, with the toggle which expands or collapses
+/// its children.
+fn show_tree_toggle<R>(ui: &mut egui::Ui, s: &mut UiState<R>, row_id: RowIdx) {
+    const INDENT: f32 = 12.;
+
+    let Some((depth, has_children, expanded)) = s.tree_node(row_id) else {
+        return;
+    };
+
+    ui.add_space(depth as f32 * INDENT);
+
+    let icon = match (has_children, expanded) {
+        (false, _) => " ",
+        (true, true) => "⏷",
+        (true, false) => "⏵",
+    };
+
+    let resp = egui::Label::new(RichText::new(icon).monospace())
+        .selectable(false)
+        .sense(egui::Sense::click())
+        .ui(ui);
+
+    if has_children && resp.clicked() {
+        s.toggle_tree_row(row_id);
+    }
+}
+
+/// Priority of the sort key being dragged in the sorting configuration.
+#[derive(Clone, Copy)]
+struct SortPriority(usize);
+
+/// Lists the sort keys by priority, which can be reordered by dragging and toggled between
+/// ascending and descending. Returns the new sort if it's modified.
+fn show_sort_config<R>(
+    ui: &mut egui::Ui,
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    style: &Style,
+    sort: &[(ColumnIdx, IsAscending)],
+    sortable_cols: &[ColumnIdx],
+) -> Option<Vec<(ColumnIdx, IsAscending)>> {
+    let mut new_sort = sort.to_owned();
+    let mut changed = false;
+
+    for (pos, &(col, asc)) in sort.iter().enumerate() {
+        let row = ui.horizontal(|ui| {
+            ui.dnd_drag_source(ui.id().with(pos), SortPriority(pos), |ui| {
+                ui.label("☰");
+            });
+
+            ui.monospace(format!("{}", pos + 1));
+
+            if ui.button(["↘", "↗"][asc.0 as usize]).clicked() {
+                new_sort[pos].1 = IsAscending(!asc.0);
+                changed = true;
+            }
+
+            ui.label(viewer.column_name(col.0));
+
+            if ui.small_button("🗙").clicked() {
+                new_sort.retain(|(c, ..)| *c != col);
+                changed = true;
+            }
+        });
+
+        if let Some(from) = row.response.dnd_release_payload::<SortPriority>() {
+            let key = new_sort.remove(from.0);
+            new_sort.insert(pos.min(new_sort.len()), key);
+            changed = true;
+        }
+    }
+
+    let unsorted = sortable_cols
+        .iter()
+        .filter(|col| !sort.iter().any(|(c, ..)| c == *col))
+        .collect::<Vec<_>>();
+
+    if !sort.is_empty() && !unsorted.is_empty() {
+        ui.separator();
+    }
+
+    for col in unsorted {
+        if ui
+            .button(format!("+ {}", viewer.column_name(col.0)))
+            .clicked()
+        {
+            new_sort.push((*col, is_ascending_first(viewer, style.sort_cycle, *col)));
+            changed = true;
+        }
+    }
+
+    changed.then_some(new_sort)
+}
+
+/// Checklist of every column to show or hide. Shown columns are placed after the nearest
+/// preceding visible column in data order. Returns the new visible columns if they're modified.
+fn show_column_picker<R>(
+    ui: &mut egui::Ui,
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    vis_cols: &[ColumnIdx],
+    num_columns: usize,
+) -> Option<Vec<ColumnIdx>> {
+    let mut changed = None;
+
+    for col in (0..num_columns).map(ColumnIdx) {
+        let mut visible = vis_cols.contains(&col);
+
+        // At least one column should remain visible.
+        let is_last = visible && vis_cols.len() == 1;
+        let is_locked = visible && !viewer.is_hidable_column(col.0);
+        let resp = ui.add_enabled(
+            !is_last && !is_locked,
+            egui::Checkbox::new(&mut visible, viewer.column_name(col.0)),
+        );
+
+        if !resp.changed() {
+            continue;
+        }
+
+        let mut new_cols = vis_cols.to_owned();
+        if visible {
+            let pos = new_cols.iter().rposition(|c| *c < col).map_or(0, |x| x + 1);
+            new_cols.insert(pos, col);
+        } else {
+            new_cols.retain(|c| *c != col);
+        }
+
+        changed = Some(new_cols);
+    }
+
+    changed
+}
+
+/// Read-only cell contents; sparkline if the viewer provides one.
+fn show_cell_content<R>(
+    ui: &mut egui::Ui,
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    row: &R,
+    column: ColumnIdx,
+    style: &CellStyle,
+) {
+    if let Some(color) = style.fg {
+        ui.visuals_mut().override_text_color = Some(color);
+    }
+
+    if let Some(font) = &style.font {
+        ui.style_mut().override_font_id = Some(font.clone());
+    }
+
+    if let Some(values) = viewer.cell_sparkline(row, column.0) {
+        ui.add(Sparkline::new(&values));
+    } else {
+        ui.add_enabled_ui(false, |ui| {
+            viewer.show_cell_view(ui, row, column.0);
+        });
+    }
+}
@@ -1,23 +1,56 @@
 #![allow(unused)]
-//! A short implementation for reading and writing TSV data.
+//! A short implementation for reading and writing delimited text data; TSV, CSV and variants
+//! described by [`ClipboardFormat`].
 
 use std::ops::Range;
 
-pub fn write_tab(buf: &mut String) {
-    buf.push('\t');
+use crate::viewer::ClipboardFormat;
+
+pub fn write_delimiter(buf: &mut String, format: &ClipboardFormat) {
+    buf.push(format.delimiter);
 }
 
 pub fn write_newline(buf: &mut String) {
     buf.push('\n');
 }
 
-pub fn write_content(buf: &mut String, mut item: &str) {
+pub fn write_content(buf: &mut String, mut item: &str, format: &ClipboardFormat) {
+    if let Some(quote) = format.quote {
+        let needs_quote = item.is_empty()
+            || item
+                .chars()
+                .any(|ch| ch == format.delimiter || ch == quote || ch == '\n' || ch == '\r');
+
+        if needs_quote {
+            // RFC 4180; wrap with quotes and double the quote characters within. Empty cells
+            // are quoted as well, to be parsed as explicit empty data.
+            buf.reserve(item.len() + 2);
+            buf.push(quote);
+
+            for char in item.chars() {
+                if char == quote {
+                    buf.push(quote);
+                }
+
+                buf.push(char);
+            }
+
+            buf.push(quote);
+            return;
+        }
+    }
+
     if item.is_empty() {
         item = " ";
     }
 
     buf.reserve(item.len());
 
+    if !format.backslash_escape {
+        buf.push_str(item);
+        return;
+    }
+
     for char in item.chars() {
         match char {
             '\t' => buf.push_str(r"\t"),
@@ -33,24 +66,29 @@ pub fn write_content(buf: &mut String, mut item: &str) {
 /*                                             READER                                             */
 /* ============================================================================================== */
 
-pub struct ParsedTsv {
-    /// We need owned buffer to store escaped TSV data.
+pub struct ParsedTable {
+    /// We need owned buffer to store unescaped cell data.
     data: String,
 
-    /// Byte span info for each cell in the TSV data. As long as the cell is explicitly allocated
-    /// using tab character, the cell will be stored in this vector even if it is empty.
+    /// Byte span info for each cell in the data. As long as the cell is explicitly allocated
+    /// using delimiter character, the cell will be stored in this vector even if it is empty.
     cell_spans: Vec<Range<u32>>,
 
     /// Index offsets for start of each row in the `cell_spans` vector.
     row_offsets: Vec<u32>,
 }
 
-impl ParsedTsv {
-    pub fn parse(data: &str) -> Self {
+impl ParsedTable {
+    pub fn parse(data: &str, format: &ClipboardFormat) -> Self {
         #[derive(Clone, Copy)]
         enum ParseState {
             Empty,
             Escaping,
+            Quoted,
+
+            /// Met a quote character within quoted cell; either an escaped quote or the end of
+            /// the quoted cell.
+            QuoteInQuoted,
         }
 
         let mut s = Self {
@@ -62,31 +100,34 @@ impl ParsedTsv {
         let mut state = ParseState::Empty;
         let mut cell_start_char = 0;
 
+        // Quoted cell is always regarded as explicit cell, even if it's empty.
+        let mut cell_quoted = false;
+
         // Add initial row offset.
         s.row_offsets.push(0);
 
         for char in data.chars() {
             match state {
-                ParseState::Empty => match char {
-                    '\n' | '\t' => {
-                        if char == '\t' || cell_start_char != s.data.len() as u32 {
-                            // For tab character, we don't care if it's empty cell. Otherwise,
-                            // we add the last cell only when it's not empty.
-                            s.cell_spans.push(cell_start_char..s.data.len() as u32);
-                            cell_start_char = s.data.len() as _;
-                        }
-
-                        if char == '\n' {
-                            // Add row offset and move to new row.
-                            s.row_offsets.push(s.cell_spans.len() as _);
-                        }
+                ParseState::Quoted => {
+                    if Some(char) == format.quote {
+                        state = ParseState::QuoteInQuoted;
+                    } else {
+                        s.data.push(char);
                     }
-                    '\r' => {
-                        // Ignoring.
+
+                    continue;
+                }
+                ParseState::QuoteInQuoted => {
+                    state = ParseState::Empty;
+
+                    if Some(char) == format.quote {
+                        s.data.push(char);
+                        state = ParseState::Quoted;
+                        continue;
                     }
-                    '\\' => state = ParseState::Escaping,
-                    ch => s.data.push(ch),
-                },
+
+                    // Otherwise, the quoted cell is closed; handle the character as usual.
+                }
                 ParseState::Escaping => {
                     match char {
                         't' => s.data.push('\t'),
@@ -101,13 +142,48 @@ impl ParsedTsv {
                     }
 
                     state = ParseState::Empty;
+                    continue;
                 }
+                ParseState::Empty => {}
+            }
+
+            match char {
+                ch if ch == '\n' || ch == format.delimiter => {
+                    if ch == format.delimiter
+                        || cell_quoted
+                        || cell_start_char != s.data.len() as u32
+                    {
+                        // For delimiter, we don't care if it's empty cell. Otherwise, we add
+                        // the last cell only when it's not empty.
+                        s.cell_spans.push(cell_start_char..s.data.len() as u32);
+                        cell_start_char = s.data.len() as _;
+                        cell_quoted = false;
+                    }
+
+                    if ch == '\n' {
+                        // Add row offset and move to new row.
+                        s.row_offsets.push(s.cell_spans.len() as _);
+                    }
+                }
+                '\r' => {
+                    // Ignoring.
+                }
+                '\\' if format.backslash_escape => state = ParseState::Escaping,
+                ch if Some(ch) == format.quote
+                    && !cell_quoted
+                    && cell_start_char == s.data.len() as u32 =>
+                {
+                    // Quote is only recognized at the beginning of the cell.
+                    cell_quoted = true;
+                    state = ParseState::Quoted;
+                }
+                ch => s.data.push(ch),
             }
         }
 
         // Need to check if we have any remaining cell to add.
         {
-            if cell_start_char != s.data.len() as u32 {
+            if cell_quoted || cell_start_char != s.data.len() as u32 {
                 s.cell_spans.push(cell_start_char..s.data.len() as u32);
             }
 
@@ -185,7 +261,7 @@ impl ParsedTsv {
 fn tsv_parsing() {
     const TSV_DATA: &str = "Hello\tWorld\nThis\tIs\tA\tTest";
 
-    let parsed = ParsedTsv::parse(TSV_DATA);
+    let parsed = ParsedTable::parse(TSV_DATA, &ClipboardFormat::TSV);
     assert_eq!(parsed.num_columns_at(0), 2);
     assert_eq!(parsed.num_columns_at(1), 4);
     assert_eq!(parsed.num_columns_at(2), 0);
@@ -212,3 +288,38 @@ fn tsv_parsing() {
         ]
     );
 }
+
+#[test]
+fn csv_parsing() {
+    const CSV_DATA: &str = "\"Hello, World\",\"\"\r\n\"Multi\nLine\",\"Say \"\"Hi\"\"\",plain";
+
+    let parsed = ParsedTable::parse(CSV_DATA, &ClipboardFormat::CSV);
+    assert_eq!(parsed.num_rows(), 2);
+    assert_eq!(
+        parsed.iter_index_data().collect::<Vec<_>>(),
+        vec![
+            (0, 0, "Hello, World"),
+            (0, 1, ""),
+            (1, 0, "Multi\nLine"),
+            (1, 1, "Say \"Hi\""),
+            (1, 2, "plain"),
+        ]
+    );
+
+    // Written content should be parsed back as is.
+    let format = ClipboardFormat::CSV;
+    let mut buf = String::new();
+    for (i, item) in ["a,b", "", "\"q\"", "x\ny"].into_iter().enumerate() {
+        if i > 0 {
+            write_delimiter(&mut buf, &format);
+        }
+
+        write_content(&mut buf, item, &format);
+    }
+
+    let parsed = ParsedTable::parse(&buf, &format);
+    assert_eq!(
+        parsed.iter_index_data().collect::<Vec<_>>(),
+        vec![(0, 0, "a,b"), (0, 1, ""), (0, 2, "\"q\""), (0, 3, "x\ny")]
+    );
+}
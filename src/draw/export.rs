@@ -0,0 +1,566 @@
+//! Off-screen rendering of the table, for printing or embedding in reports, and plain text
+//! rendering for logs.
+
+use std::fmt::Write;
+
+use egui::{
+    epaint::{ColorMode, PathShape, PathStroke, RectShape, TextShape},
+    Align, Color32, Layout, Pos2, Rect, Shape, Stroke, UiBuilder,
+};
+
+use crate::{
+    viewer::{text_encoder, RowViewer},
+    DataTable,
+};
+
+use super::state::{ColumnIdx, RowIdx};
+
+use format as f;
+
+/// Options for [`crate::DataTable::render_to_image`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct ImageExportOptions {
+    /// Render only the rows which contain any selected cell.
+    pub selected_only: bool,
+
+    /// Render column names above the rows.
+    pub header: bool,
+
+    /// Width of every column, in points.
+    pub column_width: f32,
+
+    /// Height of every row including the header, in points.
+    pub row_height: f32,
+
+    /// Use dark visuals instead of light ones, which suit printing better.
+    pub dark_mode: bool,
+}
+
+impl Default for ImageExportOptions {
+    fn default() -> Self {
+        Self {
+            selected_only: false,
+            header: true,
+            column_width: 100.,
+            row_height: 20.,
+            dark_mode: false,
+        }
+    }
+}
+
+/// Rows and columns in the current visual order of the table. If the table was never rendered,
+/// every row and column is returned in the original order.
+fn export_layout<R>(
+    table: &mut DataTable<R>,
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    selected_only: bool,
+) -> (Vec<RowIdx>, Vec<ColumnIdx>) {
+    table
+        .with_ui_state(|ui, table| ui.export_layout(table, viewer, selected_only))
+        .unwrap_or_else(|| {
+            let rows = (0..table.rows.len())
+                .filter(|x| !table.trash.contains(x) && !selected_only)
+                .map(RowIdx)
+                .collect();
+            (rows, (0..viewer.num_columns()).map(ColumnIdx).collect())
+        })
+}
+
+/// See [`DataTable::render_to_image`].
+pub(crate) fn render_table<R>(
+    table: &mut DataTable<R>,
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    opts: &ImageExportOptions,
+) -> String {
+    let (rows, columns) = export_layout(table, viewer, opts.selected_only);
+    let rows = rows.iter().map(|x| &table.rows[x.0]).collect::<Vec<_>>();
+    render_svg(viewer, &rows, &columns, opts)
+}
+
+/// See [`DataTable::to_pretty_string`].
+pub(crate) fn pretty_table<R>(
+    table: &mut DataTable<R>,
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    max_rows: usize,
+) -> Option<String> {
+    let (rows, columns) = export_layout(table, viewer, false);
+    let names = columns
+        .iter()
+        .map(|c| viewer.column_name(c.0).into_owned())
+        .collect::<Vec<_>>();
+
+    let mut encode = text_encoder(viewer);
+    let mut has_text = false;
+    let mut cells = vec![names];
+
+    for row in rows.iter().take(max_rows) {
+        let row = &table.rows[row.0];
+        let mut texts = Vec::with_capacity(columns.len());
+
+        for column in &columns {
+            let mut text = String::new();
+            has_text |= encode(row, column.0, &mut text);
+
+            // Line breaks and tabs would break the alignment.
+            texts.push(text.replace(char::is_control, " "));
+        }
+
+        cells.push(texts);
+    }
+
+    if !has_text && !rows.is_empty() {
+        return None;
+    }
+
+    let widths = (0..columns.len())
+        .map(|c| {
+            cells
+                .iter()
+                .map(|x| x[c].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let border = |l: char, m: char, r: char| {
+        let mut line = String::from(l);
+        for (i, width) in widths.iter().enumerate() {
+            if i > 0 {
+                line.push(m);
+            }
+            line.extend(std::iter::repeat_n('─', width + 2));
+        }
+        line.push(r);
+        line.push('\n');
+        line
+    };
+
+    let mut out = border('┌', '┬', '┐');
+
+    for (i, texts) in cells.iter().enumerate() {
+        if i == 1 {
+            out += &border('├', '┼', '┤');
+        }
+
+        for (text, width) in texts.iter().zip(&widths) {
+            let _ = write!(out, "│ {text:<width$} ");
+        }
+
+        out += "│\n";
+    }
+
+    out += &border('└', '┴', '┘');
+
+    if rows.len() > max_rows {
+        let _ = writeln!(out, "… {} more rows", rows.len() - max_rows);
+    }
+
+    Some(out)
+}
+
+/// See [`DataTable::export_xlsx`].
+#[cfg(feature = "xlsx")]
+pub(crate) fn table_xlsx<R>(
+    table: &mut DataTable<R>,
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+) -> Vec<u8> {
+    let (rows, columns) = export_layout(table, viewer, false);
+    let rows = rows.iter().map(|x| &table.rows[x.0]).collect::<Vec<_>>();
+    xlsx_of(viewer, &rows, &columns)
+}
+
+/// Workbook of the cells encoded through [`text_encoder`], headed by the column names.
+#[cfg(feature = "xlsx")]
+pub(crate) fn xlsx_of<R>(
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    rows: &[&R],
+    columns: &[ColumnIdx],
+) -> Vec<u8> {
+    let names = columns
+        .iter()
+        .map(|c| viewer.column_name(c.0).into_owned())
+        .collect::<Vec<_>>();
+
+    let mut encode = text_encoder(viewer);
+    let cells = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|c| {
+                    let mut text = String::new();
+                    encode(row, c.0, &mut text);
+                    text
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    super::xlsx::write_workbook(&names, &cells)
+}
+
+/// Lays out `rows` in a headless egui context through [`RowViewer::show_cell_view`], then
+/// converts the painted shapes into an SVG document.
+fn render_svg<R>(
+    viewer: &mut (impl RowViewer<R> + ?Sized),
+    rows: &[&R],
+    columns: &[ColumnIdx],
+    opts: &ImageExportOptions,
+) -> String {
+    let num_rows = rows.len() + opts.header as usize;
+    let size = egui::vec2(
+        opts.column_width * columns.len() as f32,
+        opts.row_height * num_rows as f32,
+    );
+
+    let ctx = egui::Context::default();
+    ctx.set_visuals(if opts.dark_mode {
+        egui::Visuals::dark()
+    } else {
+        egui::Visuals::light()
+    });
+
+    let input = egui::RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, size)),
+        ..Default::default()
+    };
+
+    let output = ctx.run(input, |ctx| {
+        let frame = egui::Frame::none().fill(ctx.style().visuals.panel_fill);
+
+        egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+            let stroke = ui.visuals().widgets.noninteractive.bg_stroke;
+            let cell_rect = |vis_row: usize, vis_col: usize| {
+                Rect::from_min_size(
+                    Pos2::new(
+                        vis_col as f32 * opts.column_width,
+                        vis_row as f32 * opts.row_height,
+                    ),
+                    egui::vec2(opts.column_width, opts.row_height),
+                )
+            };
+            let cell_ui = |ui: &mut egui::Ui, rect: Rect| {
+                let mut ui = ui.new_child(
+                    UiBuilder::new()
+                        .max_rect(rect.shrink2(egui::vec2(4., 0.)))
+                        .layout(Layout::left_to_right(Align::Center)),
+                );
+                ui.set_clip_rect(rect);
+                ui
+            };
+
+            for (vis_col, column) in columns.iter().enumerate() {
+                if opts.header {
+                    let name = viewer.column_name(column.0);
+                    cell_ui(ui, cell_rect(0, vis_col)).strong(name);
+                }
+
+                for (vis_row, row) in rows.iter().enumerate() {
+                    let rect = cell_rect(vis_row + opts.header as usize, vis_col);
+                    let style = viewer.cell_style(row, column.0).unwrap_or_default();
+
+                    if let Some(bg) = style.bg {
+                        ui.painter().rect_filled(rect, 0., bg);
+                    }
+
+                    super::show_cell_content(&mut cell_ui(ui, rect), viewer, row, *column, &style);
+                }
+            }
+
+            for vis_row in 1..num_rows {
+                let y = vis_row as f32 * opts.row_height;
+                ui.painter().hline(0.0..=size.x, y, stroke);
+            }
+
+            for vis_col in 1..columns.len() {
+                let x = vis_col as f32 * opts.column_width;
+                ui.painter().vline(x, 0.0..=size.y, stroke);
+            }
+        });
+    });
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = size.x,
+        h = size.y,
+    );
+
+    let mut clip = None;
+    for (index, shape) in output.shapes.iter().enumerate() {
+        if clip != Some(shape.clip_rect) {
+            if clip.is_some() {
+                svg.push_str("</g>\n");
+            }
+
+            let r = shape
+                .clip_rect
+                .intersect(Rect::from_min_size(Pos2::ZERO, size));
+            let _ = writeln!(
+                svg,
+                r#"<clipPath id="c{index}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+                r.min.x,
+                r.min.y,
+                r.width().max(0.),
+                r.height().max(0.),
+            );
+            let _ = writeln!(svg, r#"<g clip-path="url(#c{index})">"#);
+            clip = Some(shape.clip_rect);
+        }
+
+        write_shape(&mut svg, &shape.shape);
+    }
+
+    if clip.is_some() {
+        svg.push_str("</g>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Writes supported shapes as SVG elements; meshes, curves and callbacks are skipped.
+fn write_shape(svg: &mut String, shape: &Shape) {
+    match shape {
+        Shape::Vec(shapes) => shapes.iter().for_each(|x| write_shape(svg, x)),
+        Shape::Rect(RectShape {
+            rect,
+            rounding,
+            fill,
+            stroke,
+            ..
+        }) => {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" {} {}/>"#,
+                rect.min.x,
+                rect.min.y,
+                rect.width(),
+                rect.height(),
+                rounding.nw,
+                paint("fill", *fill),
+                stroke_paint(*stroke),
+            );
+        }
+        Shape::LineSegment {
+            points: [a, b],
+            stroke,
+        } => {
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {}/>"#,
+                a.x,
+                a.y,
+                b.x,
+                b.y,
+                stroke_paint(solid_stroke(stroke)),
+            );
+        }
+        Shape::Circle(circle) => {
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}" {} {}/>"#,
+                circle.center.x,
+                circle.center.y,
+                circle.radius,
+                paint("fill", circle.fill),
+                stroke_paint(circle.stroke),
+            );
+        }
+        Shape::Path(PathShape {
+            points,
+            closed,
+            fill,
+            stroke,
+        }) => {
+            let points = points.iter().fold(String::new(), |mut s, p| {
+                let _ = write!(s, "{},{} ", p.x, p.y);
+                s
+            });
+            let _ = writeln!(
+                svg,
+                r#"<{tag} points="{points}" {} {}/>"#,
+                paint("fill", *fill),
+                stroke_paint(solid_stroke(stroke)),
+                tag = if *closed { "polygon" } else { "polyline" },
+            );
+        }
+        Shape::Text(TextShape {
+            pos,
+            galley,
+            fallback_color,
+            override_text_color,
+            opacity_factor,
+            ..
+        }) => {
+            for row in &galley.rows {
+                let Some(glyph) = row.glyphs.first() else {
+                    continue;
+                };
+
+                let format = &galley.job.sections[glyph.section_index as usize].format;
+                let color = override_text_color
+                    .or((format.color != Color32::PLACEHOLDER).then_some(format.color))
+                    .unwrap_or(*fallback_color)
+                    .gamma_multiply(*opacity_factor);
+                let family = match format.font_id.family {
+                    egui::FontFamily::Monospace => "monospace",
+                    _ => "sans-serif",
+                };
+
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}" font-family="{family}" font-size="{}" xml:space="preserve" {}>{}</text>"#,
+                    pos.x + glyph.pos.x,
+                    pos.y + glyph.pos.y,
+                    format.font_id.size,
+                    paint("fill", color),
+                    escape(&row.text()),
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn paint(attr: &str, color: Color32) -> String {
+    if color.a() == 0 {
+        return f!(r#"{attr}="none""#);
+    }
+
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    f!(
+        r##"{attr}="#{r:02x}{g:02x}{b:02x}" {attr}-opacity="{:.3}""##,
+        a as f32 / 255.
+    )
+}
+
+fn stroke_paint(stroke: Stroke) -> String {
+    if stroke.is_empty() {
+        return paint("stroke", Color32::TRANSPARENT);
+    }
+
+    f!(
+        r#"{} stroke-width="{}""#,
+        paint("stroke", stroke.color),
+        stroke.width
+    )
+}
+
+/// Gradient strokes are not supported, thus omitted.
+fn solid_stroke(stroke: &PathStroke) -> Stroke {
+    match stroke.color {
+        ColorMode::Solid(color) => Stroke::new(stroke.width, color),
+        ColorMode::UV(_) => Stroke::NONE,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_svg, ImageExportOptions};
+    use crate::{
+        draw::state::ColumnIdx,
+        viewer::{DecodeErrorBehavior, RowCodec},
+    };
+
+    /// Rows are names; the codec is provided only if the field is true.
+    struct Names(bool);
+
+    impl crate::RowViewer<&'static str> for Names {
+        type CellValue = ();
+
+        fn num_columns(&mut self) -> usize {
+            1
+        }
+
+        fn column_name(&mut self, _: usize) -> std::borrow::Cow<'static, str> {
+            "Name".into()
+        }
+
+        fn try_create_codec(&mut self, _: bool) -> Option<Box<dyn RowCodec<&'static str> + '_>> {
+            self.0.then(|| Box::new(Names(true)) as _)
+        }
+
+        fn cell_display_text(&mut self, row: &&'static str, _: usize) -> Option<String> {
+            Some(row.to_uppercase())
+        }
+
+        fn show_cell_view(&mut self, ui: &mut egui::Ui, row: &&'static str, _: usize) {
+            ui.label(*row);
+        }
+
+        fn show_cell_editor(
+            &mut self,
+            _: &mut egui::Ui,
+            _: &mut &'static str,
+            _: usize,
+        ) -> Option<egui::Response> {
+            None
+        }
+
+        fn set_cell_value(&mut self, src: &&'static str, dst: &mut &'static str, _: usize) {
+            *dst = src;
+        }
+
+        fn new_empty_row(&mut self) -> &'static str {
+            ""
+        }
+    }
+
+    impl RowCodec<&'static str> for Names {
+        fn create_empty_decoded_row(&mut self) -> &'static str {
+            ""
+        }
+
+        fn encode_column(&mut self, src_row: &&'static str, _: usize, dst: &mut String) {
+            dst.push_str(src_row);
+        }
+
+        fn decode_column(
+            &mut self,
+            _: &str,
+            _: usize,
+            _: &mut &'static str,
+        ) -> Result<(), DecodeErrorBehavior> {
+            Err(DecodeErrorBehavior::SkipRow)
+        }
+    }
+
+    #[test]
+    fn pretty_string() {
+        let mut table = crate::DataTable::from_iter(["Tom", "Jerry", "Spike"]);
+        let text = table.to_pretty_string(&mut Names(true), 2).unwrap();
+
+        assert_eq!(
+            text,
+            "┌───────┐\n\
+             │ Name  │\n\
+             ├───────┤\n\
+             │ Tom   │\n\
+             │ Jerry │\n\
+             └───────┘\n\
+             … 1 more rows\n"
+        );
+
+        let text = table.to_pretty_string(&mut Names(false), 1).unwrap();
+        assert!(text.contains("│ TOM  │"));
+    }
+
+    #[test]
+    fn svg_contains_cells() {
+        let opts = ImageExportOptions::default();
+        let svg = render_svg(&mut Names(false), &[&"Tom & Jerry"], &[ColumnIdx(0)], &opts);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(">Name</text>"));
+        assert!(svg.contains(">Tom &amp; Jerry</text>"));
+    }
+}
@@ -0,0 +1,397 @@
+//! Preview of rows parsed from imported data, e.g. the contents of a CSV or JSON file, before
+//! they're inserted into the table.
+
+use std::borrow::Cow;
+
+use egui::{Color32, RichText};
+use itertools::Itertools;
+
+use crate::{
+    viewer::{DecodeErrorBehavior, EmptyRowCreateContext, RowStatus},
+    DataTable, RowViewer,
+};
+
+use super::{delimited, json, state::ColumnIdx, Renderer, Style};
+
+/// Outcome of decoding a record of the imported data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportStatus {
+    /// Decoded; inserted on [`ImportPreview::commit`].
+    Ok,
+
+    /// The record is empty, or the codec skipped it with [`DecodeErrorBehavior::SkipRow`].
+    Skip,
+
+    /// The codec aborted, or [`RowViewer::validate_cell`] rejected a cell of the decoded row.
+    Error(Cow<'static, str>),
+}
+
+/// Rows parsed from imported data, shown in a read-only table with the status of each row until
+/// the user confirms them. Confirmed rows are appended to the table by [`Self::commit`], as a
+/// single undoable command.
+///
+/// ```ignore
+/// if let Some(preview) = &mut self.import {
+///     match preview.show(ui) {
+///         Some(true) => {
+///             self.import.take().unwrap().commit(&mut self.table, &mut self.viewer);
+///         }
+///         Some(false) => self.import = None,
+///         None => {}
+///     }
+/// }
+/// ```
+pub struct ImportPreview<R> {
+    /// Decoded rows by the records; `None` unless the status is [`ImportStatus::Ok`].
+    rows: Vec<Option<R>>,
+    table: DataTable<PreviewRow>,
+    viewer: PreviewViewer,
+}
+
+impl<R> ImportPreview<R> {
+    /// Parses `data` with the codec of the viewer. JSON records are mapped by the column keys;
+    /// delimited text in [`crate::viewer::RowCodec::clipboard_format`] is mapped by the header
+    /// row if it consists of the column keys, otherwise by the position. Columns missing from a
+    /// record take [`RowViewer::default_cell_value`].
+    ///
+    /// Returns `None` if the viewer has no codec.
+    pub fn parse<V: RowViewer<R> + ?Sized>(viewer: &mut V, data: &str) -> Option<Self> {
+        let num_columns = viewer.num_columns();
+        let keys = (0..num_columns)
+            .map(|col| viewer.column_key(col).into_owned())
+            .collect::<Vec<_>>();
+        let names = (0..num_columns)
+            .map(|col| viewer.column_name(col))
+            .collect::<Vec<_>>();
+        let skipped = (0..num_columns)
+            .map(|col| viewer.is_computed_column(col))
+            .collect::<Vec<_>>();
+
+        let mut codec = viewer.try_create_codec(false)?;
+        let records = parse_records(data, &codec.clipboard_format(), &keys);
+        let mut decoded = Vec::with_capacity(records.len());
+
+        for record in &records {
+            if record.iter().all(|(_, text)| text.is_empty()) {
+                decoded.push((ImportStatus::Skip, None));
+                continue;
+            }
+
+            let mut row = codec.create_empty_decoded_row();
+            let mut status = ImportStatus::Ok;
+
+            for &(col, ref text) in record {
+                if skipped[col] || !codec.supports_column(col) {
+                    continue;
+                }
+
+                match codec.decode_column(text, col, &mut row) {
+                    Ok(()) | Err(DecodeErrorBehavior::SkipCell) => {}
+                    Err(DecodeErrorBehavior::SkipRow) => {
+                        status = ImportStatus::Skip;
+                        break;
+                    }
+                    Err(DecodeErrorBehavior::Abort) => {
+                        status = ImportStatus::Error(format!("Invalid {}", names[col]).into());
+                        break;
+                    }
+                }
+            }
+
+            decoded.push((status, Some(row)));
+        }
+
+        drop(codec);
+
+        let mut rows = Vec::with_capacity(records.len());
+        let mut preview = Vec::with_capacity(records.len());
+
+        for (record, (mut status, row)) in records.into_iter().zip(decoded) {
+            let row = row
+                .filter(|_| status == ImportStatus::Ok)
+                .and_then(|mut row| {
+                    let given = record.iter().map(|x| ColumnIdx(x.0)).collect::<Vec<_>>();
+                    super::state::UiState::<R>::fill_default_cells(
+                        viewer,
+                        &mut row,
+                        num_columns,
+                        &given,
+                        EmptyRowCreateContext::InsertNewLine,
+                    );
+
+                    let error =
+                        (0..num_columns).find_map(|col| viewer.validate_cell(&row, col).err());
+                    match error {
+                        Some(error) => {
+                            status = ImportStatus::Error(error.message);
+                            None
+                        }
+                        None => Some(row),
+                    }
+                });
+
+            let mut cells = vec![String::new(); num_columns];
+            for (col, text) in record {
+                cells[col] = text;
+            }
+
+            rows.push(row);
+            preview.push(PreviewRow { status, cells });
+        }
+
+        Some(Self {
+            rows,
+            table: preview.into_iter().collect(),
+            viewer: PreviewViewer { names },
+        })
+    }
+
+    /// Status of each record, in the order of the imported data.
+    pub fn statuses(&self) -> impl Iterator<Item = &ImportStatus> {
+        self.table.iter().map(|x| &x.status)
+    }
+
+    /// Number of rows to be inserted on [`Self::commit`].
+    pub fn num_ok_rows(&self) -> usize {
+        self.rows.iter().flatten().count()
+    }
+
+    /// Renders the counts of each status, the buttons to confirm or cancel the import, and the
+    /// read-only table of the records. Returns `Some(true)` once the user confirms the import,
+    /// and `Some(false)` once cancelled.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<bool> {
+        let (mut n_skip, mut n_error) = (0, 0);
+
+        for status in self.statuses() {
+            match status {
+                ImportStatus::Ok => {}
+                ImportStatus::Skip => n_skip += 1,
+                ImportStatus::Error(_) => n_error += 1,
+            }
+        }
+
+        let n_ok = self.num_ok_rows();
+        let mut decision = None;
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{n_ok} ok, {n_skip} skipped, {n_error} errors"));
+            ui.separator();
+
+            let import = egui::Button::new(format!("Import {n_ok} Rows"));
+            if ui.add_enabled(n_ok > 0, import).clicked() {
+                decision = Some(true);
+            }
+
+            if ui.button("Cancel").clicked() {
+                decision = Some(false);
+            }
+        });
+
+        ui.separator();
+        Renderer::new(&mut self.table, &mut self.viewer)
+            .with_style(Style {
+                read_only: true,
+                enable_undo: false,
+                ..Default::default()
+            })
+            .show(ui);
+
+        decision
+    }
+
+    /// Appends the rows of [`ImportStatus::Ok`] to `table` as a single undoable command. Returns
+    /// the number of inserted rows.
+    pub fn commit<V: RowViewer<R> + ?Sized>(
+        self,
+        table: &mut DataTable<R>,
+        viewer: &mut V,
+    ) -> usize {
+        let rows = self.rows.into_iter().flatten().collect();
+        table.insert_rows(viewer, usize::MAX, rows)
+    }
+}
+
+/// Cells of the record by the columns, as pairs of the column and the text.
+fn parse_records(
+    data: &str,
+    format: &crate::viewer::ClipboardFormat,
+    keys: &[String],
+) -> Vec<Vec<(usize, String)>> {
+    let unique_keys = !keys.is_empty() && keys.iter().all_unique();
+
+    let records = unique_keys.then(|| json::parse_records(data)).flatten();
+    if let Some(records) = records {
+        return Vec::from_iter(records.into_iter().map(|record| {
+            Vec::from_iter(
+                record.into_iter().filter_map(|(key, value)| {
+                    Some((keys.iter().position(|x| *x == key)?, value?))
+                }),
+            )
+        }));
+    }
+
+    let view = delimited::ParsedTable::parse(data, format);
+
+    // Header row maps the cells to the columns, same as pasted contents.
+    let header = unique_keys
+        .then(|| view.iter_rows().next())
+        .flatten()
+        .and_then(|(_, cells)| {
+            cells
+                .map(|(_, key)| keys.iter().position(|x| x == key))
+                .collect::<Option<Vec<_>>>()
+        });
+
+    let n_header_rows = header.is_some() as usize;
+    let mut records = Vec::new();
+
+    for (_, cells) in view.iter_rows().skip(n_header_rows) {
+        // Cells out of the columns are ignored.
+        let record = cells.filter_map(|(column, text)| {
+            let col = match &header {
+                Some(header) => *header.get(column)?,
+                None => column,
+            };
+
+            (col < keys.len()).then(|| (col, text.to_owned()))
+        });
+
+        records.push(record.collect());
+    }
+
+    records
+}
+
+/* ---------------------------------------------------------------------------------------------- */
+/*                                          PREVIEW TABLE                                         */
+/* ---------------------------------------------------------------------------------------------- */
+
+#[derive(Clone)]
+struct PreviewRow {
+    status: ImportStatus,
+    cells: Vec<String>,
+}
+
+/// The first column shows the status, followed by the source texts of the columns.
+struct PreviewViewer {
+    names: Vec<Cow<'static, str>>,
+}
+
+impl PreviewViewer {
+    fn status_text(status: &ImportStatus) -> (&'static str, Color32) {
+        match status {
+            ImportStatus::Ok => ("Ok", Color32::from_rgb(0, 158, 115)),
+            ImportStatus::Skip => ("Skip", Color32::GRAY),
+            ImportStatus::Error(_) => ("Error", Color32::from_rgb(213, 94, 0)),
+        }
+    }
+}
+
+impl RowViewer<PreviewRow> for PreviewViewer {
+    type CellValue = ();
+
+    fn num_columns(&mut self) -> usize {
+        self.names.len() + 1
+    }
+
+    fn column_name(&mut self, column: usize) -> Cow<'static, str> {
+        match column {
+            0 => "Status".into(),
+            _ => self.names[column - 1].clone(),
+        }
+    }
+
+    fn show_cell_view(&mut self, ui: &mut egui::Ui, row: &PreviewRow, column: usize) {
+        match column {
+            0 => {
+                let (text, color) = Self::status_text(&row.status);
+                ui.label(RichText::new(text).color(color));
+            }
+            _ => {
+                ui.label(&row.cells[column - 1]);
+            }
+        }
+    }
+
+    fn cell_tooltip(&mut self, row: &PreviewRow, column: usize) -> Option<egui::WidgetText> {
+        match &row.status {
+            ImportStatus::Error(message) if column == 0 => Some(message.to_string().into()),
+            _ => None,
+        }
+    }
+
+    fn cell_text<'a>(&mut self, row: &'a PreviewRow, column: usize) -> Option<Cow<'a, str>> {
+        match column {
+            0 => Some(Self::status_text(&row.status).0.into()),
+            _ => Some(row.cells[column - 1].as_str().into()),
+        }
+    }
+
+    fn row_status(&mut self, row: &PreviewRow) -> RowStatus {
+        match row.status {
+            ImportStatus::Ok => RowStatus::None,
+            ImportStatus::Skip => RowStatus::Custom("–", Color32::GRAY),
+            ImportStatus::Error(_) => RowStatus::Error,
+        }
+    }
+
+    fn show_cell_editor(
+        &mut self,
+        _: &mut egui::Ui,
+        _: &mut PreviewRow,
+        _: usize,
+    ) -> Option<egui::Response> {
+        None
+    }
+
+    fn set_cell_value(&mut self, src: &PreviewRow, dst: &mut PreviewRow, column: usize) {
+        match column {
+            0 => dst.status = src.status.clone(),
+            _ => dst.cells[column - 1].clone_from(&src.cells[column - 1]),
+        }
+    }
+
+    fn confirm_cell_write_by_ui(
+        &mut self,
+        _: &PreviewRow,
+        _: &PreviewRow,
+        _: usize,
+        _: crate::viewer::CellWriteContext,
+    ) -> bool {
+        false
+    }
+
+    fn confirm_row_deletion_by_ui(&mut self, _: &PreviewRow) -> bool {
+        false
+    }
+
+    fn new_empty_row(&mut self) -> PreviewRow {
+        PreviewRow {
+            status: ImportStatus::Skip,
+            cells: vec![String::new(); self.names.len()],
+        }
+    }
+}
+
+#[test]
+fn import_records_mapping() {
+    let keys = ["name", "age"].map(String::from);
+    let format = crate::viewer::ClipboardFormat::CSV;
+
+    let records = parse_records("age,name\n31,Alice\n\n7,Bob,extra", &format, &keys);
+    assert_eq!(
+        records,
+        [
+            vec![(1, "31".into()), (0, "Alice".into())],
+            vec![],
+            vec![(1, "7".into()), (0, "Bob".into())],
+        ]
+    );
+
+    // Without the header, cells are mapped by the position.
+    let records = parse_records("Alice,31,x", &format, &keys);
+    assert_eq!(records, [vec![(0, "Alice".into()), (1, "31".into())]]);
+
+    let records = parse_records(r#"[{"age": 31, "unknown": 1}]"#, &format, &keys);
+    assert_eq!(records, [vec![(1, "31".into())]]);
+}
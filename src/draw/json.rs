@@ -0,0 +1,338 @@
+//! A short implementation for reading and writing JSON records; array of objects keyed by the
+//! column keys, used by [`ClipboardFormat::json`](crate::viewer::ClipboardFormat::json).
+
+/// Writes `text` as a JSON string literal.
+pub fn write_string(buf: &mut String, text: &str) {
+    buf.reserve(text.len() + 2);
+    buf.push('"');
+
+    for char in text.chars() {
+        match char {
+            '"' => buf.push_str(r#"\""#),
+            '\\' => buf.push_str(r"\\"),
+            '\n' => buf.push_str(r"\n"),
+            '\r' => buf.push_str(r"\r"),
+            '\t' => buf.push_str(r"\t"),
+            ch if (ch as u32) < 0x20 => {
+                buf.push_str(&format!(r"\u{:04x}", ch as u32));
+            }
+            ch => buf.push(ch),
+        }
+    }
+
+    buf.push('"');
+}
+
+/// Writes the encoded cell; numbers and booleans keep their types, and others are written as
+/// strings.
+pub fn write_value(buf: &mut String, text: &str) {
+    if text == "true" || text == "false" || is_number(text) {
+        buf.push_str(text);
+    } else {
+        write_string(buf, text);
+    }
+}
+
+fn is_number(text: &str) -> bool {
+    fn digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> usize {
+        let mut n = 0;
+
+        while chars.next_if(char::is_ascii_digit).is_some() {
+            n += 1;
+        }
+
+        n
+    }
+
+    let mut chars = text.strip_prefix('-').unwrap_or(text).chars().peekable();
+
+    // Leading zeros are not allowed, except for the zero itself.
+    match chars.peek() {
+        Some('0') => {
+            chars.next();
+        }
+        Some('1'..='9') => {
+            digits(&mut chars);
+        }
+        _ => return false,
+    }
+
+    if chars.next_if_eq(&'.').is_some() && digits(&mut chars) == 0 {
+        return false;
+    }
+
+    if chars.next_if(|x| matches!(x, 'e' | 'E')).is_some() {
+        chars.next_if(|x| matches!(x, '+' | '-'));
+
+        if digits(&mut chars) == 0 {
+            return false;
+        }
+    }
+
+    chars.next().is_none()
+}
+
+/* ============================================================================================== */
+/*                                             READER                                             */
+/* ============================================================================================== */
+
+/// Cells of a record, as pairs of the key and the text of the value. `null` values are kept as
+/// [`None`], and nested arrays or objects are kept as their raw JSON text.
+pub type Record = Vec<(String, Option<String>)>;
+
+/// Parses the array of objects. Returns [`None`] if the data is not a valid JSON, or not an
+/// array of objects.
+pub fn parse_records(data: &str) -> Option<Vec<Record>> {
+    let mut r = Reader { src: data, pos: 0 };
+    let mut records = Vec::new();
+
+    r.expect('[')?;
+
+    if !r.eat(']') {
+        loop {
+            let mut record = Record::new();
+            r.expect('{')?;
+
+            if !r.eat('}') {
+                loop {
+                    r.skip_ws();
+                    let key = r.string()?;
+                    r.expect(':')?;
+                    record.push((key, r.value()?));
+
+                    if r.eat('}') {
+                        break;
+                    }
+
+                    r.expect(',')?;
+                }
+            }
+
+            records.push(record);
+
+            if r.eat(']') {
+                break;
+            }
+
+            r.expect(',')?;
+        }
+    }
+
+    r.skip_ws();
+    (r.pos == data.len()).then_some(records)
+}
+
+/// Nesting limit of the arrays and objects within a value; deeper values fail the parsing
+/// instead of overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Reader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn skip_ws(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|x| matches!(x, ' ' | '\t' | '\n' | '\r'))
+        {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips whitespaces, then consumes `ch` if it's the next character.
+    fn eat(&mut self, ch: char) -> bool {
+        self.skip_ws();
+        let eaten = self.peek() == Some(ch);
+        self.pos += eaten as usize;
+        eaten
+    }
+
+    fn expect(&mut self, ch: char) -> Option<()> {
+        self.eat(ch).then_some(())
+    }
+
+    fn string(&mut self) -> Option<String> {
+        (self.bump()? == '"').then_some(())?;
+        let mut out = String::new();
+
+        loop {
+            match self.bump()? {
+                '"' => return Some(out),
+                '\\' => match self.bump()? {
+                    '"' => out.push('"'),
+                    '\\' => out.push('\\'),
+                    '/' => out.push('/'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    't' => out.push('\t'),
+                    'u' => {
+                        let mut code = self.hex4()?;
+
+                        // Characters out of the basic plane are written as surrogate pairs.
+                        if (0xD800..0xDC00).contains(&code) {
+                            (self.bump()? == '\\' && self.bump()? == 'u').then_some(())?;
+                            let low = self.hex4()?;
+                            (0xDC00..0xE000).contains(&low).then_some(())?;
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+
+                        out.push(char::from_u32(code)?);
+                    }
+                    _ => return None,
+                },
+                ch if (ch as u32) < 0x20 => return None,
+                ch => out.push(ch),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let hex = self.src.get(self.pos..self.pos + 4)?;
+        self.pos += 4;
+        u32::from_str_radix(hex, 16).ok()
+    }
+
+    /// Text of the value; see [`Record`].
+    fn value(&mut self) -> Option<Option<String>> {
+        self.skip_ws();
+        let start = self.pos;
+
+        match self.peek()? {
+            '"' => self.string().map(Some),
+            '[' | '{' => {
+                self.skip_value(0)?;
+                Some(Some(self.src[start..self.pos].to_owned()))
+            }
+            _ => {
+                let text = self.scalar()?;
+                Some((text != "null").then(|| text.to_owned()))
+            }
+        }
+    }
+
+    fn skip_value(&mut self, depth: usize) -> Option<()> {
+        self.skip_ws();
+
+        match self.peek()? {
+            '"' => self.string().map(drop),
+            open @ ('[' | '{') if depth < MAX_DEPTH => {
+                let close = if open == '[' { ']' } else { '}' };
+                self.pos += 1;
+
+                if self.eat(close) {
+                    return Some(());
+                }
+
+                loop {
+                    if open == '{' {
+                        self.skip_ws();
+                        self.string()?;
+                        self.expect(':')?;
+                    }
+
+                    self.skip_value(depth + 1)?;
+
+                    if self.eat(close) {
+                        return Some(());
+                    }
+
+                    self.expect(',')?;
+                }
+            }
+            '[' | '{' => None,
+            _ => self.scalar().map(drop),
+        }
+    }
+
+    /// Number, boolean or `null`.
+    fn scalar(&mut self) -> Option<&str> {
+        let start = self.pos;
+
+        while self
+            .peek()
+            .is_some_and(|x| x.is_ascii_alphanumeric() || matches!(x, '-' | '+' | '.'))
+        {
+            self.pos += 1;
+        }
+
+        let text = &self.src[start..self.pos];
+        matches!(text, "true" | "false" | "null")
+            .then_some(text)
+            .or_else(|| is_number(text).then_some(text))
+    }
+}
+
+#[test]
+fn json_round_trip() {
+    let cells = [
+        "Alice",
+        "31",
+        "true",
+        "tab\there \"quoted\"\n",
+        "-1.5e3",
+        "01",
+        "😀",
+    ];
+    let mut buf = String::from("[{");
+
+    for (i, text) in cells.iter().enumerate() {
+        if i > 0 {
+            buf.push(',');
+        }
+
+        write_string(&mut buf, &i.to_string());
+        buf.push(':');
+        write_value(&mut buf, text);
+    }
+
+    buf.push_str("}, {}]");
+    assert!(buf.contains(r#""1":31,"2":true"#));
+    assert!(buf.contains(r#""5":"01""#));
+
+    let records = parse_records(&buf).unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(records[1].is_empty());
+
+    for (i, (key, value)) in records[0].iter().enumerate() {
+        assert_eq!(key, &i.to_string());
+        assert_eq!(value.as_deref(), Some(cells[i]));
+    }
+}
+
+#[test]
+fn json_records_parsing() {
+    let records = parse_records(r#" [ {"a": null, "b": [1, {"c": "]"}], "d": "😀\/"} ] "#).unwrap();
+
+    assert_eq!(records[0][0], ("a".into(), None));
+    assert_eq!(
+        records[0][1],
+        ("b".into(), Some(r#"[1, {"c": "]"}]"#.into()))
+    );
+    assert_eq!(records[0][2], ("d".into(), Some("😀/".into())));
+
+    // Not an array of objects.
+    assert!(parse_records("[1, 2]").is_none());
+    assert!(parse_records(r#"{"a": 1}"#).is_none());
+    assert!(parse_records("[{\"a\": 1}] trailing").is_none());
+    assert!(parse_records("Hello\tWorld").is_none());
+
+    // Nesting past the limit is rejected, not overflowing the stack.
+    let nested = |depth| format!(r#"[{{"a": {}{}}}]"#, "[".repeat(depth), "]".repeat(depth));
+    assert!(parse_records(&nested(MAX_DEPTH)).is_some());
+    assert!(parse_records(&nested(MAX_DEPTH + 1)).is_none());
+    assert!(parse_records(&nested(100_000)).is_none());
+}
@@ -0,0 +1,212 @@
+use std::{marker::PhantomData, ops::Range};
+
+use egui::{Align, Color32, Layout, Response, RichText, Sense, Widget};
+use egui_extras::Column;
+
+use crate::{source::RowSource, RowViewer};
+
+use super::{show_cell_content, state::ColumnIdx, Style};
+
+/// Read-only renderer of rows fetched lazily from a [`RowSource`]. Only the visible window of
+/// rows is rendered and requested from the source, along with a prefetch margin; rows which
+/// are not available yet are rendered as shimmering skeleton cells, and the table keeps
+/// repainting until they arrive.
+///
+/// Since rows are not in memory, editing, sorting, filtering and undo are not supported; let the
+/// source do sorting and filtering. The row height is fixed to [`Style::table_row_height`] or
+/// 20 pixels if it's not specified.
+pub struct LazyRenderer<'a, R, S: RowSource<R>, V: RowViewer<R> + ?Sized> {
+    source: &'a mut S,
+    viewer: &'a mut V,
+    style: Style,
+    prefetch: usize,
+    _row: PhantomData<fn() -> R>,
+}
+
+impl<R, S: RowSource<R>, V: RowViewer<R> + ?Sized> Widget for LazyRenderer<'_, R, S, V> {
+    fn ui(self, ui: &mut egui::Ui) -> Response {
+        self.show(ui)
+    }
+}
+
+impl<'a, R, S: RowSource<R>, V: RowViewer<R> + ?Sized> LazyRenderer<'a, R, S, V> {
+    pub fn new(source: &'a mut S, viewer: &'a mut V) -> Self {
+        Self {
+            source,
+            viewer,
+            style: Default::default(),
+            prefetch: 50,
+            _row: PhantomData,
+        }
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Number of rows requested above and below the visible window. Defaults to 50.
+    pub fn with_prefetch(mut self, rows: usize) -> Self {
+        self.prefetch = rows;
+        self
+    }
+
+    pub fn show(self, ui: &mut egui::Ui) -> Response {
+        egui::ScrollArea::horizontal()
+            .show(ui, |ui| ui.scope(|ui| self.impl_show(ui)).response)
+            .inner
+    }
+
+    fn impl_show(self, ui: &mut egui::Ui) {
+        let Self {
+            source,
+            viewer,
+            style,
+            prefetch,
+            ..
+        } = self;
+
+        let num_rows = source.num_rows();
+        let sortable = source.supports_sorting();
+        let sorted_by = source.sorted_by();
+        let mut sort_request = None;
+        let num_columns = viewer.num_columns();
+        let row_height = style.table_row_height.unwrap_or(20.);
+        let row_digits = num_rows.max(1).ilog10() as usize + 1;
+        let mut visible = None::<Range<usize>>;
+        let mut any_placeholder = false;
+        let time = ui.input(|i| i.time);
+        let skeleton_color = ui.visuals().widgets.noninteractive.bg_fill;
+
+        let mut builder = egui_extras::TableBuilder::new(ui).column(Column::auto());
+        for column in 0..num_columns {
+            builder =
+                builder.column(viewer.column_render_config(column, column + 1 == num_columns));
+        }
+
+        builder
+            .striped(true)
+            .max_scroll_height(f32::MAX)
+            .header(20., |mut h| {
+                h.col(|_| {});
+
+                for column in 0..num_columns {
+                    h.col(|ui| {
+                        let sortable = sortable && viewer.is_sortable_column(column);
+                        let resp = ui.interact(
+                            ui.max_rect(),
+                            ui.id().with(column),
+                            if sortable {
+                                Sense::click()
+                            } else {
+                                Sense::hover()
+                            },
+                        );
+
+                        ui.horizontal_centered(|ui| {
+                            match sorted_by {
+                                Some((col, asc)) if col == column => {
+                                    ui.monospace(["↘", "↗"][asc as usize]);
+                                }
+                                _ => {
+                                    ui.monospace(" ");
+                                }
+                            }
+
+                            egui::Label::new(viewer.column_name(column))
+                                .selectable(false)
+                                .ui(ui);
+                        });
+
+                        if resp.clicked() {
+                            // Ascending first, then toggles the direction.
+                            let asc = sorted_by.is_none_or(|(col, asc)| col != column || !asc);
+                            sort_request = Some((column, asc));
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(row_height, num_rows, |mut row| {
+                    let index = row.index();
+                    let visible = visible.get_or_insert(index..index);
+                    visible.start = visible.start.min(index);
+                    visible.end = visible.end.max(index + 1);
+
+                    row.col(|ui| {
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            ui.separator();
+                            ui.monospace(
+                                RichText::from(format!("{:·>row_digits$}", index + 1)).weak(),
+                            );
+                        });
+                    });
+
+                    let data = source.row(index);
+                    any_placeholder |= data.is_none();
+
+                    for column in 0..num_columns {
+                        row.col(|ui| match data {
+                            Some(data) => {
+                                let strong = ui.visuals().strong_text_color();
+                                ui.style_mut()
+                                    .visuals
+                                    .widgets
+                                    .noninteractive
+                                    .fg_stroke
+                                    .color = strong;
+
+                                let cell_style =
+                                    viewer.cell_style(data, column).unwrap_or_default();
+                                if let Some(bg) = cell_style.bg {
+                                    ui.painter().rect_filled(
+                                        ui.max_rect(),
+                                        egui::Rounding::ZERO,
+                                        bg,
+                                    );
+                                }
+
+                                show_cell_content(ui, viewer, data, ColumnIdx(column), &cell_style);
+                            }
+                            None => {
+                                show_skeleton(ui, skeleton_color, time, index + column);
+                            }
+                        });
+                    }
+                });
+            });
+
+        if let Some((column, asc)) = sort_request {
+            source.sort(column, asc);
+            ui.ctx().request_repaint();
+        }
+
+        if any_placeholder {
+            // Keep the shimmer moving, and pick up the rows as soon as they're delivered.
+            ui.ctx().request_repaint();
+        }
+
+        if let Some(visible) = visible {
+            let start = visible.start.saturating_sub(prefetch);
+            let end = visible.end.saturating_add(prefetch).min(num_rows);
+            source.request_rows(start..end);
+        }
+    }
+}
+
+/// Placeholder bar of a cell which is not fetched yet. Brightness sweeps across the rows over
+/// time, and the width varies by `seed` so that the rows don't look identical.
+fn show_skeleton(ui: &mut egui::Ui, color: Color32, time: f64, seed: usize) {
+    let rect = ui
+        .max_rect()
+        .shrink2(egui::vec2(4., ui.max_rect().height() * 0.3));
+    let width = rect.width() * (0.5 + 0.4 * ((seed * 7919) % 100) as f32 / 100.);
+    let phase = (time * 1.5 - seed as f64 * 0.1).rem_euclid(2.) as f32;
+    let shimmer = 1. - (phase - 1.).abs();
+
+    ui.painter().rect_filled(
+        rect.with_max_x(rect.left() + width),
+        egui::Rounding::same(rect.height() * 0.5),
+        color.gamma_multiply(0.6 + 0.4 * shimmer),
+    );
+}
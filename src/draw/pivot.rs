@@ -0,0 +1,58 @@
+//! Group-by summary of the visible rows, shown in the pivot preview popup.
+
+use egui::ahash::{HashMap, HashMapExt};
+
+/// Summary of the rows sharing the same value of the group column.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PivotRow {
+    pub group: String,
+    pub count: usize,
+
+    /// Sum of the numeric values of the aggregate column; `None` if no row had one.
+    pub sum: Option<f64>,
+}
+
+/// Groups `(group, value)` pairs in the order of the first appearance of each group.
+pub(crate) fn aggregate(items: impl IntoIterator<Item = (String, Option<f64>)>) -> Vec<PivotRow> {
+    let mut rows = Vec::<PivotRow>::new();
+    let mut index_of = HashMap::new();
+
+    for (group, value) in items {
+        let index = *index_of.entry(group).or_insert_with_key(|group: &String| {
+            rows.push(PivotRow {
+                group: group.clone(),
+                count: 0,
+                sum: None,
+            });
+            rows.len() - 1
+        });
+
+        let row = &mut rows[index];
+        row.count += 1;
+
+        if let Some(value) = value {
+            *row.sum.get_or_insert(0.) += value;
+        }
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{aggregate, PivotRow};
+
+    #[test]
+    fn groups_in_visible_order() {
+        let items = [("b", Some(1.)), ("a", None), ("b", Some(2.5)), ("a", None)];
+        let rows = aggregate(items.map(|(g, v)| (g.to_owned(), v)));
+
+        let row = |group: &str, count, sum| PivotRow {
+            group: group.to_owned(),
+            count,
+            sum,
+        };
+
+        assert_eq!(rows, [row("b", 2, Some(3.5)), row("a", 2, None)]);
+    }
+}
@@ -0,0 +1,188 @@
+//! Series continuing the leading cells of a column, for [`crate::UiAction::FillSeries`]; numbers,
+//! ISO 8601 dates and texts ending with a number, e.g. `Item 7`.
+
+/// Term of the series. Dates are counted in days since the Unix epoch.
+enum Term<'a> {
+    Number {
+        value: f64,
+        decimals: usize,
+    },
+    Date(i64),
+    Text {
+        prefix: &'a str,
+        value: i64,
+        width: usize,
+    },
+}
+
+impl<'a> Term<'a> {
+    fn parse(text: &'a str) -> Option<Self> {
+        let text = text.trim();
+
+        if let Some(days) = parse_date(text) {
+            return Some(Self::Date(days));
+        }
+
+        if (text.chars()).all(|x| x.is_ascii_digit() || matches!(x, '-' | '+' | '.')) {
+            let value = text.parse::<f64>().ok()?;
+            let decimals = text.split_once('.').map_or(0, |x| x.1.len());
+            return Some(Self::Number { value, decimals });
+        }
+
+        let digits = text.len() - text.trim_end_matches(|x: char| x.is_ascii_digit()).len();
+        let (prefix, digits) = text.split_at(text.len() - digits);
+
+        Some(Self::Text {
+            prefix,
+            value: digits.parse().ok()?,
+            width: digits.len(),
+        })
+    }
+
+    /// Difference to the next term, if both are of the same kind.
+    fn step(&self, next: &Self) -> Option<f64> {
+        match (self, next) {
+            (Self::Number { value: a, .. }, Self::Number { value: b, .. }) => Some(b - a),
+            (Self::Date(a), Self::Date(b)) => Some((b - a) as f64),
+            (
+                Self::Text {
+                    prefix: pa,
+                    value: a,
+                    ..
+                },
+                Self::Text {
+                    prefix: pb,
+                    value: b,
+                    ..
+                },
+            ) if pa == pb => Some((b - a) as f64),
+            _ => None,
+        }
+    }
+
+    fn nth(&self, step: f64, n: usize) -> String {
+        let offset = step * n as f64;
+
+        match *self {
+            Self::Number { value, decimals } => format!("{:.*}", decimals, value + offset),
+            Self::Date(days) => format_date(days + offset as i64),
+            Self::Text {
+                prefix,
+                value,
+                width,
+            } => {
+                format!("{prefix}{:0width$}", (value + offset as i64).max(0))
+            }
+        }
+    }
+}
+
+/// Continues the leading cells; the first two cells give the step if they're of the same kind,
+/// otherwise the first cell is incremented by one. Returns the number of the leading cells and
+/// the values of the rest, or [`None`] if the first cell is not a term of any series.
+pub fn fill(cells: &[String]) -> Option<(usize, Vec<String>)> {
+    let first = Term::parse(cells.first()?)?;
+    let second = cells.get(1).and_then(|x| Term::parse(x));
+    let (n_leading, step) = match second.as_ref().and_then(|x| first.step(x)) {
+        Some(step) => (2, step),
+        None => (1, 1.),
+    };
+
+    // Decimals of the leading cells are kept, e.g. 0.5, 1.25 continues as 2.00.
+    let first = match (first, second) {
+        (Term::Number { value, decimals: a }, Some(Term::Number { decimals: b, .. }))
+            if n_leading == 2 =>
+        {
+            Term::Number {
+                value,
+                decimals: a.max(b),
+            }
+        }
+        (first, _) => first,
+    };
+
+    let values = (n_leading..cells.len()).map(|n| first.nth(step, n));
+    Some((n_leading, values.collect()))
+}
+
+fn parse_date(text: &str) -> Option<i64> {
+    let bytes = text.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+
+    let year: i64 = text[..4].parse().ok()?;
+    let month: u32 = text[5..7].parse().ok()?;
+    let day: u32 = text[8..].parse().ok()?;
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 => 28 + leap as u32,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+
+    (1..=days_in_month)
+        .contains(&day)
+        .then(|| days_from_civil(year, month, day))
+}
+
+fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Days since 1970-01-01 of the proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+#[test]
+fn series_fill() {
+    let fill = |cells: &[&str]| {
+        let cells: Vec<_> = cells.iter().map(|x| x.to_string()).collect();
+        fill(&cells)
+    };
+    let s = |x: &[&str]| x.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(fill(&["1", "", "", ""]), Some((1, s(&["2", "3", "4"]))));
+    assert_eq!(fill(&["10", "8", "", ""]), Some((2, s(&["6", "4"]))));
+    assert_eq!(fill(&["0.5", "1.25", ""]), Some((2, s(&["2.00"]))));
+    assert_eq!(
+        fill(&["Item 7", "x", ""]),
+        Some((1, s(&["Item 8", "Item 9"])))
+    );
+    assert_eq!(fill(&["A009", ""]), Some((1, s(&["A010"]))));
+    assert_eq!(
+        fill(&["2024-02-28", "", ""]),
+        Some((1, s(&["2024-02-29", "2024-03-01"])))
+    );
+    assert_eq!(
+        fill(&["2023-12-01", "2023-12-08", ""]),
+        Some((2, s(&["2023-12-15"])))
+    );
+    assert_eq!(fill(&["Hello", ""]), None);
+    assert_eq!(fill(&[]), None);
+}
@@ -1,11 +1,13 @@
 use std::{
+    borrow::Cow,
     collections::{BTreeMap, BTreeSet, VecDeque},
     hash::{Hash, Hasher},
     mem::{replace, take},
+    ops::Range,
 };
 
 use egui::{
-    ahash::{AHasher, HashMap, HashMapExt},
+    ahash::{AHasher, HashMap, HashMapExt, HashSet, HashSetExt},
     Modifiers,
 };
 use itertools::Itertools;
@@ -13,12 +15,19 @@ use tap::prelude::{Pipe, Tap};
 
 use crate::{
     default,
-    draw::tsv,
+    draw::{
+        delimited, json,
+        pivot::{self, PivotRow},
+        series,
+    },
+    model::TableModel,
     viewer::{
-        CellWriteContext, DecodeErrorBehavior, EmptyRowCreateContext, MoveDirection, RowCodec,
-        UiActionContext, UiCursorState,
+        text_encoder, CellWriteContext, ChangeEvent, ClipboardFormat, CommitPolicy,
+        DecodeErrorBehavior, EmptyRowCreateContext, MarkKind, MoveDirection, PasteDecision,
+        PasteSummary, TableChange, UiActionContext, UiCursorState, UndoKind, ValidationError,
+        VisibleCell,
     },
-    DataTable, RowViewer, UiAction,
+    DataTable, MacroReplay, RowViewer, UiAction,
 };
 
 macro_rules! int_ty {
@@ -141,6 +150,9 @@ pub(crate) struct UiState<R> {
     /// state is changed.
     viewer_filter_hash: u64,
 
+    /// Filter given by `Renderer::with_extra_filter`, with the hash of its key.
+    extra_filter: Option<(u64, ExtraFilter<R>)>,
+
     /// Undo queue.
     ///
     /// - Push tasks front of the queue.
@@ -151,11 +163,54 @@ pub(crate) struct UiState<R> {
     /// Undo cursor => increment by 1 on every undo, decrement by 1 on redo.
     undo_cursor: usize,
 
+    /// Capacity of undo queue given by the latest rendering. Used for commands which are
+    /// pushed outside of the rendering.
+    undo_capacity: usize,
+
+    /// Whether commands are recorded into the undo history, given by the latest rendering.
+    undo_enabled: bool,
+
+    /// Whether row deletion moves rows into the trash, given by the latest rendering.
+    soft_delete: bool,
+
+    /// Whether editing is left to the host, given by the latest rendering.
+    detached_editing: bool,
+
+    /// Cell the user wants to edit in detached editing mode, not taken by the host yet.
+    edit_request: Option<(RowIdx, ColumnIdx)>,
+
+    /// Whether rows of the undo history are stored encoded, given by the latest rendering.
+    compress_undo_history: bool,
+
     /// Clipboard contents.
     ///
     /// XXX: Should we move this into global storage?
     clipboard: Option<Clipboard<R>>,
 
+    /// Recorded keyboard macro.
+    macro_steps: Vec<MacroStep<R>>,
+
+    /// Steps being recorded. Replaces `macro_steps` when the recording stops.
+    macro_recording: Option<Vec<MacroStep<R>>>,
+
+    /// Replay queued for the next frame.
+    macro_replay: Option<MacroReplay>,
+
+    /// Search bar state; `None` if the search bar is closed.
+    pub search: Option<SearchState>,
+
+    /// Heat coloring of the rows; `None` if disabled.
+    heat: Option<HeatState>,
+
+    /// Rows with duplicate values; `None` if not highlighted.
+    duplicates: Option<DuplicateState>,
+
+    /// Group-by summary preview; `None` if closed.
+    pivot: Option<PivotState>,
+
+    /// Markers of the minimap; `None` if they should be rebuilt.
+    cc_minimap: Option<MinimapMarkers>,
+
     /// Persistent data
     p: PersistData,
 
@@ -177,6 +232,26 @@ pub(crate) struct UiState<R> {
     /// row height support, therefore invalid during table rendering.
     pub cc_row_heights: Vec<f32>,
 
+    /// Rows to be shown exclusively; see [`UiAction::ToggleShowSelectedOnly`].
+    cc_shown_rows: Option<BTreeSet<RowIdx>>,
+
+    /// Depth and whether it has any child, for each row in data order; empty unless any row is
+    /// nested by [`RowViewer::row_depth`].
+    cc_tree: Vec<(usize, bool)>,
+
+    /// Tree rows of which descendants are hidden.
+    cc_collapsed: BTreeSet<RowIdx>,
+
+    /// Number of rows pinned by [`RowViewer::is_pinned_row`], which lead `cc_rows`.
+    cc_num_pinned_rows: usize,
+
+    /// Statistics of the current selection; `None` if it has to be recomputed.
+    cc_selection_status: Option<SelectionStatus>,
+
+    /// Measured row heights by the row, which survive the rebuild of `cc_row_heights` on sort
+    /// or filter. Shifted along with the rows on insertion, removal and move.
+    cc_row_height_of: HashMap<RowIdx, f32>,
+
     /// Cached row id to visual row position table for quick lookup.
     cc_row_id_to_vis: HashMap<RowIdx, VisRowPos>,
 
@@ -190,15 +265,101 @@ pub(crate) struct UiState<R> {
     /// Number of frames from the last edit. Used to validate sorting.
     cc_num_frame_from_last_edit: usize,
 
+    /// Rows edited since the last validation, which may be out of the sort order or filter.
+    cc_stale_rows: BTreeSet<RowIdx>,
+
+    /// Stale rows should be repositioned on the next validation.
+    cc_resort_stale: bool,
+
+    /// Whether the stale rows are repositioned only on the user's request, given by the latest
+    /// rendering.
+    manual_resort: bool,
+
     /// Cached previous number of columns.
     cc_prev_n_columns: usize,
 
+    /// Column keys at the time the column list was changed last time. Used to remap the states
+    /// when the number of columns changes.
+    cc_column_keys: Vec<String>,
+
     /// Latest interactive cell; Used for keyboard navigation.
     cc_interactive_cell: VisLinearIdx,
 
+    /// Moving corner of the selection extended by keyboard, opposite to the interactive cell.
+    cc_sel_extent: Option<VisLinearIdx>,
+
     /// Desired selection of next validation
     cc_desired_selection: Option<Vec<(RowIdx, Vec<ColumnIdx>)>>,
 
+    /// Paste waiting for the user's confirmation; see [`RowViewer::confirm_paste`].
+    cc_pending_paste: Option<PendingPaste<R>>,
+
+    /// Source cells of the deferred cut, cleared once the paste lands; see
+    /// [`crate::Style::deferred_cut`].
+    cc_cut: Option<PendingCut>,
+
+    /// Whether the cut leaves the source cells until the paste, given by the latest rendering.
+    deferred_cut: bool,
+
+    /// Selection covers the rows hidden by the filter too; see
+    /// [`UiAction::SelectAllIncludingFiltered`].
+    cc_select_all_data: bool,
+
+    /// Action over the whole data, waiting for the user's confirmation.
+    cc_pending_all_data: Option<UiAction>,
+
+    /// Filter is changed since the last rebuild of the cache.
+    cc_filter_changed: bool,
+
+    /// Selected rows which got hidden by the filter change, until the next selection.
+    cc_hidden_selection: BTreeSet<RowIdx>,
+
+    /// Action on the selection spanning hidden rows, waiting for the user's confirmation.
+    cc_pending_hidden: Option<UiAction>,
+
+    /// Whether the actions on the selection spanning hidden rows are confirmed first, given by
+    /// the latest rendering.
+    confirm_hidden_selection: bool,
+
+    /// Edition to start once the appended row is validated; see [`Self::append_new_row`].
+    cc_pending_edit: Option<(RowIdx, VisColumnPos)>,
+
+    /// Rows shown in the comparison popup; see [`UiAction::CompareRows`].
+    cc_compared_rows: Option<[RowIdx; 2]>,
+
+    /// Row animations queued by the latest row insertion/removal.
+    cc_row_anims: RowAnimations,
+
+    /// Batches of recently changed cells, which are flashed for a while.
+    cc_cell_flashes: Vec<CellFlash>,
+
+    /// Incremented on every new flash batch, to give each batch unique animation id.
+    cc_cell_flash_generation: u64,
+
+    /// Rows modified by UI since the last clearance of user modification flag.
+    cc_modified_rows: BTreeSet<RowIdx>,
+
+    /// Length of the table when the row cache was built. Mismatch means the table was modified
+    /// without invalidating the cache.
+    cc_num_table_rows: usize,
+
+    /// Cells reported as invalid by the viewer. Kept over the rebuilds; only the rows changed
+    /// by commands are re-validated.
+    cc_invalid_cells: BTreeMap<(RowIdx, ColumnIdx), ValidationError>,
+
+    /// Every row is re-validated on the next rebuild, as the table was modified from code.
+    cc_revalidate_all: bool,
+
+    /// Time of the latest frame, in seconds.
+    cc_time: f64,
+
+    /// Cell and time of the latest commit which can be coalesced with the next one.
+    cc_last_commit: Option<(RowIdx, ColumnIdx, f64)>,
+
+    /// Incremented whenever the table is mutated from outside of the UI; row indices captured
+    /// before then may be stale.
+    cc_generation: u64,
+
     /*
 
         SECTION: Cache - Input Status
@@ -207,14 +368,45 @@ pub(crate) struct UiState<R> {
     /// (Pivot, Current) selection.
     cci_selection: Option<(VisLinearIdx, VisLinearIdx)>,
 
+    /// Selection in progress was started from the row header, which selects whole rows.
+    cci_sel_rows: bool,
+
+    /// Cell under the pointer while the fill handle is being dragged.
+    cci_fill: Option<VisLinearIdx>,
+
+    /// Screen rectangle of the fill handle rendered recently.
+    pub cci_fill_handle: Option<egui::Rect>,
+
     /// We have latest click.
     pub cci_has_focus: bool,
 
     /// Interface wants to scroll to the row.
     pub cci_want_move_scroll: bool,
 
+    /// Touch is held after a long press opened the context menu; blocks drag selection until
+    /// the release.
+    pub cci_touch_hold: bool,
+
+    /// Cells rendered in the latest frame.
+    pub cci_visible_cells: Vec<VisibleCell>,
+
+    /// Horizontal scroll offset of the table, as of the latest frame.
+    pub cci_scroll_x: f32,
+
+    /// Horizontal scroll offset requested by the host, applied on the next frame.
+    pub cci_want_scroll_x: Option<f32>,
+
     /// How many rows are rendered at once recently?
     pub cci_page_row_count: usize,
+
+    /// The first row rendered recently.
+    pub cci_first_visible_row: VisRowPos,
+
+    /// Horizontal span of each visible column header, as of the latest frame.
+    pub cci_column_spans: Vec<(ColumnIdx, egui::Rangef)>,
+
+    /// Columns to fit to their content on the next frame.
+    pub cci_fit_columns: Vec<ColumnIdx>,
 }
 
 #[cfg_attr(feature = "persistency", derive(serde::Serialize, serde::Deserialize))]
@@ -228,6 +420,16 @@ struct PersistData {
 
     /// Column sorting state.
     sort: Vec<(ColumnIdx, IsAscending)>,
+
+    /// Column schema version declared by the viewer.
+    #[cfg_attr(feature = "persistency", serde(default))]
+    schema_version: u64,
+
+    /// Column keys at the time of persisting. Used to remap persisted layout by column key,
+    /// when the column set is changed between sessions.
+    #[cfg(feature = "persistency")]
+    #[serde(default)]
+    column_keys: Vec<String>,
 }
 
 struct Clipboard<R> {
@@ -236,6 +438,45 @@ struct Clipboard<R> {
     /// The first tuple element `VisRowPos` is offset from the top-left corner of the
     /// selection.
     pastes: Box<[(VisRowOffset, ColumnIdx, RowSlabIndex)]>,
+
+    /// Number of rows skipped due to decode errors, when parsed from system clipboard.
+    skipped_rows: usize,
+}
+
+/// Statistics of the selection, shown in the status bar.
+pub struct SelectionStatus {
+    pub cells: usize,
+    pub rows: usize,
+
+    /// Aggregates given by [`RowViewer::selection_summary`].
+    pub summary: Vec<(Cow<'static, str>, String)>,
+}
+
+/// Leading part of the clipboard contents, shown before pasting.
+pub struct ClipboardPreview {
+    /// Dimensions of the whole contents.
+    pub rows: usize,
+    pub columns: usize,
+
+    /// Names of the columns in the preview.
+    pub header: Vec<Cow<'static, str>>,
+
+    /// Text of the cells by rows; [`None`] for the cells not copied.
+    pub cells: Vec<Vec<Option<String>>>,
+}
+
+struct PendingPaste<R> {
+    summary: PasteSummary,
+    commands: Vec<Command<R>>,
+    desired_selection: Option<Vec<(RowIdx, Vec<ColumnIdx>)>>,
+}
+
+struct PendingCut {
+    cells: BTreeSet<(RowIdx, ColumnIdx)>,
+
+    /// Text dumped into the system clipboard by the cut; the cut is dropped once the system
+    /// clipboard turns into anything else.
+    text: Option<String>,
 }
 
 impl<R> Clipboard<R> {
@@ -247,32 +488,381 @@ impl<R> Clipboard<R> {
     }
 }
 
+/// Batch of row animations which share single animation progress.
+#[derive(Default)]
+struct RowAnimations {
+    /// Incremented on every new batch, to give each batch unique animation id.
+    generation: u64,
+    started: bool,
+    fade_in: Vec<Range<usize>>,
+
+    /// Row which is placed right after removed rows, and total height of removed rows.
+    collapse: Vec<(RowIdx, f32)>,
+}
+
+impl RowAnimations {
+    fn reset(&mut self) {
+        self.generation += 1;
+        self.started = false;
+        self.fade_in.clear();
+        self.collapse.clear();
+    }
+
+    fn is_empty(&self) -> bool {
+        self.fade_in.is_empty() && self.collapse.is_empty()
+    }
+}
+
+/// Row animation status of the current frame.
+pub(crate) struct RowAnimFrame {
+    progress: f32,
+    fade_in: Vec<Range<usize>>,
+    collapse: Vec<(VisRowPos, f32)>,
+}
+
+impl RowAnimFrame {
+    /// Opacity of given row, if it's being faded in.
+    pub fn opacity(&self, row: RowIdx) -> Option<f32> {
+        self.fade_in
+            .iter()
+            .any(|range| range.contains(&row.0))
+            .then_some(self.progress)
+    }
+
+    /// Extra height of the gap left by removed rows, which is collapsing above given row.
+    pub fn extra_height(&self, row: VisRowPos) -> f32 {
+        self.collapse
+            .iter()
+            .find(|(vis, _)| *vis == row)
+            .map_or(0., |(_, height)| height * (1. - self.progress))
+    }
+}
+
+/// Cells changed by single command. [`None`] column means the whole row.
+struct CellFlash {
+    generation: u64,
+    started: bool,
+    cells: Vec<(RowIdx, Option<ColumnIdx>)>,
+}
+
+/// Flash intensity of changed cells of the current frame.
+#[derive(Default)]
+pub(crate) struct CellFlashFrame {
+    rows: HashMap<RowIdx, Vec<(Option<ColumnIdx>, f32)>>,
+}
+
+impl CellFlashFrame {
+    /// Returns flash intensity in range `0..=1` for given cell.
+    pub fn intensity(&self, row: RowIdx, column: ColumnIdx) -> f32 {
+        self.rows.get(&row).map_or(0., |cells| {
+            cells
+                .iter()
+                .filter(|(c, _)| c.is_none() || *c == Some(column))
+                .fold(0., |acc: f32, (_, x)| acc.max(*x))
+        })
+    }
+}
+
+/// Row filter applied on top of the viewer's model.
+pub(crate) type ExtraFilter<R> = Box<dyn Fn(&R) -> bool + Send + Sync>;
+
+/// State of the built-in search bar.
+#[derive(Default)]
+pub(crate) struct SearchState {
+    pub query: String,
+    pub replacement: String,
+    pub replace_mode: bool,
+
+    /// Query field should take the focus on the next frame.
+    pub focus_query: bool,
+
+    /// Matching cells, sorted by visual position.
+    matches: Vec<VisLinearIdx>,
+
+    /// Matches should be recomputed; set on any query or table change.
+    stale: bool,
+}
+
+impl SearchState {
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn num_matches(&self) -> usize {
+        self.matches.len()
+    }
+}
+
+/// State of the built-in heat coloring, which tints the rows by the rank of a column's value.
+struct HeatState {
+    column: ColumnIdx,
+
+    /// Rank of every visible row, normalized into `0..=1`. Equal values share the rank.
+    ranks: HashMap<RowIdx, f32>,
+
+    /// Ranks should be recomputed; set on any table change.
+    stale: bool,
+}
+
+/// Rows which share the value of a column with another row.
+struct DuplicateState {
+    column: ColumnIdx,
+    rows: HashSet<RowIdx>,
+
+    /// Duplicates should be recomputed; set on any table change.
+    stale: bool,
+}
+
+/// Columns and the result of the group-by summary preview.
+struct PivotState {
+    group: ColumnIdx,
+    value: Option<ColumnIdx>,
+    rows: Vec<PivotRow>,
+
+    /// Summary should be recomputed; set on any table change.
+    stale: bool,
+}
+
+/// Markers of the minimap, with the selection they were built for.
+struct MinimapMarkers {
+    buckets: Vec<MinimapBucket>,
+    selection: Vec<VisSelection>,
+}
+
+/// Markers of the rows which fall into a single pixel of the minimap.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct MinimapBucket {
+    pub modified: bool,
+    pub search_match: bool,
+    pub marker: Option<egui::Color32>,
+    pub selected: bool,
+}
+
+enum MacroStep<R> {
+    Action(UiAction),
+
+    /// Committed edition of given column; written into the interactive row on replay.
+    SetCell(ColumnIdx, Box<R>),
+}
+
 struct UndoArg<R> {
-    apply: Command<R>,
-    restore: Vec<Command<R>>,
+    apply: UndoCommand<R>,
+    restore: Vec<UndoCommand<R>>,
+}
+
+/// Command stored in the undo history. Rows of the command may be encoded with the codec of the
+/// viewer, to save memory; they're decoded back when the command is applied again.
+enum UndoCommand<R> {
+    Plain(Command<R>),
+    Encoded(EncodedCommand, EncodedRows),
+}
+
+/// Row carrying command, without its rows.
+enum EncodedCommand {
+    SetRowValue(RowIdx),
+    SetCells(Box<[(RowIdx, ColumnIdx, RowSlabIndex)]>),
+    InsertRows(RowIdx),
+}
+
+/// Format of encoded rows; quoting keeps empty cells and special characters intact.
+const UNDO_FORMAT: ClipboardFormat = ClipboardFormat::SPREADSHEET;
+
+/// Rows encoded as delimited text, one line per row.
+struct EncodedRows {
+    /// Column of each encoded cell of the line; `None` if the column was removed since.
+    columns: Vec<Option<ColumnIdx>>,
+    data: String,
+}
+
+impl<R> UndoCommand<R> {
+    fn encode<V: RowViewer<R> + ?Sized>(vwr: &mut V, num_columns: usize, cmd: Command<R>) -> Self {
+        let rows: &[R] = match &cmd {
+            Command::SetRowValue(_, row) => std::slice::from_ref(&**row),
+            Command::SetCells { slab, .. } | Command::InsertRows(_, slab) => slab,
+            _ => return Self::Plain(cmd),
+        };
+
+        // Computed columns are derived from the other columns; they're never decoded back.
+        let computed = (0..num_columns)
+            .map(|x| vwr.is_computed_column(x))
+            .collect::<Vec<_>>();
+
+        let Some(mut codec) = vwr.try_create_codec(true) else {
+            return Self::Plain(cmd);
+        };
+
+        // Columns out of the codec would be lost by the round-trip.
+        if (0..num_columns).any(|x| !computed[x] && !codec.supports_column(x)) {
+            return Self::Plain(cmd);
+        }
+
+        let mut data = String::new();
+        let mut buf = String::new();
+
+        for (index, row) in rows.iter().enumerate() {
+            if index > 0 {
+                delimited::write_newline(&mut data);
+            }
+
+            for (column, is_computed) in computed.iter().enumerate() {
+                if column > 0 {
+                    delimited::write_delimiter(&mut data, &UNDO_FORMAT);
+                }
+
+                buf.clear();
+                if !is_computed {
+                    codec.encode_column(row, column, &mut buf);
+                }
+                delimited::write_content(&mut data, &buf, &UNDO_FORMAT);
+            }
+        }
+
+        let rows = EncodedRows {
+            columns: computed
+                .iter()
+                .enumerate()
+                .map(|(x, is_computed)| (!is_computed).then_some(ColumnIdx(x)))
+                .collect(),
+            data,
+        };
+
+        match cmd {
+            Command::SetRowValue(row, _) => Self::Encoded(EncodedCommand::SetRowValue(row), rows),
+            Command::SetCells { values, .. } => {
+                Self::Encoded(EncodedCommand::SetCells(values), rows)
+            }
+            Command::InsertRows(pos, _) => Self::Encoded(EncodedCommand::InsertRows(pos), rows),
+            _ => unreachable!(),
+        }
+    }
+
+    fn is_set_row_value_of(&self, row_id: RowIdx) -> bool {
+        match self {
+            Self::Plain(Command::SetRowValue(row, _))
+            | Self::Encoded(EncodedCommand::SetRowValue(row), _) => *row == row_id,
+            _ => false,
+        }
+    }
+}
+
+impl EncodedCommand {
+    /// Decodes the rows of the command. Returns `None` if any row can't be decoded.
+    fn decode<R, V: RowViewer<R> + ?Sized>(
+        &self,
+        rows: &EncodedRows,
+        vwr: &mut V,
+    ) -> Option<Command<R>> {
+        let mut codec = vwr.try_create_codec(false)?;
+        let view = delimited::ParsedTable::parse(&rows.data, &UNDO_FORMAT);
+        let mut slab = Vec::with_capacity(view.num_rows());
+
+        for index in 0..view.num_rows() {
+            let mut row = codec.create_empty_decoded_row();
+
+            for (pos, column) in rows.columns.iter().enumerate() {
+                let Some(column) = column else {
+                    continue;
+                };
+
+                let data = view.get_cell(index, pos).unwrap_or_default();
+                match codec.decode_column(data, column.0, &mut row) {
+                    Ok(()) | Err(DecodeErrorBehavior::SkipCell) => {}
+                    Err(DecodeErrorBehavior::SkipRow | DecodeErrorBehavior::Abort) => return None,
+                }
+            }
+
+            slab.push(row);
+        }
+
+        Some(match self {
+            EncodedCommand::SetRowValue(row) => Command::SetRowValue(*row, slab.pop()?.into()),
+            EncodedCommand::SetCells(values) => Command::SetCells {
+                slab: slab.into(),
+                values: values.clone(),
+            },
+            EncodedCommand::InsertRows(pos) => Command::InsertRows(*pos, slab.into()),
+        })
+    }
 }
 
 impl<R> Default for UiState<R> {
     fn default() -> Self {
         Self {
             viewer_filter_hash: 0,
+            extra_filter: None,
             clipboard: None,
+            macro_steps: Vec::new(),
+            macro_recording: None,
+            macro_replay: None,
+            search: None,
+            heat: None,
+            duplicates: None,
+            cc_minimap: None,
+            pivot: None,
             viewer_type: std::any::TypeId::of::<()>(),
             cc_cursor: CursorState::Select(default()),
             undo_queue: VecDeque::new(),
             cc_rows: Vec::new(),
             cc_row_heights: Vec::new(),
+            cc_num_pinned_rows: 0,
+            cc_row_height_of: HashMap::new(),
+            cc_shown_rows: None,
+            cc_tree: Vec::new(),
+            cc_collapsed: BTreeSet::new(),
             cc_dirty: false,
             undo_cursor: 0,
+            undo_capacity: 0,
+            undo_enabled: true,
+            soft_delete: false,
+            detached_editing: false,
+            edit_request: None,
+            compress_undo_history: false,
             cci_selection: None,
+            cci_sel_rows: false,
+            cci_fill: None,
+            cci_fill_handle: None,
             cci_has_focus: false,
+            cci_touch_hold: false,
             cc_interactive_cell: VisLinearIdx(0),
+            cc_sel_extent: None,
             cc_row_id_to_vis: default(),
             cc_num_frame_from_last_edit: 0,
+            cc_stale_rows: BTreeSet::new(),
+            cc_resort_stale: false,
+            manual_resort: false,
             cc_prev_n_columns: 0,
+            cc_column_keys: Vec::new(),
             cc_desired_selection: None,
+            cc_pending_paste: None,
+            cc_cut: None,
+            deferred_cut: false,
+            cc_select_all_data: false,
+            cc_pending_all_data: None,
+            cc_filter_changed: false,
+            cc_selection_status: None,
+            cc_hidden_selection: BTreeSet::new(),
+            cc_pending_hidden: None,
+            confirm_hidden_selection: false,
+            cc_compared_rows: None,
+            cc_pending_edit: None,
+            cc_row_anims: default(),
+            cc_cell_flashes: Vec::new(),
+            cc_cell_flash_generation: 0,
+            cc_modified_rows: BTreeSet::new(),
+            cc_num_table_rows: 0,
+            cc_invalid_cells: BTreeMap::new(),
+            cc_revalidate_all: true,
+            cc_time: 0.,
+            cc_last_commit: None,
+            cc_generation: 0,
             cci_want_move_scroll: false,
+            cci_visible_cells: Vec::new(),
+            cci_scroll_x: 0.,
+            cci_want_scroll_x: None,
             cci_page_row_count: 0,
+            cci_first_visible_row: VisRowPos(0),
+            cci_column_spans: Vec::new(),
+            cci_fit_columns: Vec::new(),
             p: default(),
             #[cfg(feature = "persistency")]
             is_p_loaded: false,
@@ -287,6 +877,9 @@ enum CursorState<R> {
         last_focus: VisColumnPos,
         row: RowIdx,
         edition: R,
+
+        /// Table generation when the edition started.
+        generation: u64,
     },
 }
 
@@ -295,1163 +888,4333 @@ impl<R> UiState<R> {
         self.cc_dirty
     }
 
-    pub fn validate_identity<V: RowViewer<R>>(&mut self, vwr: &mut V) {
-        let num_columns = vwr.num_columns();
-        let vwr_type_id = std::any::TypeId::of::<V>();
-        let vwr_hash = AHasher::default().pipe(|mut hsh| {
-            vwr.row_filter_hash().hash(&mut hsh);
-            hsh.finish()
-        });
+    pub fn cc_mark_dirty(&mut self) {
+        self.cc_dirty = true;
+    }
 
-        // Check for nontrivial changes.
-        if self.p.num_columns == num_columns && self.viewer_type == vwr_type_id {
-            // Check for trivial changes which does not require total reconstruction of
-            // UiState.
+    /// Advances flash animations of recently changed cells. Returns `None` if there's nothing
+    /// to flash, or the flash is disabled.
+    pub fn cell_flash_frame(
+        &mut self,
+        ctx: &egui::Context,
+        ui_id: egui::Id,
+        flash_time: Option<f32>,
+    ) -> Option<CellFlashFrame> {
+        if self.cc_cell_flashes.is_empty() {
+            return None;
+        }
 
-            // If viewer's filter is changed. It always invalidates current cache.
-            if self.viewer_filter_hash != vwr_hash {
-                self.viewer_filter_hash = vwr_hash;
-                self.cc_dirty = true;
+        let Some(flash_time) = flash_time else {
+            self.cc_cell_flashes.clear();
+            return None;
+        };
+
+        let mut frame = CellFlashFrame::default();
+        self.cc_cell_flashes.retain_mut(|flash| {
+            let id = ui_id
+                .with("_EGUI_DATATABLE__CELL_FLASH_")
+                .with(flash.generation);
+
+            if !replace(&mut flash.started, true) {
+                ctx.animate_value_with_time(id, 1., flash_time);
             }
 
-            // Defer validation of cache if it's still editing. This is prevent annoying re-sort
-            // during editing multiple cells in-a-row without escape from insertion mode.
-            {
-                if !self.is_editing() {
-                    self.cc_num_frame_from_last_edit += 1;
-                }
+            let intensity = ctx.animate_value_with_time(id, 0., flash_time);
+            if intensity <= 0. {
+                return false;
+            }
 
-                if self.cc_num_frame_from_last_edit == 2 {
-                    self.cc_dirty |= !self.p.sort.is_empty();
-                }
+            for (row, col) in &flash.cells {
+                frame.rows.entry(*row).or_default().push((*col, intensity));
             }
 
-            // Check if any sort config is invalidated.
-            self.cc_dirty |= {
-                let mut any_sort_invalidated = false;
+            true
+        });
 
-                self.p.sort.retain(|(c, _)| {
-                    vwr.is_sortable_column(c.0)
-                        .tap(|x| any_sort_invalidated |= !x)
-                });
+        Some(frame)
+    }
 
-                any_sort_invalidated
-            };
+    pub fn is_modified_row(&self, row: RowIdx) -> bool {
+        self.cc_modified_rows.contains(&row)
+    }
 
-            return;
-        }
+    /// Validation error of the cell, if the viewer reported it as invalid.
+    pub fn cell_error(&self, row: RowIdx, col: ColumnIdx) -> Option<&ValidationError> {
+        self.cc_invalid_cells.get(&(row, col))
+    }
 
-        // Clear the cache
-        *self = Default::default();
-        self.viewer_type = vwr_type_id;
-        self.viewer_filter_hash = vwr_hash;
-        self.p.num_columns = num_columns;
+    pub fn is_invalid_row(&self, row: RowIdx) -> bool {
+        self.cc_invalid_cells
+            .range((row, ColumnIdx(0))..=(row, ColumnIdx(usize::MAX)))
+            .next()
+            .is_some()
+    }
 
-        self.p.vis_cols.extend((0..num_columns).map(ColumnIdx));
-        self.cc_dirty = true;
+    /// Invalid cells as `(row_index, column_index, error)` in data space, in row order.
+    pub fn invalid_cells(&self) -> impl Iterator<Item = (usize, usize, &ValidationError)> {
+        self.cc_invalid_cells
+            .iter()
+            .map(|((row, col), err)| (row.0, col.0, err))
     }
 
-    #[cfg(feature = "persistency")]
-    pub fn validate_persistency<V: RowViewer<R>>(
+    /// Re-validates every cell of given rows.
+    fn validate_rows<V: RowViewer<R> + ?Sized>(
         &mut self,
-        ctx: &egui::Context,
-        ui_id: egui::Id,
+        table: &DataTable<R>,
         vwr: &mut V,
+        rows: impl IntoIterator<Item = RowIdx>,
     ) {
-        if !self.is_p_loaded {
-            // Load initial storage status
-            self.is_p_loaded = true;
-            self.cc_dirty = true;
-            let p: PersistData =
-                ctx.memory_mut(|m| m.data.get_persisted(ui_id).unwrap_or_default());
-
-            if p.num_columns == self.p.num_columns {
-                // Data should only be copied when column count matches. Otherwise, we regard
-                // stored column differs from the current.
-                self.p = p;
-
-                // Only retain valid sorting configuration.
-                self.p.sort.retain(|(col, _)| vwr.is_sortable_column(col.0));
+        for row in rows {
+            for col in (0..self.p.num_columns).map(ColumnIdx) {
+                match vwr.validate_cell(&table.rows[row.0], col.0) {
+                    Ok(()) => self.cc_invalid_cells.remove(&(row, col)),
+                    Err(err) => self.cc_invalid_cells.insert((row, col), err),
+                };
             }
-        } else if self.cc_dirty {
-            // Copy current ui status into persistency storage.
-            ctx.memory_mut(|m| m.data.insert_persisted(ui_id, self.p.clone()));
         }
     }
 
-    pub fn validate_cc<V: RowViewer<R>>(&mut self, rows: &mut [R], vwr: &mut V) {
-        if !replace(&mut self.cc_dirty, false) {
-            self.handle_desired_selection();
-            return;
-        }
+    pub fn clear_modified_rows(&mut self) {
+        self.cc_modified_rows.clear();
+        self.cc_minimap = None;
+    }
 
-        // XXX: Boost performance with `rayon`?
-        // - Returning `comparator` which is marked as `Sync`
-        // - For this, `R` also need to be sent to multiple threads safely.
-        // - Maybe we need specialization for `R: Send`?
+    /// Shift modified row marks to follow the rows after insertion.
+    fn mark_inserted_rows(&mut self, pos: RowIdx, count: usize) {
+        let shifted = self.cc_modified_rows.split_off(&pos);
+        self.cc_modified_rows
+            .extend(shifted.into_iter().map(|x| RowIdx(x.0 + count)));
+        self.cc_modified_rows
+            .extend((pos.0..pos.0 + count).map(RowIdx));
+    }
 
-        // We should validate the entire cache.
-        self.cc_rows.clear();
-        self.cc_rows.extend(
-            rows.iter()
-                .enumerate()
-                .filter_map(|(i, x)| vwr.filter_row(x).then_some(i))
-                .map(RowIdx),
-        );
+    /// Mark everything derived from the visible rows to be recomputed; search matches, heat
+    /// coloring, duplicates, the pivot preview, the minimap and the selection statistics.
+    fn invalidate_row_summaries(&mut self) {
+        self.cc_selection_status = None;
+        self.cc_minimap = None;
 
-        for (sort_col, asc) in self.p.sort.iter().rev() {
-            self.cc_rows.sort_by(|a, b| {
-                vwr.compare_cell(&rows[a.0], &rows[b.0], sort_col.0)
-                    .tap_mut(|x| {
-                        if !asc.0 {
-                            *x = x.reverse()
-                        }
-                    })
-            });
+        if let Some(search) = &mut self.search {
+            search.invalidate();
         }
 
-        // Just refill with neat default height.
-        self.cc_row_heights.resize(self.cc_rows.len(), 20.0);
+        if let Some(heat) = &mut self.heat {
+            heat.stale = true;
+        }
 
-        self.cc_row_id_to_vis.clear();
-        self.cc_row_id_to_vis.extend(
+        if let Some(duplicates) = &mut self.duplicates {
+            duplicates.stale = true;
+        }
+
+        if let Some(pivot) = &mut self.pivot {
+            pivot.stale = true;
+        }
+    }
+
+    /// Rebuild the positional row heights from the measured ones; unmeasured rows get neat
+    /// default height.
+    fn refill_row_heights(&mut self) {
+        self.cc_row_heights.clear();
+        self.cc_row_heights.extend(
             self.cc_rows
                 .iter()
-                .enumerate()
-                .map(|(i, id)| (*id, VisRowPos(i))),
+                .map(|row| self.cc_row_height_of.get(row).copied().unwrap_or(20.0)),
         );
+    }
 
-        if self.handle_desired_selection() {
-            // no-op.
-        } else if let CursorState::Select(cursor) = &mut self.cc_cursor {
-            // Validate cursor range if it's still in range.
-
-            let old_cols = self.cc_prev_n_columns;
-            let new_rows = self.cc_rows.len();
-            let new_cols = self.p.num_columns;
-            self.cc_prev_n_columns = self.p.num_columns;
-
-            cursor.retain_mut(|sel| {
-                let (old_min_r, old_min_c) = sel.0.row_col(old_cols);
-                if old_min_r.0 >= new_rows || old_min_c.0 >= new_cols {
-                    return false;
-                }
-
-                let (mut old_max_r, mut old_max_c) = sel.1.row_col(old_cols);
-                old_max_r.0 = old_max_r.0.min(new_rows.saturating_sub(1));
-                old_max_c.0 = old_max_c.0.min(new_cols.saturating_sub(1));
+    pub fn set_row_height(&mut self, row: VisRowPos, height: f32) {
+        self.cc_row_heights[row.0] = height;
+        self.cc_row_height_of.insert(self.cc_rows[row.0], height);
+    }
 
-                let min = old_min_r.linear_index(new_cols, old_min_c);
-                let max = old_max_r.linear_index(new_cols, old_max_c);
-                *sel = VisSelection(min, max);
+    /// Shift the states kept by row, i.e. measured heights and shown rows, to follow the rows;
+    /// `None` drops the state of the row.
+    fn remap_row_states(&mut self, remap: impl Fn(RowIdx) -> Option<RowIdx>) {
+        self.cc_row_height_of = take(&mut self.cc_row_height_of)
+            .into_iter()
+            .filter_map(|(row, height)| Some((remap(row)?, height)))
+            .collect();
 
-                true
-            });
-        } else {
-            self.cc_cursor = CursorState::Select(Vec::default());
+        if let Some(shown) = &mut self.cc_shown_rows {
+            *shown = take(shown).into_iter().filter_map(&remap).collect();
         }
 
-        // Prevent overflow.
-        self.validate_interactive_cell(self.p.vis_cols.len());
+        self.cc_invalid_cells = take(&mut self.cc_invalid_cells)
+            .into_iter()
+            .filter_map(|((row, col), err)| Some(((remap(row)?, col), err)))
+            .collect();
     }
 
-    pub fn try_update_clipboard_from_string<V: RowViewer<R>>(
-        &mut self,
-        vwr: &mut V,
-        contents: &str,
-    ) -> bool {
-        /*
-            NOTE: System clipboard implementation
-
-            We can't just determine if the internal clipboard should be preferred over the system
-            clipboard, as the source of clipboard contents can be vary. Therefore, on every copy
-            operation, we should clear out the system clipboard unconditionally, then we tries to
-            encode the copied content into clipboard.
-
-            TODO: Need to find way to handle if both of system clipboard and internal clipboard
-            content exist. We NEED to determine if which content should be applied for this.
-
-            # Dumping
-
-            - For rectangular(including single cell) selection of data, we'll just create
-              appropriate sized small TSV data which suits within given range.
-                - Note that this'll differentiate the clipboard behavior from internal-only
-                  version.
-            - For non-rectangular selections, full-scale rectangular table is dumped which
-              can cover all selection range including empty selections; where any data that
-              is not being dumped is just emptied out.
-                - From this, any data cell that is just 'empty' but selected, should be dumped
-                  as explicit empty data; in this case, empty data wrapped with double
-                  quotes("").
-
-            # Decoding
+    fn queue_cell_flash(&mut self, cells: Vec<(RowIdx, Option<ColumnIdx>)>) {
+        self.cc_cell_flash_generation += 1;
+        self.cc_cell_flashes.push(CellFlash {
+            generation: self.cc_cell_flash_generation,
+            started: false,
+            cells,
+        });
+    }
 
-            - Every format is regarded as TSV. (only \t, \n matters)
-            - For TSV data with same column count with this table
-                - Parse as full-scale table, then put into clipboard as-is.
-            - Column count is less than current table
-                - In this case, current selection matters.
-                - Offset the copied content table as the selection column offset.
-                - Then create clipboard data from it.
-            - If column count is larger than this, it is invalid data; we just skip parsing
-        */
+    /// Advances queued row animations. Returns `None` if there's nothing to animate, or the
+    /// animation is disabled.
+    pub fn row_animation_frame(
+        &mut self,
+        ctx: &egui::Context,
+        ui_id: egui::Id,
+        anim_time: Option<f32>,
+    ) -> Option<RowAnimFrame> {
+        let anims = &mut self.cc_row_anims;
+        if anims.is_empty() {
+            return None;
+        }
 
-        let Some(mut codec) = vwr.try_create_codec(false) else {
-            // Even when there is system clipboard content, we're going to ignore it and use
-            // internal clipboard if there's no way to parse it.
-            return false;
+        let Some(anim_time) = anim_time else {
+            anims.reset();
+            return None;
         };
 
-        if let CursorState::Select(selections) = &self.cc_cursor {
-            let Some(first) = selections.first().map(|x| x.0) else {
-                // No selectgion present. Do nothing
-                return false;
-            };
+        let id = ui_id
+            .with("_EGUI_DATATABLE__ROW_ANIM_")
+            .with(anims.generation);
+        if !replace(&mut anims.started, true) {
+            ctx.animate_value_with_time(id, 0., anim_time);
+        }
 
-            let (.., col) = first.row_col(self.p.vis_cols.len());
-            col
-        } else {
-            // If there's no selection, we'll just ignore the system clipboard input
-            return false;
-        };
+        let progress = ctx.animate_value_with_time(id, 1., anim_time);
+        if progress >= 1. {
+            anims.reset();
+            return None;
+        }
 
-        let selection_offset = if let CursorState::Select(sel) = &self.cc_cursor {
-            sel.first().map_or(0, |idx| {
-                let (_, c) = idx.0.row_col(self.p.vis_cols.len());
-                c.0
-            })
-        } else {
-            0
-        };
+        Some(RowAnimFrame {
+            progress,
+            fade_in: anims.fade_in.clone(),
+            collapse: anims
+                .collapse
+                .iter()
+                .filter_map(|(row, height)| Some((*self.cc_row_id_to_vis.get(row)?, *height)))
+                .collect(),
+        })
+    }
 
-        let view = tsv::ParsedTsv::parse(contents);
-        let table_width = view.calc_table_width();
+    pub fn validate_identity<V: RowViewer<R> + ?Sized>(&mut self, vwr: &mut V) {
+        let num_columns = vwr.num_columns();
+        // Dispatched dynamically, so swapping `dyn RowViewer` implementations is detected too.
+        let vwr_type_id = std::any::Any::type_id(&*vwr);
+        let vwr_hash = AHasher::default().pipe(|mut hsh| {
+            if let Some(model) = vwr.model() {
+                model.row_filter_hash(&mut hsh);
+            }
+            hsh.finish()
+        });
 
-        if table_width > self.p.vis_cols.len() {
-            // If the copied data has more columns than current table, we'll just ignore it.
-            return false;
+        // Viewer declared column schema changes; remap the states rather than resetting them.
+        let schema_version = vwr.column_schema_version();
+        if self.viewer_type == vwr_type_id && self.p.schema_version != schema_version {
+            let prev_version = replace(&mut self.p.schema_version, schema_version);
+            self.remap_columns(num_columns, |col| {
+                vwr.remap_column(prev_version, col.0)
+                    .filter(|x| *x < num_columns)
+                    .map(ColumnIdx)
+            });
+
+            self.cc_column_keys = Self::column_keys(vwr, num_columns);
         }
 
-        // If any cell is failed to be parsed, we'll just give up all parsing then use internal
-        // clipboard instead.
+        // Number of columns is changed; try to remap by the column keys rather than resetting.
+        if self.viewer_type == vwr_type_id && self.p.num_columns != num_columns {
+            let keys = Self::column_keys(vwr, num_columns);
 
-        let mut slab = Vec::new();
-        let mut pastes = Vec::new();
+            if Self::is_unique_keys(&self.cc_column_keys) && Self::is_unique_keys(&keys) {
+                let prev_keys = take(&mut self.cc_column_keys);
+                self.remap_columns_by_key(&prev_keys, &keys);
+            }
 
-        for (row_offset, row_data) in view.iter_rows() {
-            let slab_id = slab.len();
-            slab.push(codec.create_empty_decoded_row());
+            self.cc_column_keys = keys;
+        }
 
-            // The restoration point of pastes stack.
-            let pastes_restore = pastes.len();
+        // Check for nontrivial changes.
+        if self.p.num_columns == num_columns && self.viewer_type == vwr_type_id {
+            // Check for trivial changes which does not require total reconstruction of
+            // UiState.
 
-            for (column, data) in row_data {
-                let col_idx = column + selection_offset;
+            // If viewer's filter is changed. It always invalidates current cache.
+            if self.viewer_filter_hash != vwr_hash {
+                self.viewer_filter_hash = vwr_hash;
+                self.cc_dirty = true;
+                self.cc_filter_changed = true;
+            }
 
-                if col_idx > self.p.vis_cols.len() {
-                    // If the column is out of range, we'll just ignore it.
-                    return false;
+            // Defer validation of cache if it's still editing. This is prevent annoying re-sort
+            // during editing multiple cells in-a-row without escape from insertion mode.
+            {
+                if !self.is_editing() {
+                    self.cc_num_frame_from_last_edit += 1;
                 }
 
-                match codec.decode_column(data, col_idx, &mut slab[slab_id]) {
-                    Ok(_) => {
-                        pastes.push((
-                            VisRowOffset(row_offset),
-                            ColumnIdx(col_idx),
-                            RowSlabIndex(slab_id),
-                        ));
-                    }
-                    Err(DecodeErrorBehavior::SkipCell) => {
-                        // Skip this cell.
-                    }
-                    Err(DecodeErrorBehavior::SkipRow) => {
-                        pastes.drain(pastes_restore..);
-                        slab.pop();
-                        break;
-                    }
-                    Err(DecodeErrorBehavior::Abort) => {
-                        return false;
+                if self.cc_num_frame_from_last_edit == 2 {
+                    if self.p.sort.is_empty() {
+                        self.cc_stale_rows.clear();
+                    } else if !self.manual_resort {
+                        self.cc_resort_stale = true;
                     }
                 }
             }
-        }
 
-        // Replace the clipboard content from the parsed data.
-        self.clipboard = Some(Clipboard {
-            slab: slab.into_boxed_slice(),
-            pastes: pastes.into_boxed_slice(),
-        });
+            // Check if any sort config is invalidated.
+            self.cc_dirty |= {
+                let mut any_sort_invalidated = false;
 
-        true
-    }
+                self.p.sort.retain(|(c, _)| {
+                    vwr.is_sortable_column(c.0)
+                        .tap(|x| any_sort_invalidated |= !x)
+                });
 
-    fn try_dump_clipboard_content<V: RowViewer<R>>(
-        clipboard: &Clipboard<R>,
-        vwr: &mut V,
-    ) -> Option<String> {
-        // clipboard MUST be sorted before dumping; XXX: add assertion?
-        #[allow(unused_mut)]
-        let mut codec = vwr.try_create_codec(true)?;
+                any_sort_invalidated
+            };
 
-        let mut width = 0;
-        let mut height = 0;
+            return;
+        }
 
-        // We're going to offset the column to the minimum column index to make the selection copy
-        // more intuitive. If not, the copied data will be shifted to the right if the selection is
-        // not the very first column.
-        let mut min_column = usize::MAX;
+        // Clear the cache
+        *self = Default::default();
+        self.viewer_type = vwr_type_id;
+        self.viewer_filter_hash = vwr_hash;
+        self.p.num_columns = num_columns;
+        self.p.schema_version = schema_version;
+        self.cc_column_keys = Self::column_keys(vwr, num_columns);
 
-        for (row, column, ..) in clipboard.pastes.iter() {
-            width = width.max(column.0 + 1);
-            height = height.max(row.0 + 1);
-            min_column = min_column.min(column.0);
+        self.p.vis_cols.extend((0..num_columns).map(ColumnIdx));
+        self.cc_dirty = true;
+    }
+
+    /// Full rebuild is preferred when more than `1 / MAX_RESORT_RATIO` of the visible rows
+    /// are stale.
+    const MAX_RESORT_RATIO: usize = 16;
+
+    /// Maximum number of the rows in a single undo snapshot of removed rows.
+    const UNDO_CHUNK_ROWS: usize = 4096;
+
+    /// Order of rows by the sort keys in priority, then by the row index; which equals to the
+    /// stable sort of rows in the original order.
+    fn compare_rows(
+        model: &dyn TableModel<R>,
+        sort: &[(ColumnIdx, IsAscending)],
+        rows: &[R],
+        a: RowIdx,
+        b: RowIdx,
+    ) -> std::cmp::Ordering {
+        sort.iter()
+            .map(|(column, asc)| {
+                model
+                    .compare_cell(&rows[a.0], &rows[b.0], column.0)
+                    .tap_mut(|x| {
+                        if !asc.0 {
+                            *x = x.reverse()
+                        }
+                    })
+            })
+            .find(|x| x.is_ne())
+            .unwrap_or_else(|| a.cmp(&b))
+    }
+
+    /// Repositions the rows edited since the last validation by binary search, instead of
+    /// filtering and sorting the whole rows again.
+    fn resort_stale_rows<V: RowViewer<R> + ?Sized>(&mut self, table: &DataTable<R>, vwr: &mut V) {
+        let stale = take(&mut self.cc_stale_rows);
+        let rows = &table.rows;
+        let mut model = vwr.model();
+
+        // Take out the stale rows first; the rest are still in order.
+        let mut first_changed = self.cc_rows.len();
+        for row in &stale {
+            if let Some(vis) = self.cc_row_id_to_vis.get(row) {
+                first_changed = first_changed.min(vis.0);
+            }
         }
 
-        let column_offset = min_column;
-        let mut buf_out = String::new();
-        let mut buf_tmp = String::new();
-        let mut row_cursor = 0;
+        self.cc_rows.retain(|x| !stale.contains(x));
 
-        for (row, columns, ..) in &clipboard.pastes.iter().chunk_by(|(row, ..)| *row) {
-            while row_cursor < row.0 {
-                tsv::write_newline(&mut buf_out);
-                row_cursor += 1;
+        for &row in &stale {
+            if row.0 >= rows.len() || table.trash.contains(&row.0) {
+                continue;
             }
 
-            let mut column_cursor = 0;
+            if !model.as_mut().is_none_or(|m| m.filter_row(&rows[row.0]))
+                || !self.passes_extra_filter(row, &rows[row.0])
+            {
+                continue;
+            }
 
-            for (_, column, data_idx) in columns.into_iter() {
-                while column_cursor < column.0 - column_offset {
-                    tsv::write_tab(&mut buf_out);
-                    column_cursor += 1;
-                }
+            let sort = &self.p.sort;
+            let pos = self.cc_rows.partition_point(|x| match &model {
+                Some(model) => Self::compare_rows(&**model, sort, rows, *x, row).is_lt(),
+                None => *x < row,
+            });
 
-                let data = &clipboard.slab[data_idx.0];
-                codec.encode_column(data, column.0, &mut buf_tmp);
+            first_changed = first_changed.min(pos);
+            self.cc_rows.insert(pos, row);
+        }
 
-                tsv::write_content(&mut buf_out, &buf_tmp);
-                buf_tmp.clear();
-            }
+        for row in &stale {
+            self.cc_row_id_to_vis.remove(row);
+        }
+
+        for (vis, row) in self.cc_rows.iter().enumerate().skip(first_changed) {
+            self.cc_row_id_to_vis.insert(*row, VisRowPos(vis));
         }
 
-        Some(buf_out)
+        self.refill_row_heights();
+
+        self.invalidate_row_summaries();
+
+        self.validate_interactive_cell(self.p.vis_cols.len());
     }
 
-    fn handle_desired_selection(&mut self) -> bool {
-        let Some((next_sel, sel)) = self.cc_desired_selection.take().and_then(|x| {
-            if let CursorState::Select(vec) = &mut self.cc_cursor {
-                Some((x, vec))
-            } else {
-                None
-            }
-        }) else {
-            return false;
+    fn computed_columns<V: RowViewer<R> + ?Sized>(vwr: &mut V, num_columns: usize) -> Vec<bool> {
+        (0..num_columns)
+            .map(|x| vwr.is_computed_column(x))
+            .collect()
+    }
+
+    fn column_keys<V: RowViewer<R> + ?Sized>(vwr: &mut V, num_columns: usize) -> Vec<String> {
+        (0..num_columns)
+            .map(|col| vwr.column_key(col).into_owned())
+            .collect()
+    }
+
+    /// Keys can be used for column identity only when they're unique.
+    fn is_unique_keys(keys: &[String]) -> bool {
+        !keys.is_empty() && keys.iter().all_unique()
+    }
+
+    fn remap_columns_by_key(&mut self, prev_keys: &[String], keys: &[String]) {
+        self.remap_columns(keys.len(), |col| {
+            let key = prev_keys.get(col.0)?;
+            keys.iter().position(|x| x == key).map(ColumnIdx)
+        });
+    }
+
+    /// Remap every column references of the state, including undo histories and selections.
+    /// Columns that `map` returns `None` are regarded as removed, and new columns that are not
+    /// mapped from any previous column become visible.
+    fn remap_columns(
+        &mut self,
+        new_num_columns: usize,
+        map: impl FnMut(ColumnIdx) -> Option<ColumnIdx>,
+    ) {
+        // Cache the mapping, as it may be expensive.
+        let table = (0..self.p.num_columns)
+            .map(ColumnIdx)
+            .map(map)
+            .collect_vec();
+        let map = |col: ColumnIdx| table.get(col.0).copied().flatten();
+        let remap = |col: &mut ColumnIdx| map(*col).map(|x| *col = x).is_some();
+
+        let old_vis_cols = take(&mut self.p.vis_cols);
+        let old_ncol = old_vis_cols.len();
+
+        let mut vis_cols = old_vis_cols
+            .iter()
+            .filter_map(|c| map(*c))
+            .unique()
+            .collect_vec();
+        let mapped: BTreeSet<_> = table.iter().flatten().copied().collect();
+        vis_cols.extend(
+            (0..new_num_columns)
+                .map(ColumnIdx)
+                .filter(|c| !mapped.contains(c)),
+        );
+
+        if vis_cols.is_empty() {
+            // Every visible column is gone; simply show everything.
+            vis_cols.extend((0..new_num_columns).map(ColumnIdx));
+        }
+
+        let new_ncol = vis_cols.len();
+        let remap_vis_col = |col: VisColumnPos| {
+            let col = map(*old_vis_cols.get(col.0)?)?;
+            vis_cols.iter().position(|x| *x == col).map(VisColumnPos)
         };
 
-        // If there's any desired selections present for next validation, apply it.
+        // Remap selections, by shrinking the rectangle into remaining columns.
+        if let CursorState::Select(sels) = &mut self.cc_cursor {
+            sels.retain_mut(|sel| {
+                let (top, left) = sel.0.row_col(old_ncol);
+                let (bottom, right) = sel.1.row_col(old_ncol);
+
+                let Some((min, max)) = (left.0..=right.0)
+                    .filter_map(|c| remap_vis_col(VisColumnPos(c)))
+                    .minmax()
+                    .into_option()
+                else {
+                    return false;
+                };
 
-        sel.clear();
-        let ncol = self.p.vis_cols.len();
+                *sel = VisSelection(
+                    top.linear_index(new_ncol, min),
+                    bottom.linear_index(new_ncol, max),
+                );
+                true
+            });
+        } else {
+            // Edition can't be committed here; it's just cancelled.
+            self.cc_cursor = CursorState::Select(Vec::new());
+        }
 
-        for (row_id, columns) in next_sel {
-            let vis_row = self.cc_row_id_to_vis[&row_id];
+        let (ic_r, ic_c) = self.cc_interactive_cell.row_col(old_ncol.max(1));
+        let ic_c = remap_vis_col(ic_c).unwrap_or_default();
+        self.cc_interactive_cell = ic_r.linear_index(new_ncol, ic_c);
+        self.cc_sel_extent = None;
+        self.cci_selection = None;
 
-            if columns.is_empty() {
-                let p_left = vis_row.linear_index(ncol, VisColumnPos(0));
-                let p_right = vis_row.linear_index(ncol, VisColumnPos(ncol - 1));
+        self.p.sort.retain_mut(|(col, _)| remap(col));
 
-                sel.push(VisSelection(p_left, p_right));
-            } else {
-                for col in columns {
-                    let Some(vis_c) = self.p.vis_cols.iter().position(|x| *x == col) else {
-                        continue;
-                    };
+        if let Some(clip) = &mut self.clipboard {
+            let mut pastes = take(&mut clip.pastes).into_vec();
+            pastes.retain_mut(|(_, col, _)| remap(col));
+            clip.pastes = pastes.into_boxed_slice();
 
-                    let p = vis_row.linear_index(ncol, VisColumnPos(vis_c));
-                    sel.push(VisSelection(p, p));
-                }
+            if clip.pastes.is_empty() {
+                self.clipboard = None;
             }
         }
 
-        true
-    }
+        for arg in self.undo_queue.iter_mut() {
+            Self::remap_command_columns(&mut arg.apply, remap);
+            for cmd in arg.restore.iter_mut() {
+                Self::remap_command_columns(cmd, remap);
+            }
+        }
 
-    pub fn vis_cols(&self) -> &Vec<ColumnIdx> {
-        &self.p.vis_cols
-    }
+        for steps in [Some(&mut self.macro_steps), self.macro_recording.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            steps.retain_mut(|step| match step {
+                MacroStep::SetCell(col, _) => remap(col),
+                MacroStep::Action(_) => true,
+            });
+        }
 
-    pub fn force_mark_dirty(&mut self) {
+        if let Some(desired) = &mut self.cc_desired_selection {
+            for (_, cols) in desired.iter_mut() {
+                cols.retain_mut(|col| remap(col));
+            }
+        }
+
+        for flash in self.cc_cell_flashes.iter_mut() {
+            flash.cells.retain_mut(|(_, col)| match col {
+                Some(col) => remap(col),
+                None => true,
+            });
+        }
+
+        self.p.vis_cols = vis_cols;
+        self.p.num_columns = new_num_columns;
+        self.cc_prev_n_columns = new_num_columns;
         self.cc_dirty = true;
     }
 
-    pub fn row_editing_cell(&mut self, row_id: RowIdx) -> Option<(bool, VisColumnPos)> {
-        match &mut self.cc_cursor {
-            CursorState::Edit {
-                row,
-                last_focus,
-                next_focus,
-                ..
-            } if *row == row_id => Some((replace(next_focus, false), *last_focus)),
-            _ => None,
+    fn remap_command_columns(cmd: &mut UndoCommand<R>, remap: impl Fn(&mut ColumnIdx) -> bool) {
+        let cmd = match cmd {
+            UndoCommand::Plain(cmd) => cmd,
+            UndoCommand::Encoded(cmd, rows) => {
+                for column in rows.columns.iter_mut() {
+                    if column.as_mut().is_some_and(|col| !remap(col)) {
+                        *column = None;
+                    }
+                }
+
+                if let EncodedCommand::SetCells(values) = cmd {
+                    let mut v = take(values).into_vec();
+                    v.retain_mut(|(_, col, _)| remap(col));
+                    *values = v.into_boxed_slice();
+                }
+
+                return;
+            }
+        };
+
+        match cmd {
+            Command::SetColumnSort(sort) => sort.retain_mut(|(col, _)| remap(col)),
+            Command::SetVisibleColumns(cols) => cols.retain_mut(|col| remap(col)),
+            Command::SetCells { values, .. } => {
+                let mut v = take(values).into_vec();
+                v.retain_mut(|(_, col, _)| remap(col));
+                *values = v.into_boxed_slice();
+            }
+            Command::SetRowValue(..)
+            | Command::InsertRows(..)
+            | Command::RemoveRowRanges(..)
+            | Command::TrashRows(..)
+            | Command::RestoreRows(..)
+            | Command::MoveRows { .. } => {}
+            _ => unreachable!("cache commands are never stored in undo queue"),
         }
     }
 
-    pub fn num_columns(&self) -> usize {
-        self.p.num_columns
-    }
+    #[cfg(feature = "persistency")]
+    pub fn validate_persistency<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        ctx: &egui::Context,
+        ui_id: egui::Id,
+        vwr: &mut V,
+    ) {
+        if !self.is_p_loaded {
+            // Load initial storage status
+            self.is_p_loaded = true;
+            self.cc_dirty = true;
+            let Some(p) = ctx.memory_mut(|m| m.data.get_persisted::<PersistData>(ui_id)) else {
+                return;
+            };
 
-    pub fn sort(&self) -> &[(ColumnIdx, IsAscending)] {
-        &self.p.sort
-    }
+            let schema_version = self.p.schema_version;
+            let num_columns = self.p.num_columns;
+            let column_keys = Self::column_keys(vwr, num_columns);
 
-    pub fn unwrap_editing_row_data(&mut self) -> &mut R {
-        match &mut self.cc_cursor {
-            CursorState::Edit { edition, .. } => edition,
-            _ => unreachable!(),
+            if p.column_keys != column_keys
+                && Self::is_unique_keys(&p.column_keys)
+                && Self::is_unique_keys(&column_keys)
+            {
+                // Columns are identified by their keys, which is robust against the column
+                // set changes between sessions.
+                let prev_keys = p.column_keys.clone();
+                self.p = p;
+                self.p.schema_version = schema_version;
+                self.remap_columns_by_key(&prev_keys, &column_keys);
+            } else if p.schema_version != schema_version {
+                // Stored data is from different schema; remap it to the current one.
+                let prev_version = p.schema_version;
+                self.p = p;
+                self.p.schema_version = schema_version;
+                self.remap_columns(num_columns, |col| {
+                    vwr.remap_column(prev_version, col.0)
+                        .filter(|x| *x < num_columns)
+                        .map(ColumnIdx)
+                });
+            } else if p.num_columns == num_columns {
+                // Data should only be copied when column count matches. Otherwise, we regard
+                // stored column differs from the current.
+                self.p = p;
+            }
+
+            // Only retain valid sorting configuration.
+            self.p.sort.retain(|(col, _)| vwr.is_sortable_column(col.0));
+        } else if self.cc_dirty {
+            // Copy current ui status into persistency storage.
+            self.p.column_keys = Self::column_keys(vwr, self.p.num_columns);
+            ctx.memory_mut(|m| m.data.insert_persisted(ui_id, self.p.clone()));
         }
     }
 
-    pub fn is_editing(&self) -> bool {
-        matches!(self.cc_cursor, CursorState::Edit { .. })
-    }
+    pub fn validate_cc<V: RowViewer<R> + ?Sized>(&mut self, table: &DataTable<R>, vwr: &mut V) {
+        if !self.cc_dirty && self.cc_num_table_rows != table.rows.len() {
+            log::debug!("cache out of sync with the table, rebuilding");
+            self.cc_dirty = true;
+            self.cc_revalidate_all = true;
+            self.cc_row_height_of.clear();
+            self.cc_shown_rows = None;
+        }
 
-    pub fn is_selected(&self, row: VisRowPos, col: VisColumnPos) -> bool {
-        if let CursorState::Select(selections) = &self.cc_cursor {
-            selections
-                .iter()
-                .any(|sel| self.vis_sel_contains(*sel, row, col))
-        } else {
-            false
+        if !self.cc_dirty && take(&mut self.cc_resort_stale) {
+            // Pinned rows are out of the sort order, so they're placed by the full rebuild.
+            let any_pinned = self.cc_num_pinned_rows > 0
+                || self
+                    .cc_stale_rows
+                    .iter()
+                    .any(|row| table.rows.get(row.0).is_some_and(|x| vwr.is_pinned_row(x)));
+
+            // Repositioning is O(n) per row; fall back to the full rebuild for bulk edits. Tree
+            // rows are sorted among their siblings, which is left to the full rebuild as well.
+            if !any_pinned
+                && self.cc_tree.is_empty()
+                && self.cc_stale_rows.len() * Self::MAX_RESORT_RATIO <= self.cc_rows.len()
+            {
+                self.resort_stale_rows(table, vwr);
+            } else {
+                self.cc_dirty = true;
+            }
         }
-    }
 
-    pub fn is_selected_cci(&self, row: VisRowPos, col: VisColumnPos) -> bool {
-        self.cci_selection.is_some_and(|(pivot, current)| {
-            self.vis_sel_contains(
-                VisSelection::from_points(self.p.vis_cols.len(), pivot, current),
-                row,
-                col,
-            )
-        })
-    }
+        if !replace(&mut self.cc_dirty, false) {
+            self.handle_desired_selection();
+            return;
+        }
 
-    pub fn is_interactive_row(&self, row: VisRowPos) -> Option<VisColumnPos> {
-        let (r, c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
-        (r == row).then_some(c)
-    }
+        self.cc_stale_rows.clear();
+        self.cc_resort_stale = false;
 
-    pub fn interactive_cell(&self) -> (VisRowPos, VisColumnPos) {
-        self.cc_interactive_cell.row_col(self.p.vis_cols.len())
-    }
+        // Selection follows its rows over the filter change; the rows hidden by it are
+        // remembered, to guard the operations on the selection.
+        if take(&mut self.cc_filter_changed) && self.cc_num_table_rows == table.rows.len() {
+            let selected = self.selected_cells_by_row();
+            self.cc_hidden_selection
+                .extend(selected.iter().map(|x| x.0));
 
-    pub fn cci_sel_update(&mut self, current: VisLinearIdx) {
-        if let Some((_, pivot)) = &mut self.cci_selection {
-            *pivot = current;
-        } else {
-            self.cci_selection = Some((current, current));
+            if !selected.is_empty() {
+                self.cc_desired_selection = Some(selected);
+            }
         }
-    }
 
-    pub fn cci_sel_update_row(&mut self, row: VisRowPos) {
-        [0, self.p.vis_cols.len() - 1].map(|col| {
-            self.cci_sel_update(row.linear_index(self.p.vis_cols.len(), VisColumnPos(col)))
+        // We should validate the entire cache.
+        if self.cc_num_table_rows != table.rows.len() {
+            // Pending paste refers to the rows before the change.
+            self.cc_pending_paste = None;
+            self.cc_cut = None;
+            self.cc_collapsed.clear();
+            self.cc_select_all_data = false;
+            self.cc_pending_all_data = None;
+            self.cc_hidden_selection.clear();
+            self.cc_pending_hidden = None;
+        }
+
+        self.cc_rows.clear();
+        self.cc_num_table_rows = table.rows.len();
+        let rows = &table.rows;
+
+        #[cfg(feature = "rayon")]
+        let parallel = vwr.parallel_model().map(|model| {
+            let candidates = (0..rows.len())
+                .filter(|i| !table.trash.contains(i))
+                .collect();
+            let sort: Vec<_> = self.p.sort.iter().map(|(c, asc)| (c.0, asc.0)).collect();
+            model.filter_and_sort(rows, candidates, &sort)
         });
-    }
 
-    pub fn has_cci_selection(&self) -> bool {
-        self.cci_selection.is_some()
-    }
+        #[cfg(not(feature = "rayon"))]
+        let parallel: Option<Vec<usize>> = None;
 
-    pub fn vis_sel_contains(&self, sel: VisSelection, row: VisRowPos, col: VisColumnPos) -> bool {
-        sel.contains(self.p.vis_cols.len(), row, col)
-    }
+        let extra = self.extra_filter.as_ref().map(|x| &x.1);
+        let shown = self.cc_shown_rows.as_ref();
+        let is_shown = |i: usize| {
+            shown.is_none_or(|x| x.contains(&RowIdx(i))) && extra.is_none_or(|f| f(&rows[i]))
+        };
 
-    fn vis_sel_to_row<'a>(&'a self, table: &'a DataTable<R>, sel: &VisSelection) -> &'a R {
-        let (ic_r, _ic_c) = sel.1.row_col(self.p.vis_cols.len());
-        let row_id = self.cc_rows[ic_r.0];
+        if let Some(visible) = parallel {
+            self.cc_rows
+                .extend(visible.into_iter().filter(|&i| is_shown(i)).map(RowIdx));
+        } else {
+            let mut model = vwr.model();
+            self.cc_rows.extend(
+                rows.iter()
+                    .enumerate()
+                    .filter(|(i, _)| !table.trash.contains(i) && is_shown(*i))
+                    .filter_map(|(i, x)| {
+                        model.as_mut().is_none_or(|m| m.filter_row(x)).then_some(i)
+                    })
+                    .map(RowIdx),
+            );
 
-        (&table.rows[row_id.0]) as _
-    }
+            if let Some(model) = model.filter(|_| !self.p.sort.is_empty()) {
+                let sort = &self.p.sort;
+                self.cc_rows
+                    .sort_by(|a, b| Self::compare_rows(&*model, sort, rows, *a, *b));
+            }
+        }
 
-    fn get_highlight_changes<'a>(
-        &'a self,
-        table: &'a DataTable<R>,
-        sel: &[VisSelection],
-    ) -> (Vec<&'a R>, Vec<&'a R>) {
-        let mut ohs: BTreeSet<&VisSelection> = BTreeSet::default();
-        let nhs: BTreeSet<&VisSelection> = sel.iter().collect();
+        self.arrange_tree(table, vwr);
 
-        if let CursorState::Select(s) = &self.cc_cursor {
-            ohs = s.iter().collect();
-        }
-        let new_highlight = nhs.difference(&ohs);
-        let old_highlight = ohs.difference(&nhs);
-        let highlighted: Vec<&R> = new_highlight
-            .into_iter()
-            .map(|v| self.vis_sel_to_row(table, v))
-            .collect();
-        let unhighlighted: Vec<&R> = old_highlight
-            .into_iter()
-            .map(|v| self.vis_sel_to_row(table, v))
+        // Pinned rows lead the others, regardless of the sort and filter.
+        let pinned: Vec<_> = (0..rows.len())
+            .filter(|i| !table.trash.contains(i) && vwr.is_pinned_row(&rows[*i]))
+            .map(RowIdx)
             .collect();
-        (highlighted, unhighlighted)
-    }
 
-    pub fn push_new_command<V: RowViewer<R>>(
-        &mut self,
-        table: &mut DataTable<R>,
-        vwr: &mut V,
-        command: Command<R>,
-        capacity: usize,
-    ) {
-        if self.is_editing() && !matches!(command, Command::CcCancelEdit | Command::CcCommitEdit) {
-            // If any non-editing command is pushed while editing, commit it first
-            self.push_new_command(table, vwr, Command::CcCommitEdit, capacity);
+        if !pinned.is_empty() {
+            self.cc_rows.retain(|x| pinned.binary_search(x).is_err());
         }
 
-        // Generate redo argument from command
-        let restore = match command {
-            Command::CcHideColumn(column_idx) => {
-                if self.p.vis_cols.len() == 1 {
-                    return;
-                }
+        self.cc_num_pinned_rows = pinned.len();
+        self.cc_rows.splice(0..0, pinned);
+
+        if take(&mut self.cc_revalidate_all) {
+            self.cc_invalid_cells.clear();
+            self.validate_rows(
+                table,
+                vwr,
+                (0..rows.len())
+                    .filter(|i| !table.trash.contains(i))
+                    .map(RowIdx),
+            );
+        }
 
-                let mut vis_cols = self.p.vis_cols.clone();
-                let idx = vis_cols.iter().position(|x| *x == column_idx).unwrap();
-                vis_cols.remove(idx);
+        self.refill_row_heights();
 
-                self.push_new_command(table, vwr, Command::SetVisibleColumns(vis_cols), capacity);
-                return;
-            }
-            Command::CcShowColumn { what, at } => {
-                assert!(self.p.vis_cols.iter().all(|x| *x != what));
+        self.invalidate_row_summaries();
 
-                let mut vis_cols = self.p.vis_cols.clone();
-                vis_cols.insert(at.0, what);
+        self.cc_row_id_to_vis.clear();
+        self.cc_row_id_to_vis.extend(
+            self.cc_rows
+                .iter()
+                .enumerate()
+                .map(|(i, id)| (*id, VisRowPos(i))),
+        );
 
-                self.push_new_command(table, vwr, Command::SetVisibleColumns(vis_cols), capacity);
-                return;
+        let row_id_to_vis = &self.cc_row_id_to_vis;
+        self.cc_hidden_selection
+            .retain(|x| !row_id_to_vis.contains_key(x));
+
+        if self.handle_desired_selection() {
+            // no-op.
+        } else if let CursorState::Select(cursor) = &mut self.cc_cursor {
+            // Validate cursor range if it's still in range.
+
+            let old_cols = self.cc_prev_n_columns;
+            let new_rows = self.cc_rows.len();
+            let new_cols = self.p.num_columns;
+            self.cc_prev_n_columns = self.p.num_columns;
+
+            cursor.retain_mut(|sel| {
+                let (old_min_r, old_min_c) = sel.0.row_col(old_cols);
+                if old_min_r.0 >= new_rows || old_min_c.0 >= new_cols {
+                    return false;
+                }
+
+                let (mut old_max_r, mut old_max_c) = sel.1.row_col(old_cols);
+                old_max_r.0 = old_max_r.0.min(new_rows.saturating_sub(1));
+                old_max_c.0 = old_max_c.0.min(new_cols.saturating_sub(1));
+
+                let min = old_min_r.linear_index(new_cols, old_min_c);
+                let max = old_max_r.linear_index(new_cols, old_max_c);
+                *sel = VisSelection(min, max);
+
+                true
+            });
+        } else {
+            self.cc_cursor = CursorState::Select(Vec::default());
+        }
+
+        if let Some((row_id, column)) = self.cc_pending_edit.take() {
+            let is_editable = self
+                .p
+                .vis_cols
+                .get(column.0)
+                .is_some_and(|col| !vwr.is_computed_column(col.0));
+
+            if let Some(vis_row) = self.cc_row_id_to_vis.get(&row_id).filter(|_| is_editable) {
+                self.cc_interactive_cell = vis_row.linear_index(self.p.vis_cols.len(), column);
+
+                if self.detached_editing {
+                    self.edit_request = Some((row_id, self.p.vis_cols[column.0]));
+                } else {
+                    self.cc_cursor = CursorState::Edit {
+                        edition: vwr.clone_row(&table.rows[row_id.0]),
+                        next_focus: true,
+                        last_focus: column,
+                        row: row_id,
+                        generation: self.cc_generation,
+                    };
+                }
+            }
+        }
+
+        // Prevent overflow.
+        self.validate_interactive_cell(self.p.vis_cols.len());
+    }
+
+    /// Arranges the filtered and sorted rows as the tree given by [`RowViewer::row_depth`], where
+    /// the parent of a row is the nearest preceding row of smaller depth in data order. Ancestors
+    /// of the rows passing the filter are kept, siblings follow the sort, and descendants of the
+    /// collapsed rows are hidden.
+    fn arrange_tree<V: RowViewer<R> + ?Sized>(&mut self, table: &DataTable<R>, vwr: &mut V) {
+        let rows = &table.rows;
+        let n = rows.len();
+        let alive = || (0..n).filter(|i| !table.trash.contains(i));
+
+        let mut depths = vec![0; n];
+        for i in alive() {
+            depths[i] = vwr.row_depth(&rows[i]);
+        }
+
+        self.cc_tree.clear();
+        if depths.iter().all(|x| *x == 0) {
+            return;
+        }
+
+        let mut parents = vec![None; n];
+        let mut ancestors = Vec::<usize>::new();
+        self.cc_tree.extend(depths.iter().map(|x| (*x, false)));
+
+        for i in alive() {
+            while ancestors.last().is_some_and(|x| depths[*x] >= depths[i]) {
+                ancestors.pop();
+            }
+
+            if let Some(&parent) = ancestors.last() {
+                parents[i] = Some(parent);
+                self.cc_tree[parent].1 = true;
+            }
+
+            ancestors.push(i);
+        }
+
+        // Rows out of the filter follow the others among siblings, in data order.
+        let mut ranks = vec![usize::MAX; n];
+        let mut kept = vec![false; n];
+
+        for (rank, row) in self.cc_rows.iter().enumerate() {
+            ranks[row.0] = rank;
+            kept[row.0] = true;
+        }
+
+        for row in &self.cc_rows {
+            let mut parent = parents[row.0];
+
+            while let Some(x) = parent.filter(|x| !kept[*x]) {
+                kept[x] = true;
+                parent = parents[x];
+            }
+        }
+
+        // The last one holds the roots.
+        let mut children = vec![Vec::new(); n + 1];
+        for i in (0..n).filter(|i| kept[*i]) {
+            children[parents[i].unwrap_or(n)].push(i);
+        }
+
+        for siblings in &mut children {
+            siblings.sort_by_key(|x| (ranks[*x], *x));
+        }
+
+        self.cc_rows.clear();
+        let mut stack = Vec::from_iter(children[n].iter().rev().copied());
+
+        while let Some(i) = stack.pop() {
+            self.cc_rows.push(RowIdx(i));
+
+            if !self.cc_collapsed.contains(&RowIdx(i)) {
+                stack.extend(children[i].iter().rev());
+            }
+        }
+    }
+
+    /// Depth of the tree row, whether it has any child, and whether it's expanded; [`None`] if
+    /// no row is nested.
+    pub fn tree_node(&self, row: RowIdx) -> Option<(usize, bool, bool)> {
+        let (depth, has_children) = *self.cc_tree.get(row.0)?;
+        Some((depth, has_children, !self.cc_collapsed.contains(&row)))
+    }
+
+    /// Expands or collapses the tree row. Selection follows its rows, like the filter change.
+    pub fn toggle_tree_row(&mut self, row: RowIdx) {
+        if !self.cc_collapsed.remove(&row) {
+            self.cc_collapsed.insert(row);
+        }
+
+        self.cc_dirty = true;
+        self.cc_filter_changed = true;
+    }
+
+    /// Commands which append a new row created with [`EmptyRowCreateContext::InsertNewLine`].
+    /// Editing of the row at given column starts after the insertion is validated, as the
+    /// rebuild of the cache drops any ongoing edition.
+    pub fn append_new_row<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+        column: VisColumnPos,
+    ) -> Vec<Command<R>> {
+        let row_id = RowIdx(table.rows.len());
+        let context = EmptyRowCreateContext::InsertNewLine;
+        let mut row = vwr.new_empty_row_for(context);
+        Self::fill_default_cells(vwr, &mut row, self.p.num_columns, &[], context);
+
+        self.cc_pending_edit = Some((row_id, column));
+        vec![Command::InsertRows(row_id, [row].into())]
+    }
+
+    /// Writes [`RowViewer::default_cell_value`] into the columns of the new row other than
+    /// `given`.
+    pub(crate) fn fill_default_cells<V: RowViewer<R> + ?Sized>(
+        vwr: &mut V,
+        row: &mut R,
+        num_columns: usize,
+        given: &[ColumnIdx],
+        context: EmptyRowCreateContext<R>,
+    ) {
+        for column in (0..num_columns).filter(|x| !given.contains(&ColumnIdx(*x))) {
+            if vwr.is_computed_column(column) {
+                continue;
+            }
+
+            if let Some(value) = vwr.default_cell_value(column, context) {
+                vwr.set_cell(row, column, value);
+            }
+        }
+    }
+
+    pub fn try_update_clipboard_from_string<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        vwr: &mut V,
+        contents: &str,
+    ) -> bool {
+        /*
+            NOTE: System clipboard implementation
+
+            We can't just determine if the internal clipboard should be preferred over the system
+            clipboard, as the source of clipboard contents can be vary. Therefore, on every copy
+            operation, we should clear out the system clipboard unconditionally, then we tries to
+            encode the copied content into clipboard.
+
+            TODO: Need to find way to handle if both of system clipboard and internal clipboard
+            content exist. We NEED to determine if which content should be applied for this.
+
+            # Dumping
+
+            - For rectangular(including single cell) selection of data, we'll just create
+              appropriate sized small TSV data which suits within given range.
+                - Note that this'll differentiate the clipboard behavior from internal-only
+                  version.
+            - For non-rectangular selections, full-scale rectangular table is dumped which
+              can cover all selection range including empty selections; where any data that
+              is not being dumped is just emptied out.
+                - From this, any data cell that is just 'empty' but selected, should be dumped
+                  as explicit empty data; in this case, empty data wrapped with double
+                  quotes("").
+
+            # Decoding
+
+            - Every format is regarded as delimited text, which is described by the codec's
+              `ClipboardFormat`. (only delimiter, newline and quotes matter)
+            - For data with same column count with this table
+                - Parse as full-scale table, then put into clipboard as-is.
+            - Column count is less than current table
+                - In this case, current selection matters.
+                - Offset the copied content table as the selection column offset.
+                - Then create clipboard data from it.
+            - If column count is larger than this, it is invalid data; we just skip parsing
+        */
+
+        // Clipboard content from elsewhere replaces the one of the deferred cut.
+        if let Some(cut) = &self.cc_cut {
+            if cut
+                .text
+                .as_ref()
+                .is_none_or(|x| !x.lines().eq(contents.lines()))
+            {
+                self.cc_cut = None;
+            }
+        }
+
+        // Codec borrows the viewer; collect column keys in advance for header detection.
+        let keys = Self::column_keys(vwr, self.p.num_columns);
+        let computed = Self::computed_columns(vwr, self.p.num_columns);
+
+        let Some(mut codec) = vwr.try_create_codec(false) else {
+            // Even when there is system clipboard content, we're going to ignore it and use
+            // internal clipboard if there's no way to parse it.
+            return false;
+        };
+
+        if let CursorState::Select(selections) = &self.cc_cursor {
+            let Some(first) = selections.first().map(|x| x.0) else {
+                // No selectgion present. Do nothing
+                return false;
+            };
+
+            let (.., col) = first.row_col(self.p.vis_cols.len());
+            col
+        } else {
+            // If there's no selection, we'll just ignore the system clipboard input
+            return false;
+        };
+
+        let selection_offset = if let CursorState::Select(sel) = &self.cc_cursor {
+            sel.first().map_or(0, |idx| {
+                let (_, c) = idx.0.row_col(self.p.vis_cols.len());
+                c.0
+            })
+        } else {
+            0
+        };
+
+        // JSON records are mapped by the column keys; unknown keys and null values are ignored.
+        let records = Self::is_unique_keys(&keys)
+            .then(|| json::parse_records(contents))
+            .flatten()
+            .map(|records| {
+                Vec::from_iter(records.into_iter().map(|record| {
+                    Vec::from_iter(record.into_iter().filter_map(|(key, value)| {
+                        Some((keys.iter().position(|x| *x == key)?, value?))
+                    }))
+                }))
+            });
+
+        let rows = match records {
+            Some(rows) if rows.iter().all(Vec::is_empty) => return false,
+            Some(rows) => rows,
+            None => {
+                let format = codec.clipboard_format();
+                let Some(rows) = self.delimited_rows(contents, &format, &keys, selection_offset)
+                else {
+                    return false;
+                };
+
+                rows
+            }
+        };
+
+        // If any cell is failed to be parsed, we'll just give up all parsing then use internal
+        // clipboard instead.
+
+        let mut slab = Vec::new();
+        let mut pastes = Vec::new();
+        let mut skipped_rows = 0;
+
+        for (row_offset, row_data) in rows.iter().enumerate() {
+            let slab_id = slab.len();
+            slab.push(codec.create_empty_decoded_row());
+
+            // The restoration point of pastes stack.
+            let pastes_restore = pastes.len();
+
+            for &(col_idx, ref data) in row_data {
+                if computed.get(col_idx).copied().unwrap_or_default()
+                    || !codec.supports_column(col_idx)
+                {
+                    continue;
+                }
+
+                match codec.decode_column(data, col_idx, &mut slab[slab_id]) {
+                    Ok(_) => {
+                        pastes.push((
+                            VisRowOffset(row_offset),
+                            ColumnIdx(col_idx),
+                            RowSlabIndex(slab_id),
+                        ));
+                    }
+                    Err(DecodeErrorBehavior::SkipCell) => {
+                        // Skip this cell.
+                    }
+                    Err(DecodeErrorBehavior::SkipRow) => {
+                        pastes.drain(pastes_restore..);
+                        slab.pop();
+                        skipped_rows += 1;
+                        break;
+                    }
+                    Err(DecodeErrorBehavior::Abort) => {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        // Replace the clipboard content from the parsed data.
+        self.clipboard = Some(Clipboard {
+            slab: slab.into_boxed_slice(),
+            pastes: pastes.into_boxed_slice(),
+            skipped_rows,
+        });
+
+        true
+    }
+
+    /// Cells of the delimited text by rows, as pairs of the column and the text.
+    fn delimited_rows(
+        &self,
+        contents: &str,
+        format: &ClipboardFormat,
+        keys: &[String],
+        selection_offset: usize,
+    ) -> Option<Vec<Vec<(usize, String)>>> {
+        let view = delimited::ParsedTable::parse(contents, format);
+        let table_width = view.calc_table_width();
+
+        // If the first row consists of column keys, it's regarded as header; then columns are
+        // mapped by the header rather than the position.
+        let header = Self::is_unique_keys(keys)
+            .then(|| view.iter_rows().next())
+            .flatten()
+            .and_then(|(_, cells)| {
+                cells
+                    .map(|(_, key)| keys.iter().position(|x| x == key).map(ColumnIdx))
+                    .collect::<Option<Vec<_>>>()
+            });
+
+        if header.is_none() && table_width > self.p.vis_cols.len() {
+            // If the copied data has more columns than current table, we'll just ignore it.
+            return None;
+        }
+
+        let n_header_rows = header.is_some() as usize;
+        let mut rows = Vec::new();
+
+        for (_, row_data) in view.iter_rows().skip(n_header_rows) {
+            let mut row = Vec::new();
+
+            for (column, data) in row_data {
+                let col_idx = if let Some(header) = &header {
+                    let Some(col) = header.get(column) else {
+                        // Cells without header are ignored.
+                        continue;
+                    };
+
+                    col.0
+                } else {
+                    column + selection_offset
+                };
+
+                if col_idx > self.p.vis_cols.len() {
+                    // If the column is out of range, we'll just ignore it.
+                    return None;
+                }
+
+                row.push((col_idx, data.to_owned()));
+            }
+
+            rows.push(row);
+        }
+
+        Some(rows)
+    }
+
+    fn try_dump_clipboard_content<V: RowViewer<R> + ?Sized>(
+        clipboard: &Clipboard<R>,
+        vwr: &mut V,
+    ) -> Option<String> {
+        // clipboard MUST be sorted before dumping; XXX: add assertion?
+        let format = vwr
+            .try_create_codec(true)
+            .map_or_else(default, |codec| codec.clipboard_format());
+
+        if format.json {
+            return Self::try_dump_json(clipboard, vwr);
+        }
+
+        // Without codec, the cells are dumped as text-only; nothing to dump if there's no text.
+        let mut encode = text_encoder(vwr);
+        let mut has_text = false;
+
+        let mut width = 0;
+        let mut height = 0;
+
+        // We're going to offset the column to the minimum column index to make the selection copy
+        // more intuitive. If not, the copied data will be shifted to the right if the selection is
+        // not the very first column.
+        let mut min_column = usize::MAX;
+
+        for (row, column, ..) in clipboard.pastes.iter() {
+            width = width.max(column.0 + 1);
+            height = height.max(row.0 + 1);
+            min_column = min_column.min(column.0);
+        }
+
+        let column_offset = min_column;
+        let mut buf_out = String::new();
+        let mut buf_tmp = String::new();
+        let mut row_cursor = 0;
+
+        for (row, columns, ..) in &clipboard.pastes.iter().chunk_by(|(row, ..)| *row) {
+            while row_cursor < row.0 {
+                delimited::write_newline(&mut buf_out);
+                row_cursor += 1;
+            }
+
+            let mut column_cursor = 0;
+
+            for (_, column, data_idx) in columns.into_iter() {
+                while column_cursor < column.0 - column_offset {
+                    delimited::write_delimiter(&mut buf_out, &format);
+                    column_cursor += 1;
+                }
+
+                let data = &clipboard.slab[data_idx.0];
+                has_text |= encode(data, column.0, &mut buf_tmp);
+
+                delimited::write_content(&mut buf_out, &buf_tmp, &format);
+                buf_tmp.clear();
+            }
+        }
+
+        has_text.then_some(buf_out)
+    }
+
+    /// Dumps the clipboard as JSON records; see [`ClipboardFormat::json`].
+    fn try_dump_json<V: RowViewer<R> + ?Sized>(
+        clipboard: &Clipboard<R>,
+        vwr: &mut V,
+    ) -> Option<String> {
+        let num_columns = vwr.num_columns();
+        let keys = Self::column_keys(vwr, num_columns);
+        let mut encode = text_encoder(vwr);
+        let mut has_text = false;
+
+        let mut buf_out = String::from("[");
+        let mut buf_tmp = String::new();
+        let mut row_cursor = 0;
+
+        for (row, columns) in &clipboard.pastes.iter().chunk_by(|(row, ..)| *row) {
+            // Rows skipped by the selection are kept as empty records, to keep the offsets.
+            while row_cursor < row.0 {
+                buf_out.push_str(if row_cursor == 0 { "{}" } else { ",\n{}" });
+                row_cursor += 1;
+            }
+
+            buf_out.push_str(if row_cursor == 0 { "{" } else { ",\n{" });
+            row_cursor += 1;
+
+            for (i, (_, column, data_idx)) in columns.enumerate() {
+                if i > 0 {
+                    buf_out.push(',');
+                }
+
+                let data = &clipboard.slab[data_idx.0];
+                has_text |= encode(data, column.0, &mut buf_tmp);
+
+                json::write_string(&mut buf_out, &keys[column.0]);
+                buf_out.push(':');
+                json::write_value(&mut buf_out, &buf_tmp);
+                buf_tmp.clear();
+            }
+
+            buf_out.push('}');
+        }
+
+        buf_out.push(']');
+        has_text.then_some(buf_out)
+    }
+
+    fn handle_desired_selection(&mut self) -> bool {
+        let Some((next_sel, sel)) = self.cc_desired_selection.take().and_then(|x| {
+            if let CursorState::Select(vec) = &mut self.cc_cursor {
+                Some((x, vec))
+            } else {
+                None
+            }
+        }) else {
+            return false;
+        };
+
+        // If there's any desired selections present for next validation, apply it.
+        self.cc_selection_status = None;
+
+        sel.clear();
+        let ncol = self.p.vis_cols.len();
+
+        for (row_id, columns) in next_sel {
+            // Desired row may be filtered out.
+            let Some(&vis_row) = self.cc_row_id_to_vis.get(&row_id) else {
+                continue;
+            };
+
+            if columns.is_empty() {
+                let p_left = vis_row.linear_index(ncol, VisColumnPos(0));
+                let p_right = vis_row.linear_index(ncol, VisColumnPos(ncol - 1));
+
+                sel.push(VisSelection(p_left, p_right));
+            } else {
+                for col in columns {
+                    let Some(vis_c) = self.p.vis_cols.iter().position(|x| *x == col) else {
+                        continue;
+                    };
+
+                    let p = vis_row.linear_index(ncol, VisColumnPos(vis_c));
+                    sel.push(VisSelection(p, p));
+                }
+            }
+        }
+
+        true
+    }
+
+    pub fn vis_cols(&self) -> &Vec<ColumnIdx> {
+        &self.p.vis_cols
+    }
+
+    pub fn force_mark_dirty(&mut self) {
+        self.cc_dirty = true;
+        self.cc_revalidate_all = true;
+        self.cc_generation = self.cc_generation.wrapping_add(1);
+        self.cc_last_commit = None;
+    }
+
+    pub fn row_editing_cell(&mut self, row_id: RowIdx) -> Option<(bool, VisColumnPos)> {
+        match &mut self.cc_cursor {
+            CursorState::Edit {
+                row,
+                last_focus,
+                next_focus,
+                ..
+            } if *row == row_id => Some((replace(next_focus, false), *last_focus)),
+            _ => None,
+        }
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.p.num_columns
+    }
+
+    pub fn sort(&self) -> &[(ColumnIdx, IsAscending)] {
+        &self.p.sort
+    }
+
+    pub fn unwrap_editing_row_data(&mut self) -> &mut R {
+        match &mut self.cc_cursor {
+            CursorState::Edit { edition, .. } => edition,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        matches!(self.cc_cursor, CursorState::Edit { .. })
+    }
+
+    pub fn is_selected(&self, row: VisRowPos, col: VisColumnPos) -> bool {
+        if let CursorState::Select(selections) = &self.cc_cursor {
+            selections
+                .iter()
+                .any(|sel| self.vis_sel_contains(*sel, row, col))
+        } else {
+            false
+        }
+    }
+
+    pub fn is_selected_cci(&self, row: VisRowPos, col: VisColumnPos) -> bool {
+        self.cci_selection.is_some_and(|(pivot, current)| {
+            self.vis_sel_contains(
+                VisSelection::from_points(self.p.vis_cols.len(), pivot, current),
+                row,
+                col,
+            )
+        })
+    }
+
+    pub fn is_interactive_row(&self, row: VisRowPos) -> Option<VisColumnPos> {
+        let (r, c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
+        (r == row).then_some(c)
+    }
+
+    pub fn interactive_cell(&self) -> (VisRowPos, VisColumnPos) {
+        self.cc_interactive_cell.row_col(self.p.vis_cols.len())
+    }
+
+    /// Moving corner of the selection extended by keyboard, or the interactive cell if the
+    /// selection was changed otherwise since.
+    pub fn selection_extent(&self) -> (VisRowPos, VisColumnPos) {
+        let ncol = self.p.vis_cols.len();
+        let ic = self.cc_interactive_cell;
+
+        match (self.cc_sel_extent, self.cursor_as_selection()) {
+            (Some(ext), Some(&[sel])) if sel == VisSelection::from_points(ncol, ic, ext) => {
+                ext.row_col(ncol)
+            }
+            _ => ic.row_col(ncol),
+        }
+    }
+
+    /// Interactive cell in data space; editing cell takes precedence if present.
+    pub fn interactive_cell_index(&self) -> Option<(usize, usize)> {
+        if let CursorState::Edit {
+            row, last_focus, ..
+        } = &self.cc_cursor
+        {
+            return Some((row.0, self.p.vis_cols.get(last_focus.0)?.0));
+        }
+
+        let (r, c) = self.interactive_cell();
+        Some((self.cc_rows.get(r.0)?.0, self.p.vis_cols.get(c.0)?.0))
+    }
+
+    /// Screen rectangle of the cell in data space, if rendered in the latest frame.
+    pub fn cell_rect(&self, row: usize, column: usize) -> Option<egui::Rect> {
+        self.cci_visible_cells
+            .iter()
+            .find(|x| x.row == row && x.column == column)
+            .map(|x| x.rect)
+    }
+
+    /// Number of pinned rows, which lead the visible rows.
+    pub fn num_pinned_rows(&self) -> usize {
+        self.cc_num_pinned_rows
+    }
+
+    /// Visual position of given data row, if it is visible under current sort/filter.
+    pub fn visual_position_of(&self, row: usize) -> Option<usize> {
+        self.cc_row_id_to_vis.get(&RowIdx(row)).map(|x| x.0)
+    }
+
+    /// Data row index placed at given visual position.
+    pub fn row_at_visual(&self, pos: usize) -> Option<usize> {
+        self.cc_rows.get(pos).map(|x| x.0)
+    }
+
+    /// Rows shown by the current filter, in visual order.
+    pub fn visible_rows(&self) -> impl Iterator<Item = RowIdx> + '_ {
+        self.cc_rows.iter().copied()
+    }
+
+    pub fn cci_sel_update(&mut self, current: VisLinearIdx) {
+        if let Some((_, pivot)) = &mut self.cci_selection {
+            *pivot = current;
+        } else {
+            self.cci_selection = Some((current, current));
+        }
+    }
+
+    pub fn cci_sel_update_row(&mut self, row: VisRowPos) {
+        if self.cci_selection.is_none() {
+            self.cci_sel_rows = true;
+        }
+
+        for col in [0, self.p.vis_cols.len() - 1] {
+            self.cci_sel_update(row.linear_index(self.p.vis_cols.len(), VisColumnPos(col)))
+        }
+    }
+
+    pub fn has_cci_selection(&self) -> bool {
+        self.cci_selection.is_some()
+    }
+
+    /// Selection in progress spans more than a single cell.
+    pub fn is_range_selecting(&self) -> bool {
+        self.cci_selection
+            .is_some_and(|(pivot, current)| pivot != current)
+    }
+
+    /// Whether the cell is marked as the source of the deferred cut.
+    pub fn is_cut_source(&self, row: VisRowPos, column: ColumnIdx) -> bool {
+        self.cc_cut.as_ref().is_some_and(|cut| {
+            (self.cc_rows.get(row.0)).is_some_and(|r| cut.cells.contains(&(*r, column)))
+        })
+    }
+
+    pub fn vis_sel_contains(&self, sel: VisSelection, row: VisRowPos, col: VisColumnPos) -> bool {
+        sel.contains(self.p.vis_cols.len(), row, col)
+    }
+
+    fn vis_sel_to_row<'a>(&'a self, table: &'a DataTable<R>, sel: &VisSelection) -> &'a R {
+        let (ic_r, _ic_c) = sel.1.row_col(self.p.vis_cols.len());
+        let row_id = self.cc_rows[ic_r.0];
+
+        (&table.rows[row_id.0]) as _
+    }
+
+    fn get_highlight_changes<'a>(
+        &'a self,
+        table: &'a DataTable<R>,
+        sel: &[VisSelection],
+    ) -> (Vec<&'a R>, Vec<&'a R>) {
+        let mut ohs: BTreeSet<&VisSelection> = BTreeSet::default();
+        let nhs: BTreeSet<&VisSelection> = sel.iter().collect();
+
+        if let CursorState::Select(s) = &self.cc_cursor {
+            ohs = s.iter().collect();
+        }
+        let new_highlight = nhs.difference(&ohs);
+        let old_highlight = ohs.difference(&nhs);
+        let highlighted: Vec<&R> = new_highlight
+            .into_iter()
+            .map(|v| self.vis_sel_to_row(table, v))
+            .collect();
+        let unhighlighted: Vec<&R> = old_highlight
+            .into_iter()
+            .map(|v| self.vis_sel_to_row(table, v))
+            .collect();
+        (highlighted, unhighlighted)
+    }
+
+    pub fn set_undo_capacity(&mut self, capacity: usize) {
+        self.undo_capacity = capacity;
+    }
+
+    pub fn set_undo_enabled(&mut self, enabled: bool) {
+        self.undo_enabled = enabled;
+
+        if !enabled {
+            self.undo_queue = VecDeque::new();
+            self.undo_cursor = 0;
+        }
+    }
+
+    pub fn set_soft_delete(&mut self, soft_delete: bool) {
+        self.soft_delete = soft_delete;
+    }
+
+    pub fn set_detached_editing(&mut self, detached_editing: bool) {
+        self.detached_editing = detached_editing;
+    }
+
+    /// Takes the cell requested to be edited in detached editing mode, in data space.
+    pub fn take_edit_request(&mut self) -> Option<(usize, usize)> {
+        self.edit_request.take().map(|(row, col)| (row.0, col.0))
+    }
+
+    /// Replace the extra filter; the rows are filtered again if the hash of its key changed.
+    pub fn set_extra_filter(&mut self, filter: Option<(u64, ExtraFilter<R>)>) {
+        if self.extra_filter.as_ref().map(|x| x.0) != filter.as_ref().map(|x| x.0) {
+            self.cc_dirty = true;
+            self.cc_filter_changed = true;
+        }
+
+        self.extra_filter = filter;
+    }
+
+    fn passes_extra_filter(&self, row_id: RowIdx, row: &R) -> bool {
+        self.cc_shown_rows
+            .as_ref()
+            .is_none_or(|x| x.contains(&row_id))
+            && self.extra_filter.as_ref().is_none_or(|(_, f)| f(row))
+    }
+
+    pub fn is_showing_selected_only(&self) -> bool {
+        self.cc_shown_rows.is_some()
+    }
+
+    pub fn set_compress_undo_history(&mut self, compress: bool) {
+        self.compress_undo_history = compress;
+    }
+
+    pub fn set_frame_time(&mut self, time: f64) {
+        self.cc_time = time;
+    }
+
+    /// Merges the commit into the latest undo step, if it's a commit on the same cell under
+    /// [`CommitPolicy::Coalesce`].
+    fn try_coalesce_commit<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        row_id: RowIdx,
+        column: ColumnIdx,
+        edition: &R,
+    ) -> bool {
+        let CommitPolicy::Coalesce { window } = vwr.column_commit_policy(column.0) else {
+            return false;
+        };
+
+        let Some((last_row, last_col, last_time)) = self.cc_last_commit else {
+            return false;
+        };
+
+        let is_latest_commit = self.undo_cursor == 0
+            && self
+                .undo_queue
+                .front()
+                .is_some_and(|x| x.apply.is_set_row_value_of(row_id));
+
+        if last_row != row_id
+            || last_col != column
+            || self.cc_time - last_time > window as f64
+            || !is_latest_commit
+        {
+            return false;
+        }
+
+        // Restoration point of the latest step is kept; only the applied value is replaced.
+        let cmd = Command::SetRowValue(row_id, vwr.clone_row(edition).into());
+        self.cmd_apply(table, vwr, &cmd);
+        self.undo_queue[0].apply = self.undo_command(vwr, cmd);
+        self.cc_last_commit = Some((row_id, column, self.cc_time));
+
+        true
+    }
+
+    /// Values of the selected cells, in visual order.
+    pub fn selected_cell_values<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+    ) -> Vec<(usize, usize, V::CellValue)> {
+        // Selection may refer stale rows, if the table was modified programmatically.
+        self.validate_cc(table, vwr);
+
+        self.collect_selection()
+            .into_iter()
+            .filter_map(|(vis_row, vis_col)| {
+                let row = self.cc_rows[vis_row.0];
+                let col = self.p.vis_cols[vis_col.0];
+                Some((row.0, col.0, vwr.get_cell(&table.rows[row.0], col.0)?))
+            })
+            .collect()
+    }
+
+    pub fn map_selection<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        whole_row: bool,
+        mut f: impl FnMut(&mut R, usize),
+    ) -> usize {
+        // Selection may refer stale rows, if the table was modified programmatically.
+        self.validate_cc(table, vwr);
+
+        let mut slab = Vec::new();
+        let mut values = Vec::new();
+        let all_columns = (0..self.p.num_columns).map(ColumnIdx).collect_vec();
+
+        for (vis_row, vis_cols) in &self.collect_selection().into_iter().chunk_by(|(r, _)| *r) {
+            let row_id = self.cc_rows[vis_row.0];
+            let slab_id = RowSlabIndex(slab.len());
+            let mut row = vwr.clone_row(&table.rows[row_id.0]);
+
+            if whole_row {
+                f(&mut row, 0);
+                values.extend(all_columns.iter().map(|col| (row_id, *col, slab_id)));
+            } else {
+                for (_, vis_col) in vis_cols {
+                    let col = self.p.vis_cols[vis_col.0];
+                    f(&mut row, col.0);
+                    values.push((row_id, col, slab_id));
+                }
+            }
+
+            slab.push(row);
+        }
+
+        if slab.is_empty() {
+            return 0;
+        }
+
+        let num_rows = slab.len();
+        let cmd = Command::SetCells {
+            slab: slab.into_boxed_slice(),
+            values: values.into_boxed_slice(),
+        };
+
+        self.push_new_command(table, vwr, cmd, self.undo_capacity);
+        num_rows
+    }
+
+    /// Writes `(row, column, source)` edits as a single undoable command. Edits out of range, or
+    /// of computed columns, are skipped. Returns the number of written cells.
+    pub fn apply_edit<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        edits: &[(usize, usize, R)],
+    ) -> usize {
+        self.validate_cc(table, vwr);
+
+        let mut slab = Vec::new();
+        let mut values = Vec::new();
+
+        for (row, column, src) in edits {
+            if *row >= table.rows.len()
+                || *column >= self.p.num_columns
+                || vwr.is_computed_column(*column)
+            {
+                continue;
+            }
+
+            values.push((RowIdx(*row), ColumnIdx(*column), RowSlabIndex(slab.len())));
+            slab.push(vwr.clone_row(src));
+        }
+
+        if values.is_empty() {
+            return 0;
+        }
+
+        let num_cells = values.len();
+        let cmd = Command::SetCells {
+            slab: slab.into_boxed_slice(),
+            values: values.into_boxed_slice(),
+        };
+
+        self.push_new_command(table, vwr, cmd, self.undo_capacity);
+        num_cells
+    }
+
+    pub fn insert_rows<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        index: usize,
+        rows: Vec<R>,
+    ) -> usize {
+        self.validate_cc(table, vwr);
+
+        let num_rows = rows.len();
+        if num_rows == 0 {
+            return 0;
+        }
+
+        let pos = RowIdx(index.min(table.rows.len()));
+        let cmd = Command::InsertRows(pos, rows.into_boxed_slice());
+
+        self.push_new_command(table, vwr, cmd, self.undo_capacity);
+        num_rows
+    }
+
+    pub fn push_new_command<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        command: Command<R>,
+        capacity: usize,
+    ) {
+        if self.is_editing() && !matches!(command, Command::CcCancelEdit | Command::CcCommitEdit) {
+            // If any non-editing command is pushed while editing, commit it first
+            self.push_new_command(table, vwr, Command::CcCommitEdit, capacity);
+        }
+
+        // Generate redo argument from command
+        let mut snapshot = Vec::new();
+        let restore = match command {
+            Command::CcHideColumn(column_idx) => {
+                if self.p.vis_cols.len() == 1 || !vwr.is_hidable_column(column_idx.0) {
+                    return;
+                }
+
+                let mut vis_cols = self.p.vis_cols.clone();
+                let idx = vis_cols.iter().position(|x| *x == column_idx).unwrap();
+                vis_cols.remove(idx);
+
+                self.push_new_command(table, vwr, Command::SetVisibleColumns(vis_cols), capacity);
+                return;
+            }
+            Command::CcShowColumn { what, at } => {
+                assert!(self.p.vis_cols.iter().all(|x| *x != what));
+
+                let mut vis_cols = self.p.vis_cols.clone();
+                vis_cols.insert(at.0, what);
+
+                self.push_new_command(table, vwr, Command::SetVisibleColumns(vis_cols), capacity);
+                return;
+            }
+            Command::SetVisibleColumns(ref value) => {
+                if self.p.vis_cols.iter().eq(value.iter()) {
+                    return;
+                }
+
+                vec![Command::SetVisibleColumns(self.p.vis_cols.clone())]
+            }
+            Command::CcReorderColumn { from, to } => {
+                if from == to || to.0 > self.p.vis_cols.len() {
+                    // Reorder may deliver invalid parameter if there's multiple data
+                    // tables present at the same time; as the drag drop payload are
+                    // compatible between different tables...
+                    return;
+                }
+
+                let mut vis_cols = self.p.vis_cols.clone();
+                if from.0 < to.0 {
+                    vis_cols.insert(to.0, vis_cols[from.0]);
+                    vis_cols.remove(from.0);
+                } else {
+                    vis_cols.remove(from.0).pipe(|x| vis_cols.insert(to.0, x));
+                }
+
+                // Locked columns stay at their positions.
+                let is_locked_moved = (self.p.vis_cols.iter().zip(&vis_cols))
+                    .any(|(prev, new)| prev != new && !vwr.is_reorderable_column(prev.0));
+
+                if is_locked_moved {
+                    return;
+                }
+
+                self.push_new_command(table, vwr, Command::SetVisibleColumns(vis_cols), capacity);
+                return;
+            }
+            Command::CcEditStart(row_id, column_pos, current) => {
+                if self
+                    .p
+                    .vis_cols
+                    .get(column_pos.0)
+                    .is_some_and(|col| vwr.is_computed_column(col.0))
+                {
+                    return;
+                }
+
+                // Update interactive cell.
+                if let Some(vis_row) = self.cc_row_id_to_vis.get(&row_id) {
+                    self.cc_interactive_cell =
+                        vis_row.linear_index(self.p.vis_cols.len(), column_pos);
+                }
+
+                if self.detached_editing {
+                    self.edit_request = Some((row_id, self.p.vis_cols[column_pos.0]));
+                    return;
+                }
+
+                // EditStart command is directly applied.
+                self.cc_cursor = CursorState::Edit {
+                    edition: *current,
+                    next_focus: true,
+                    last_focus: column_pos,
+                    row: row_id,
+                    generation: self.cc_generation,
+                };
+
+                // No redo argument is generated.
+                return;
+            }
+            ref cmd @ (Command::CcCancelEdit | Command::CcCommitEdit) => {
+                // This edition state become selection. Restorat
+                let Some((row_id, edition, last_focus, generation)) = self.try_take_edition()
+                else {
+                    return;
+                };
+
+                if matches!(cmd, Command::CcCancelEdit) {
+                    // Cancellation does not affect to any state.
+                    return;
+                }
+
+                // The table was mutated from code during the edition; the row index may now
+                // point to another row, or out of bounds.
+                let row_id = if generation != self.cc_generation {
+                    match vwr.resolve_stale_edit(&table.rows, row_id.0, &edition) {
+                        Some(row) if row < table.rows.len() && !table.trash.contains(&row) => {
+                            RowIdx(row)
+                        }
+                        _ => return,
+                    }
+                } else {
+                    row_id
+                };
+
+                let column = self.p.vis_cols[last_focus.0];
+                if self.try_coalesce_commit(table, vwr, row_id, column, &edition) {
+                    return;
+                }
+
+                // Change command type of self.
+                self.push_new_command(
+                    table,
+                    vwr,
+                    Command::SetRowValue(row_id, edition.into()),
+                    capacity,
+                );
+
+                self.cc_last_commit = Some((row_id, column, self.cc_time));
+                return;
+            }
+
+            Command::SetRowValue(row_id, _) => {
+                vec![Command::SetRowValue(
+                    row_id,
+                    vwr.clone_row(&table.rows[row_id.0]).into(),
+                )]
+            }
+
+            Command::CcSetCells {
+                context,
+                slab,
+                values,
+            } => {
+                let mut values = values.to_vec();
+
+                values.retain(|(row, col, slab_id)| {
+                    !vwr.is_computed_column(col.0)
+                        && vwr.confirm_cell_write_by_ui(
+                            &table.rows[row.0],
+                            &slab[slab_id.0],
+                            col.0,
+                            context,
+                        )
+                });
+
+                return self.push_new_command(
+                    table,
+                    vwr,
+                    Command::SetCells {
+                        slab,
+                        values: values.into_boxed_slice(),
+                    },
+                    capacity,
+                );
+            }
+
+            Command::SetCells { ref values, .. } => {
+                let mut keys = Vec::from_iter(values.iter().map(|(r, ..)| *r));
+                keys.dedup();
+
+                keys.iter()
+                    .map(|row_id| {
+                        Command::SetRowValue(*row_id, vwr.clone_row(&table.rows[row_id.0]).into())
+                    })
+                    .collect()
+            }
+
+            Command::SetColumnSort(ref sort) => {
+                if self.p.sort.iter().eq(sort.iter()) {
+                    return;
+                }
+
+                vec![Command::SetColumnSort(self.p.sort.clone())]
+            }
+            Command::CcExtendSelection(ext) => {
+                let sel = vec![VisSelection::from_points(
+                    self.p.vis_cols.len(),
+                    self.cc_interactive_cell,
+                    ext,
+                )];
+
+                let (highlighted, unhighlighted) = self.get_highlight_changes(table, &sel);
+                vwr.on_highlight_change(&highlighted, &unhighlighted);
+                self.cc_select_all_data = false;
+                self.cc_hidden_selection.clear();
+                self.cc_selection_status = None;
+                self.cc_sel_extent = Some(ext);
+                self.cc_cursor = CursorState::Select(sel);
+                return;
+            }
+            Command::CcSelectAllData => {
+                self.cc_select_all_data = true;
+                return;
+            }
+            Command::CcSetSelection(sel) => {
+                self.cc_select_all_data = false;
+                self.cc_hidden_selection.clear();
+                self.cc_selection_status = None;
+
+                if !sel.is_empty() {
+                    self.cc_interactive_cell = sel[0].0;
+                }
+
+                let (highlighted, unhighlighted) = self.get_highlight_changes(table, &sel);
+                vwr.on_highlight_change(&highlighted, &unhighlighted);
+                self.cc_cursor = CursorState::Select(sel);
+                return;
+            }
+            Command::InsertRows(pivot, ref values) => {
+                vec![Command::RemoveRowRanges(
+                    [pivot..RowIdx(pivot.0 + values.len())].into(),
+                )]
+            }
+            Command::MoveRows { from, len, to } => {
+                vec![Command::MoveRows {
+                    from: to,
+                    len,
+                    to: from,
+                }]
+            }
+            Command::TrashRows(ref rows) | Command::RestoreRows(ref rows) if rows.is_empty() => {
+                return;
+            }
+            Command::TrashRows(ref rows) => vec![Command::RestoreRows(rows.clone())],
+            Command::RestoreRows(ref rows) => vec![Command::TrashRows(rows.clone())],
+            Command::RemoveRowRanges(ref ranges) => {
+                if ranges.is_empty() {
+                    // From various sources, it can be just 'empty' removal command
+                    return;
+                }
+
+                debug_assert!(ranges.windows(2).all(|x| x[0].end < x[1].start));
+
+                // Removed rows are restored chunk by chunk, in ascending order. Each chunk is
+                // encoded as it's taken when the history is compressed, so that mass deletion
+                // never holds another copy of every removed row.
+                if self.undo_enabled {
+                    for range in ranges.iter() {
+                        for start in (range.start.0..range.end.0).step_by(Self::UNDO_CHUNK_ROWS) {
+                            let end = (start + Self::UNDO_CHUNK_ROWS).min(range.end.0);
+                            let rows = table.rows[start..end]
+                                .iter()
+                                .map(|x| Self::share_row(vwr, x))
+                                .collect();
+
+                            let cmd = Command::InsertRows(RowIdx(start), rows);
+                            snapshot.push(self.undo_command(vwr, cmd));
+                        }
+                    }
+                }
+
+                vec![]
+            }
+            Command::CcUpdateSystemClipboard(..) => {
+                // This command MUST've be consumed before calling this.
+                unreachable!()
+            }
+        };
+
+        // Any new command breaks the coalescing of commits.
+        self.cc_last_commit = None;
+
+        if !self.undo_enabled {
+            // Nothing is recorded; the restoration commands are dropped right away.
+            self.cmd_apply(table, vwr, &command);
+            return;
+        }
+
+        // Discard all redos after this point.
+        self.undo_queue.drain(0..self.undo_cursor);
+
+        // Discard all undos that exceed the capacity.
+        let new_len = capacity.saturating_sub(1).min(self.undo_queue.len());
+        self.undo_queue.drain(new_len..);
+
+        // Now it's the foremost element of undo queue.
+        self.undo_cursor = 0;
+
+        // Apply the command.
+        self.cmd_apply(table, vwr, &command);
+
+        // Push the command to the queue.
+        let apply = self.undo_command(vwr, command);
+        let restore = snapshot
+            .into_iter()
+            .chain(restore.into_iter().map(|cmd| self.undo_command(vwr, cmd)))
+            .collect();
+        self.undo_queue.push_front(UndoArg { apply, restore });
+    }
+
+    fn undo_command<V: RowViewer<R> + ?Sized>(
+        &self,
+        vwr: &mut V,
+        cmd: Command<R>,
+    ) -> UndoCommand<R> {
+        if self.compress_undo_history {
+            UndoCommand::encode(vwr, self.p.num_columns, cmd)
+        } else {
+            UndoCommand::Plain(cmd)
+        }
+    }
+
+    /// Applies commands of the undo history, then notifies the viewer. Encoded commands are
+    /// decoded first, so that the step is applied either entirely or not at all; returns false
+    /// if decoding failed.
+    fn apply_undo_commands<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        kind: UndoKind,
+        cmds: &[UndoCommand<R>],
+    ) -> bool {
+        let mut decoded = Vec::new();
+        for cmd in cmds {
+            if let UndoCommand::Encoded(cmd, rows) = cmd {
+                let Some(cmd) = cmd.decode(rows, vwr) else {
+                    return false;
+                };
+
+                decoded.push(cmd);
+            }
+        }
+
+        let mut decoded = decoded.into_iter();
+        let mut changes = Vec::with_capacity(cmds.len());
+        for cmd in cmds {
+            let decoded_cmd;
+            let cmd = match cmd {
+                UndoCommand::Plain(cmd) => cmd,
+                UndoCommand::Encoded(..) => {
+                    decoded_cmd = decoded.next().unwrap();
+                    &decoded_cmd
+                }
+            };
+
+            self.cmd_apply(table, vwr, cmd);
+            changes.extend(Self::table_change(cmd));
+        }
+
+        vwr.on_undo_applied(kind, &changes);
+        true
+    }
+
+    fn table_change(cmd: &Command<R>) -> Option<TableChange> {
+        let rows = |rows: &[RowIdx]| rows.iter().map(|x| x.0).collect();
+
+        Some(match cmd {
+            Command::SetRowValue(row, _) => TableChange::RowModified(row.0),
+            Command::SetCells { values, .. } => {
+                TableChange::CellsModified(values.iter().map(|(r, c, _)| (r.0, c.0)).collect())
+            }
+            Command::InsertRows(pos, values) => TableChange::RowsInserted {
+                at: pos.0,
+                count: values.len(),
+            },
+            Command::RemoveRowRanges(ranges) => {
+                TableChange::RowsRemoved(ranges.iter().flat_map(|x| x.start.0..x.end.0).collect())
+            }
+            Command::MoveRows { from, len, to } => TableChange::RowsMoved {
+                from: from.0,
+                len: *len,
+                to: to.0,
+            },
+            Command::TrashRows(values) => TableChange::RowsTrashed(rows(values)),
+            Command::RestoreRows(values) => TableChange::RowsRestored(rows(values)),
+            Command::SetColumnSort(..) | Command::SetVisibleColumns(..) => TableChange::Layout,
+            _ => return None,
+        })
+    }
+
+    fn cmd_apply<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        cmd: &Command<R>,
+    ) {
+        // Cell edits keep the rows in place; only the search matches of the edited rows are
+        // updated below, instead of scanning the whole table again.
+        let search_stale = self.search.as_ref().map(|x| x.stale);
+        self.invalidate_row_summaries();
+
+        if matches!(cmd, Command::SetRowValue(..) | Command::SetCells { .. }) {
+            if let (Some(search), Some(stale)) = (&mut self.search, search_stale) {
+                search.stale = stale;
+            }
+        }
+
+        if matches!(
+            cmd,
+            Command::InsertRows(..) | Command::RemoveRowRanges(..) | Command::MoveRows { .. }
+        ) {
+            // Compared rows, the cut cells and the collapsed rows may have been moved.
+            self.cc_compared_rows = None;
+            self.cc_cut = None;
+            self.cc_collapsed.clear();
+        }
+
+        match cmd {
+            Command::SetVisibleColumns(cols) => {
+                self.validate_interactive_cell(cols.len());
+                self.p.vis_cols.clear();
+                self.p.vis_cols.extend(cols.iter().cloned());
+                self.cc_dirty = true;
+            }
+            Command::SetColumnSort(new_sort) => {
+                self.p.sort.clear();
+                self.p.sort.extend(new_sort.iter().cloned());
+                self.cc_dirty = true;
+            }
+            Command::SetRowValue(row_id, value) => {
+                self.cc_num_frame_from_last_edit = 0;
+                table.dirty_flag = true;
+                Self::write_row(vwr, &mut table.rows[row_id.0], value);
+                Self::record_changes(table, |rows| {
+                    vec![ChangeEvent::Modified {
+                        index: row_id.0,
+                        row: vwr.clone_row(&rows[row_id.0]),
+                    }]
+                });
+
+                self.cc_modified_rows.insert(*row_id);
+                self.cc_stale_rows.insert(*row_id);
+                self.queue_cell_flash(vec![(*row_id, None)]);
+                self.validate_rows(table, vwr, [*row_id]);
+                self.rematch_search_rows(table, vwr, [*row_id]);
+            }
+            Command::SetCells { slab, values } => {
+                self.cc_num_frame_from_last_edit = 0;
+                table.dirty_flag = true;
+
+                for (row, col, value_id) in values.iter() {
+                    vwr.set_cell_value(&slab[value_id.0], &mut table.rows[row.0], col.0);
+                }
+
+                self.cc_modified_rows
+                    .extend(values.iter().map(|(r, ..)| *r));
+                self.cc_stale_rows.extend(values.iter().map(|(r, ..)| *r));
+                self.queue_cell_flash(values.iter().map(|(r, c, _)| (*r, Some(*c))).collect());
+
+                let rows: BTreeSet<_> = values.iter().map(|(r, ..)| *r).collect();
+                Self::record_changes(table, |table_rows| {
+                    rows.iter()
+                        .map(|row| ChangeEvent::Modified {
+                            index: row.0,
+                            row: vwr.clone_row(&table_rows[row.0]),
+                        })
+                        .collect()
+                });
+                self.validate_rows(table, vwr, rows.iter().copied());
+                self.rematch_search_rows(table, vwr, rows);
+            }
+            Command::InsertRows(pos, values) => {
+                self.cc_dirty = true; // It invalidates all current `RowId` occurences.
+                table.dirty_flag = true;
+
+                table
+                    .rows
+                    .splice(pos.0..pos.0, values.iter().map(|x| Self::share_row(vwr, x)));
+                Self::record_changes(table, |rows| {
+                    (pos.0..pos.0 + values.len())
+                        .map(|index| ChangeEvent::Inserted {
+                            index,
+                            row: vwr.clone_row(&rows[index]),
+                        })
+                        .collect()
+                });
+
+                let shifted = table.trash.split_off(&pos.0);
+                table
+                    .trash
+                    .extend(shifted.into_iter().map(|x| x + values.len()));
+
+                self.cc_row_anims.reset();
+                self.cc_row_anims.fade_in.push(pos.0..pos.0 + values.len());
+                self.cc_cell_flashes.clear();
+                self.mark_inserted_rows(*pos, values.len());
+                self.remap_row_states(|row| {
+                    Some(if row >= *pos {
+                        RowIdx(row.0 + values.len())
+                    } else {
+                        row
+                    })
+                });
+
+                if let Some(shown) = &mut self.cc_shown_rows {
+                    shown.extend((pos.0..pos.0 + values.len()).map(RowIdx));
+                }
+
+                self.validate_rows(table, vwr, (pos.0..pos.0 + values.len()).map(RowIdx));
+
+                self.queue_select_rows((pos.0..pos.0 + values.len()).map(RowIdx));
+            }
+            Command::RemoveRowRanges(ranges) => {
+                self.cc_dirty = true; // It invalidates all current `RowId` occurences.
+                table.dirty_flag = true;
+
+                // Record the gaps left by removed rows, before `cc_row_heights` is invalidated.
+                // Each range collapses into a single gap, before the next survivor.
+                self.cc_cell_flashes.clear();
+                self.cc_row_anims.reset();
+                let mut n_removed_before = 0;
+                for range in ranges.iter() {
+                    let height = (range.start.0..range.end.0)
+                        .filter_map(|row| self.cc_row_id_to_vis.get(&RowIdx(row)))
+                        .filter_map(|vis| self.cc_row_heights.get(vis.0))
+                        .sum();
+
+                    let next = RowIdx(range.start.0 - n_removed_before);
+                    self.cc_row_anims.collapse.push((next, height));
+                    n_removed_before += range.end.0 - range.start.0;
+                }
+
+                let remap = Self::removed_row_remap(ranges);
+                self.cc_modified_rows = take(&mut self.cc_modified_rows)
+                    .into_iter()
+                    .filter_map(&remap)
+                    .collect();
+                self.remap_row_states(&remap);
+                table.trash = take(&mut table.trash)
+                    .into_iter()
+                    .filter_map(|row| Some(remap(RowIdx(row))?.0))
+                    .collect();
+
+                // From the last, so that the indices stay valid while replaying.
+                Self::record_changes(table, |rows| {
+                    (ranges.iter().rev())
+                        .flat_map(|x| (x.start.0..x.end.0).rev())
+                        .map(|index| ChangeEvent::Removed {
+                            index,
+                            row: vwr.clone_row(&rows[index]),
+                        })
+                        .collect()
+                });
+
+                let mut index = 0;
+                table.rows.retain(|_| {
+                    let idx_now = RowIdx(index.tap(|_| index += 1));
+                    remap(idx_now).is_some()
+                });
+
+                self.queue_select_rows([]);
+            }
+            &Command::MoveRows { from, len, to } => {
+                self.cc_dirty = true; // It invalidates all current `RowId` occurences.
+                table.dirty_flag = true;
+
+                if to < from {
+                    table.rows[to.0..from.0 + len].rotate_right(len);
+                } else {
+                    table.rows[from.0..to.0 + len].rotate_left(len);
+                }
+
+                // Let the viewer persist the manual order of every row which has moved.
+                let (lo, hi) = (from.0.min(to.0), from.0.max(to.0) + len);
+                for (index, row) in table.rows[lo..hi].iter_mut().enumerate() {
+                    vwr.write_order(row, lo + index);
+                }
+
+                Self::record_changes(table, |_| {
+                    vec![ChangeEvent::Moved {
+                        from: from.0,
+                        len,
+                        to: to.0,
+                    }]
+                });
+
+                self.cc_row_anims.reset();
+                self.cc_cell_flashes.clear();
+                self.cc_modified_rows = take(&mut self.cc_modified_rows)
+                    .into_iter()
+                    .map(|row| Self::moved_row_index(row, from, len, to))
+                    .collect();
+                self.remap_row_states(|row| Some(Self::moved_row_index(row, from, len, to)));
+                table.trash = take(&mut table.trash)
+                    .into_iter()
+                    .map(|row| Self::moved_row_index(RowIdx(row), from, len, to).0)
+                    .collect();
+
+                // The order keys written above may be validated as well.
+                self.validate_rows(table, vwr, (lo..hi).map(RowIdx));
+
+                self.queue_select_rows((to.0..to.0 + len).map(RowIdx));
+            }
+            Command::TrashRows(rows) => {
+                self.cc_dirty = true;
+                table.dirty_flag = true;
+                table.trash.extend(rows.iter().map(|x| x.0));
+                Self::record_changes(table, |_| {
+                    rows.iter().map(|x| ChangeEvent::Trashed(x.0)).collect()
+                });
+
+                // Soft-deleted rows are out of validation, as they're out of sight.
+                self.cc_invalid_cells
+                    .retain(|(row, _), _| !rows.contains(row));
+
+                self.queue_select_rows([]);
+            }
+            Command::RestoreRows(rows) => {
+                self.cc_dirty = true;
+                table.dirty_flag = true;
+                for row in rows {
+                    table.trash.remove(&row.0);
+                }
+                Self::record_changes(table, |_| {
+                    rows.iter().map(|x| ChangeEvent::Restored(x.0)).collect()
+                });
+
+                self.validate_rows(table, vwr, rows.iter().copied());
+
+                self.cc_row_anims.reset();
+                self.queue_select_rows(rows.iter().copied());
+            }
+            Command::CcHideColumn(..)
+            | Command::CcShowColumn { .. }
+            | Command::CcReorderColumn { .. }
+            | Command::CcEditStart(..)
+            | Command::CcCommitEdit
+            | Command::CcCancelEdit
+            | Command::CcSetSelection(..)
+            | Command::CcExtendSelection(..)
+            | Command::CcSelectAllData
+            | Command::CcSetCells { .. }
+            | Command::CcUpdateSystemClipboard(..) => unreachable!(),
+        }
+    }
+
+    /// Records the changes into the journal of the table, if it's enabled.
+    fn record_changes(table: &mut DataTable<R>, events: impl FnOnce(&[R]) -> Vec<ChangeEvent<R>>) {
+        let Some(journal) = &mut table.journal else {
+            return;
+        };
+
+        for event in events(&table.rows) {
+            event.push_into(journal);
+        }
+    }
+
+    /// Row to move in and out of the table; shares the handle if rows are stored behind one.
+    fn share_row<V: RowViewer<R> + ?Sized>(vwr: &mut V, row: &R) -> R {
+        if let Some(mut storage) = vwr.row_storage() {
+            return storage.share(row);
+        }
+
+        vwr.clone_row(row)
+    }
+
+    /// Overwrites the row in the table, keeping its identity if rows are stored behind a handle.
+    fn write_row<V: RowViewer<R> + ?Sized>(vwr: &mut V, dst: &mut R, src: &R) {
+        if let Some(mut storage) = vwr.row_storage() {
+            storage.write(dst, src);
+            return;
+        }
+
+        *dst = vwr.clone_row(src);
+    }
+
+    /// Maps the index of a row to the one after removal of `removed` ranges, or `None` if the
+    /// row is removed. `removed` must be sorted and disjoint.
+    fn removed_row_remap(removed: &[Range<RowIdx>]) -> impl Fn(RowIdx) -> Option<RowIdx> + '_ {
+        // Number of the removed rows up to each range, inclusive.
+        let n_removed = removed
+            .iter()
+            .scan(0, |acc, x| {
+                *acc += x.end.0 - x.start.0;
+                Some(*acc)
+            })
+            .collect::<Vec<_>>();
+
+        move |row| {
+            let n_before = removed.partition_point(|x| x.end <= row);
+            if removed.get(n_before).is_some_and(|x| x.start <= row) {
+                return None;
+            }
+
+            let n_removed = n_before.checked_sub(1).map_or(0, |x| n_removed[x]);
+            Some(RowIdx(row.0 - n_removed))
+        }
+    }
+
+    fn moved_row_index(row: RowIdx, from: RowIdx, len: usize, to: RowIdx) -> RowIdx {
+        if (from.0..from.0 + len).contains(&row.0) {
+            RowIdx(row.0 - from.0 + to.0)
+        } else if to < from && (to.0..from.0).contains(&row.0) {
+            RowIdx(row.0 + len)
+        } else if from < to && (from.0 + len..to.0 + len).contains(&row.0) {
+            RowIdx(row.0 - len)
+        } else {
+            row
+        }
+    }
+
+    /// Removes the rows in data order, or moves them into the trash on soft delete. `rows` must
+    /// be sorted and unique.
+    fn remove_rows(&self, rows: Vec<RowIdx>) -> Command<R> {
+        if self.soft_delete {
+            return Command::TrashRows(rows);
+        }
+
+        // Contiguous rows are removed as single range.
+        let ranges = rows
+            .chunk_by(|a, b| a.0 + 1 == b.0)
+            .map(|x| x[0]..RowIdx(x[x.len() - 1].0 + 1))
+            .collect();
+
+        Command::RemoveRowRanges(ranges)
+    }
+
+    /// Returns the range of the rows if they're contiguous. `rows` must be sorted and unique.
+    fn contiguous_range(rows: &[RowIdx]) -> Option<Range<RowIdx>> {
+        let (first, last) = (rows.first()?, rows.last()?);
+        (last.0 - first.0 + 1 == rows.len()).then(|| *first..RowIdx(last.0 + 1))
+    }
+
+    /// Position to insert new rows at given visual row. While sorted, new rows are appended at
+    /// the end, as the visual position doesn't matter.
+    pub fn insertion_pos(&self, table: &DataTable<R>, vis_row: VisRowPos) -> RowIdx {
+        match self.cc_rows.get(vis_row.0) {
+            Some(row) if self.p.sort.is_empty() => *row,
+            _ => RowIdx(table.rows.len()),
+        }
+    }
+
+    fn queue_select_rows(&mut self, rows: impl IntoIterator<Item = RowIdx>) {
+        self.cc_desired_selection = Some(rows.into_iter().map(|r| (r, default())).collect());
+    }
+
+    fn validate_interactive_cell(&mut self, new_num_column: usize) {
+        let (r, c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
+        let rmax = self.cc_rows.len().saturating_sub(1);
+        let clen = self.p.vis_cols.len();
+
+        self.cc_interactive_cell =
+            VisLinearIdx(r.0.min(rmax) * clen + c.0.min(new_num_column.saturating_sub(1)));
+    }
+
+    pub fn has_clipboard_contents(&self) -> bool {
+        self.clipboard.is_some()
+    }
+
+    /// Preview of the first `max_rows` rows and `max_columns` columns of the clipboard.
+    pub fn clipboard_preview<V: RowViewer<R> + ?Sized>(
+        &self,
+        vwr: &mut V,
+        max_rows: usize,
+        max_columns: usize,
+    ) -> Option<ClipboardPreview> {
+        let clip = self.clipboard.as_ref()?;
+        let columns: BTreeSet<_> = clip.pastes.iter().map(|x| x.1).collect();
+        let rows = clip.pastes.iter().map(|x| x.0 .0 + 1).max().unwrap_or(0);
+
+        let column_ids: Vec<_> = columns.iter().copied().take(max_columns).collect();
+        let mut cells = vec![vec![None; column_ids.len()]; rows.min(max_rows)];
+        let header = column_ids.iter().map(|c| vwr.column_name(c.0)).collect();
+        let mut encode = text_encoder(vwr);
+
+        for (row, column, slab_id) in clip.pastes.iter() {
+            let Some(row_cells) = cells.get_mut(row.0) else {
+                break; // Pastes are sorted by rows.
+            };
+
+            let Some(col) = column_ids.iter().position(|x| x == column) else {
+                continue;
+            };
+
+            let mut text = String::new();
+            encode(&clip.slab[slab_id.0], column.0, &mut text);
+            row_cells[col] = Some(text);
+        }
+
+        Some(ClipboardPreview {
+            rows,
+            columns: columns.len(),
+            header,
+            cells,
+        })
+    }
+
+    pub fn has_undo(&self) -> bool {
+        self.undo_cursor < self.undo_queue.len()
+    }
+
+    pub fn has_redo(&self) -> bool {
+        self.undo_cursor > 0
+    }
+
+    /// Number of steps which can be undone.
+    pub fn undo_depth(&self) -> usize {
+        self.undo_queue.len() - self.undo_cursor
+    }
+
+    pub fn cursor_as_selection(&self) -> Option<&[VisSelection]> {
+        match &self.cc_cursor {
+            CursorState::Select(x) => Some(x),
+            CursorState::Edit { .. } => None,
+        }
+    }
+
+    fn try_take_edition(&mut self) -> Option<(RowIdx, R, VisColumnPos, u64)> {
+        if matches!(self.cc_cursor, CursorState::Edit { .. }) {
+            match replace(&mut self.cc_cursor, CursorState::Select(Vec::default())) {
+                CursorState::Edit {
+                    row,
+                    edition,
+                    last_focus,
+                    generation,
+                    ..
+                } => Some((row, edition, last_focus, generation)),
+                _ => unreachable!(),
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn is_recording_macro(&self) -> bool {
+        self.macro_recording.is_some()
+    }
+
+    pub fn has_recorded_macro(&self) -> bool {
+        !self.macro_steps.is_empty()
+    }
+
+    pub fn queue_macro_replay(&mut self, replay: MacroReplay) {
+        self.macro_replay = Some(replay);
+    }
+
+    pub fn has_queued_macro_replay(&self) -> bool {
+        self.macro_replay.is_some()
+    }
+
+    /// Record the action as a macro step, if recording. This should be called before the
+    /// action is applied, as the edition state matters.
+    pub fn record_macro_action<V: RowViewer<R> + ?Sized>(&mut self, vwr: &mut V, action: UiAction) {
+        let Some(steps) = &mut self.macro_recording else {
+            return;
+        };
+
+        match (action, &self.cc_cursor) {
+            (
+                UiAction::CommitEdition | UiAction::CommitEditionAndMove(_),
+                CursorState::Edit {
+                    edition,
+                    last_focus,
+                    ..
+                },
+            ) => {
+                // Edition is recorded as the value of single column, rather than the whole row;
+                // therefore it can be replayed on other rows.
+                let column = self.p.vis_cols[last_focus.0];
+                steps.push(MacroStep::SetCell(column, vwr.clone_row(edition).into()));
+
+                if let UiAction::CommitEditionAndMove(dir) = action {
+                    steps.push(MacroStep::Action(UiAction::MoveSelection(dir)));
+                }
+            }
+
+            (
+                UiAction::SelectionStartEditing
+                | UiAction::CancelEdition
+                | UiAction::CommitEdition
+                | UiAction::CommitEditionAndMove(_)
+                | UiAction::ToggleMacroRecording
+                | UiAction::OpenSearch
+                | UiAction::HighlightDuplicates(_)
+                | UiAction::CompareRows
+                | UiAction::ReplayMacro
+                | UiAction::ReplayMacroOverSelection,
+                _,
+            ) => {
+                // Editor state transitions are not replayed.
+            }
+
+            (action, _) => steps.push(MacroStep::Action(action)),
+        }
+    }
+
+    pub fn handle_macro_replay<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        capacity: usize,
+    ) {
+        let Some(replay) = self.macro_replay.take() else {
+            return;
+        };
+
+        if self.is_recording_macro() || self.macro_steps.is_empty() {
+            return;
+        }
+
+        if self.is_editing() {
+            self.push_new_command(table, vwr, Command::CcCommitEdit, capacity);
+        }
+
+        let steps = take(&mut self.macro_steps);
+        self.validate_cc(table, vwr);
+
+        match replay {
+            MacroReplay::Times(count) => {
+                for _ in 0..count {
+                    self.replay_macro_once(table, vwr, &steps, capacity);
+                }
+            }
+            MacroReplay::OverSelectedRows => {
+                let (_, ic_c) = self.interactive_cell();
+                let rows = self
+                    .collect_selected_rows()
+                    .into_iter()
+                    .map(|x| self.cc_rows[x.0])
+                    .collect_vec();
+
+                for row in rows {
+                    self.validate_cc(table, vwr);
+
+                    // Row may have disappeared during the replay.
+                    let Some(vis_row) = self.cc_row_id_to_vis.get(&row) else {
+                        continue;
+                    };
+
+                    let pos = vis_row.linear_index(self.p.vis_cols.len(), ic_c);
+                    let cmd = Command::CcSetSelection(vec![VisSelection(pos, pos)]);
+                    self.push_new_command(table, vwr, cmd, capacity);
+                    self.replay_macro_once(table, vwr, &steps, capacity);
+                }
+            }
+        }
+
+        self.macro_steps = steps;
+    }
+
+    fn replay_macro_once<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+        steps: &[MacroStep<R>],
+        capacity: usize,
+    ) {
+        for step in steps {
+            self.validate_cc(table, vwr);
+
+            let commands = match step {
+                MacroStep::Action(action) => self.try_apply_ui_action(table, vwr, *action),
+                MacroStep::SetCell(column, value) => {
+                    let (ic_r, _) = self.interactive_cell();
+                    let Some(row) = self.cc_rows.get(ic_r.0) else {
+                        continue;
+                    };
+
+                    vec![Command::CcSetCells {
+                        slab: [vwr.clone_row(value)].into(),
+                        values: [(*row, *column, RowSlabIndex(0))].into(),
+                        context: CellWriteContext::Paste,
+                    }]
+                }
+            };
+
+            for cmd in commands {
+                if matches!(cmd, Command::CcUpdateSystemClipboard(..)) {
+                    // Replay doesn't touch the system clipboard; internal one is still updated.
+                    continue;
+                }
+
+                self.push_new_command(table, vwr, cmd, capacity);
+            }
+        }
+    }
+
+    pub fn ui_action_context(&self) -> UiActionContext {
+        UiActionContext {
+            cursor: match &self.cc_cursor {
+                CursorState::Select(x) => {
+                    if x.is_empty() {
+                        UiCursorState::Idle
+                    } else if x.len() == 1 && x[0].0 == x[0].1 {
+                        UiCursorState::SelectOne
+                    } else {
+                        UiCursorState::SelectMany
+                    }
+                }
+                CursorState::Edit { .. } => UiCursorState::Editing,
+            },
+        }
+    }
+
+    pub fn undo<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+    ) -> bool {
+        if self.undo_cursor == self.undo_queue.len() {
+            return false;
+        }
+
+        self.cc_last_commit = None;
+        let queue = take(&mut self.undo_queue);
+        let restore = &queue[self.undo_cursor].restore;
+        let applied = self.apply_undo_commands(table, vwr, UndoKind::Undo, restore);
+        if applied {
+            self.undo_cursor += 1;
+        }
+        self.undo_queue = queue;
+
+        applied
+    }
+
+    pub fn redo<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut V,
+    ) -> bool {
+        if self.undo_cursor == 0 {
+            return false;
+        }
+
+        self.cc_last_commit = None;
+        let queue = take(&mut self.undo_queue);
+        let apply = std::slice::from_ref(&queue[self.undo_cursor - 1].apply);
+        let applied = self.apply_undo_commands(table, vwr, UndoKind::Redo, apply);
+        if applied {
+            self.undo_cursor -= 1;
+        }
+        self.undo_queue = queue;
+
+        applied
+    }
+
+    pub fn set_interactive_cell(&mut self, row: VisRowPos, col: VisColumnPos) {
+        self.cc_interactive_cell = row.linear_index(self.p.vis_cols.len(), col);
+    }
+
+    /// Returns true if the action can be applied without any visible row.
+    fn is_action_rowless(action: UiAction) -> bool {
+        matches!(
+            action,
+            UiAction::ToggleMacroRecording
+                | UiAction::ReplayMacro
+                | UiAction::ReplayMacroOverSelection
+                | UiAction::OpenSearch
+                | UiAction::HighlightDuplicates(_)
+                | UiAction::CancelEdition
+                | UiAction::CommitEdition
+                | UiAction::Undo
+                | UiAction::Redo
+                | UiAction::PasteInsert
+                | UiAction::RestoreTrash
+                | UiAction::ToggleShowSelectedOnly
+                | UiAction::SelectAllIncludingFiltered
+                | UiAction::CancelCut
+        )
+    }
+
+    /// Actions which operate on the whole data while it's selected by
+    /// [`UiAction::SelectAllIncludingFiltered`].
+    fn is_action_on_all_data(action: UiAction) -> bool {
+        matches!(
+            action,
+            UiAction::CopySelection
+                | UiAction::CutSelection
+                | UiAction::DeleteSelection
+                | UiAction::DeleteRow
+        )
+    }
+
+    pub fn try_apply_ui_action(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut (impl RowViewer<R> + ?Sized),
+        action: UiAction,
+    ) -> Vec<Command<R>> {
+        fn empty<T, R>(_: T) -> Vec<Command<R>> {
+            default()
+        }
+
+        self.cci_want_move_scroll = true;
+
+        // Previous command of this frame may have invalidated the cache.
+        self.validate_cc(table, vwr);
+
+        // Operations over the whole data are applied only after the user's confirmation.
+        if self.cc_select_all_data && Self::is_action_on_all_data(action) {
+            self.cc_pending_all_data = Some(action);
+            return vec![];
+        }
+
+        // Selection spans the rows hidden by the filter, which are left untouched.
+        if self.confirm_hidden_selection
+            && !self.cc_hidden_selection.is_empty()
+            && matches!(
+                action,
+                UiAction::CutSelection | UiAction::DeleteSelection | UiAction::DeleteRow
+            )
+        {
+            self.cc_pending_hidden = Some(action);
+            return vec![];
+        }
+
+        if self.cc_rows.is_empty() && !Self::is_action_rowless(action) {
+            return vec![];
+        }
+
+        let (ic_r, ic_c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
+        match action {
+            UiAction::ToggleMacroRecording => {
+                if let Some(steps) = self.macro_recording.take() {
+                    self.macro_steps = steps;
+                } else {
+                    self.macro_recording = Some(Vec::new());
+                }
+
+                vec![]
+            }
+            UiAction::ReplayMacro => {
+                self.queue_macro_replay(MacroReplay::Times(1));
+                vec![]
+            }
+            UiAction::ReplayMacroOverSelection => {
+                self.queue_macro_replay(MacroReplay::OverSelectedRows);
+                vec![]
+            }
+            UiAction::OpenSearch => {
+                self.cci_want_move_scroll = false;
+
+                self.cc_minimap = None;
+                if self.search.take().is_none() {
+                    self.search = Some(SearchState {
+                        focus_query: true,
+                        stale: true,
+                        ..default()
+                    });
+                }
+
+                vec![]
+            }
+            UiAction::HighlightDuplicates(column) => {
+                self.cci_want_move_scroll = false;
+
+                let column = ColumnIdx(column);
+                if self.duplicates.take().is_none_or(|x| x.column != column) {
+                    self.duplicates = Some(DuplicateState {
+                        column,
+                        rows: HashSet::new(),
+                        stale: true,
+                    });
+                }
+
+                vec![]
+            }
+            UiAction::SelectDuplicates => {
+                self.validate_duplicates(table, vwr);
+
+                let Some(duplicates) = self.duplicates.as_ref() else {
+                    return vec![];
+                };
+
+                let ncol = self.p.vis_cols.len();
+                let selections: Vec<_> = (self.cc_rows.iter().enumerate())
+                    .filter(|(_, row_id)| duplicates.rows.contains(row_id))
+                    .map(|(vis_r, _)| {
+                        let vis_r = VisRowPos(vis_r);
+                        VisSelection(
+                            vis_r.linear_index(ncol, VisColumnPos(0)),
+                            vis_r.linear_index(ncol, VisColumnPos(ncol - 1)),
+                        )
+                    })
+                    .collect();
+
+                if selections.is_empty() {
+                    return vec![];
+                }
+
+                vec![Command::CcSetSelection(selections)]
+            }
+            UiAction::ToggleShowSelectedOnly => {
+                let rows: BTreeSet<_> = (self.collect_selected_rows().into_iter())
+                    .map(|x| self.cc_rows[x.0])
+                    .collect();
+
+                if self.cc_shown_rows.take().is_none() {
+                    if rows.is_empty() {
+                        return vec![];
+                    }
+
+                    self.cc_shown_rows = Some(rows.clone());
+                }
+
+                // Keep the selected rows over the change of the view.
+                self.cc_dirty = true;
+                self.queue_select_rows(rows);
+                vec![]
+            }
+            UiAction::CompareRows => {
+                self.cci_want_move_scroll = false;
+
+                if let Some((a, b)) = self.collect_selected_rows().into_iter().collect_tuple() {
+                    self.cc_compared_rows = Some([self.cc_rows[a.0], self.cc_rows[b.0]]);
+                }
+
+                vec![]
+            }
+            UiAction::SelectionStartEditing => {
+                let row_id = self.cc_rows[ic_r.0];
+                let row = vwr.clone_row(&table.rows[row_id.0]);
+                vec![Command::CcEditStart(row_id, ic_c, Box::new(row))]
+            }
+            UiAction::CancelEdition => vec![Command::CcCancelEdit],
+            UiAction::CommitEdition => vec![Command::CcCommitEdit],
+            UiAction::CommitEditionAndMove(dir) => {
+                let pos = self.moved_position(self.cc_interactive_cell, dir);
+                let (r, c) = pos.row_col(self.p.vis_cols.len());
+                let row_id = self.cc_rows[r.0];
+                let row_value = if self.is_editing() && ic_r == r {
+                    vwr.clone_row(self.unwrap_editing_row_data())
+                } else {
+                    vwr.clone_row(&table.rows[row_id.0])
+                };
+
+                vec![
+                    Command::CcCommitEdit,
+                    Command::CcEditStart(row_id, c, row_value.into()),
+                ]
+            }
+            UiAction::MoveSelection(dir) => {
+                let pos = self.moved_position(self.cc_interactive_cell, dir);
+                vec![Command::CcSetSelection(vec![VisSelection(pos, pos)])]
+            }
+            UiAction::ExtendSelection(dir) => {
+                // Unlike moving, the extent doesn't wrap around the row boundaries.
+                let (VisRowPos(r), VisColumnPos(c)) = self.selection_extent();
+                let (rmax, cmax) = (
+                    self.cc_rows.len().saturating_sub(1),
+                    self.p.vis_cols.len().saturating_sub(1),
+                );
+
+                let (r, c) = match dir {
+                    MoveDirection::Up => (r.saturating_sub(1), c),
+                    MoveDirection::Down => ((r + 1).min(rmax), c),
+                    MoveDirection::Left => (r, c.saturating_sub(1)),
+                    MoveDirection::Right => (r, (c + 1).min(cmax)),
+                };
+
+                vec![Command::CcExtendSelection(
+                    VisRowPos(r.min(rmax)).linear_index(self.p.vis_cols.len(), VisColumnPos(c)),
+                )]
+            }
+            UiAction::Undo => self.undo(table, vwr).pipe(empty),
+            UiAction::Redo => self.redo(table, vwr).pipe(empty),
+            #[cfg(feature = "xlsx")]
+            UiAction::ExportSelection => {
+                let sels = self.collect_selection();
+                let rows = sels.iter().map(|x| x.0).collect::<BTreeSet<_>>();
+                let columns = sels.iter().map(|x| x.1).collect::<BTreeSet<_>>();
+
+                let rows = rows
+                    .into_iter()
+                    .map(|r| &table.rows[self.cc_rows[r.0].0])
+                    .collect::<Vec<_>>();
+                let columns = columns
+                    .into_iter()
+                    .map(|c| self.p.vis_cols[c.0])
+                    .collect::<Vec<_>>();
+
+                let data = super::export::xlsx_of(vwr, &rows, &columns);
+                vwr.on_xlsx_export(data);
+                vec![]
+            }
+            UiAction::CopySelection | UiAction::CutSelection => {
+                let sels = self.collect_selection();
+                self.clipboard = None;
+                self.cc_cut = None;
+
+                if sels.is_empty() {
+                    return vec![]; // we do nothing.
+                }
+
+                // Copy contents to clipboard
+                let offset = sels.first().unwrap().0;
+                let mut slab = Vec::with_capacity(10);
+                let mut vis_map = HashMap::with_capacity(10);
+
+                for vis_row in self.collect_selected_rows() {
+                    vis_map.insert(vis_row, slab.len());
+                    slab.push(vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[vis_row.0].0]));
+                }
+
+                let clipboard = Clipboard {
+                    slab: slab.into_boxed_slice(),
+                    pastes: sels
+                        .iter()
+                        .map(|(v_r, v_c)| {
+                            (
+                                VisRowOffset(v_r.0 - offset.0),
+                                self.p.vis_cols[v_c.0],
+                                RowSlabIndex(vis_map[v_r]),
+                            )
+                        })
+                        .collect(),
+                    skipped_rows: 0,
+                }
+                .tap_mut(Clipboard::sort);
+
+                let sys_clip = Self::try_dump_clipboard_content(&clipboard, vwr);
+                self.clipboard = Some(clipboard);
+
+                if action == UiAction::CutSelection && self.deferred_cut {
+                    self.cc_cut = Some(PendingCut {
+                        cells: (sels.iter())
+                            .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0]))
+                            .collect(),
+                        text: sys_clip.clone(),
+                    });
+
+                    vec![]
+                } else if action == UiAction::CutSelection {
+                    self.try_apply_ui_action(table, vwr, UiAction::DeleteSelection)
+                } else {
+                    vec![]
+                }
+                .tap_mut(|v| {
+                    // We only overwrite system clipboard when there's any text to dump.
+                    if let Some(clip) = sys_clip {
+                        v.push(Command::CcUpdateSystemClipboard(clip));
+                    }
+                })
+            }
+            UiAction::SelectionDuplicateValues => {
+                let pivot_row = vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[ic_r.0].0]);
+                let sels = self.collect_selection();
+
+                vec![Command::CcSetCells {
+                    slab: [pivot_row].into(),
+                    values: sels
+                        .into_iter()
+                        .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0], RowSlabIndex(0)))
+                        .collect(),
+                    context: CellWriteContext::Paste,
+                }]
+            }
+            UiAction::FillSeries => {
+                let computed = Self::computed_columns(vwr, self.p.num_columns);
+                let mut columns = BTreeMap::<_, Vec<_>>::new();
+
+                for (r, c) in self.collect_selection() {
+                    columns.entry(self.p.vis_cols[c.0]).or_default().push(r);
+                }
+
+                // Each column continues its first cells, as encoded by the codec.
+                let mut cells = Vec::new();
+                if let Some(mut codec) = vwr.try_create_codec(true) {
+                    for (col, rows) in columns.iter().filter(|(c, _)| !computed[c.0]) {
+                        if !codec.supports_column(col.0) {
+                            continue;
+                        }
+
+                        let texts = Vec::from_iter(rows.iter().map(|r| {
+                            let mut data = String::new();
+                            let row = &table.rows[self.cc_rows[r.0].0];
+                            codec.encode_column(row, col.0, &mut data);
+                            data
+                        }));
+
+                        let Some((n_leading, values)) = series::fill(&texts) else {
+                            continue;
+                        };
+
+                        let rows = rows[n_leading..].iter().map(|r| self.cc_rows[r.0]);
+                        cells.extend(rows.zip(values).map(|(r, data)| (r, *col, data)));
+                    }
+                }
+
+                let Some(mut codec) = vwr.try_create_codec(false) else {
+                    return vec![];
+                };
+
+                let mut slab = Vec::new();
+                let mut values = Vec::new();
+
+                for (row_id, col, data) in cells {
+                    let mut row = codec.create_empty_decoded_row();
+
+                    if codec.decode_column(&data, col.0, &mut row).is_ok() {
+                        values.push((row_id, col, RowSlabIndex(slab.len())));
+                        slab.push(row);
+                    }
+                }
+
+                if values.is_empty() {
+                    return vec![];
+                }
+
+                vec![Command::CcSetCells {
+                    slab: slab.into(),
+                    values: values.into(),
+                    context: CellWriteContext::Paste,
+                }]
+            }
+            UiAction::PasteInPlace => {
+                let Some(clip) = &self.clipboard else {
+                    return vec![];
+                };
+
+                let (slab, values) = match self.shifted_column_pastes(table, vwr, clip, ic_c) {
+                    Some(shifted) => shifted,
+                    None => (
+                        clip.slab.iter().map(|x| vwr.clone_row(x)).collect(),
+                        Vec::from_iter(clip.pastes.iter().filter_map(|(offset, col, slab_id)| {
+                            let vis_r = VisRowPos(ic_r.0 + offset.0);
+                            (vis_r.0 < self.cc_rows.len())
+                                .then(|| (self.cc_rows[vis_r.0], *col, *slab_id))
+                        })),
+                    ),
+                };
+
+                let mut desired = Vec::new();
+
+                for (row, group) in &values.iter().chunk_by(|(row, ..)| *row) {
+                    desired.push((row, group.map(|(_, c, ..)| *c).collect()))
+                }
+
+                let summary = PasteSummary {
+                    cells: values.len(),
+                    rows: desired.len(),
+                    skipped_rows: clip.skipped_rows,
+                    insert: false,
+                };
+
+                let commands =
+                    Vec::from_iter(self.take_cut(vwr).into_iter().chain([Command::CcSetCells {
+                        slab: slab.into_boxed_slice(),
+                        values: values.into_boxed_slice(),
+                        context: CellWriteContext::Paste,
+                    }]));
+
+                self.gate_paste(vwr, summary, commands, Some(desired))
+            }
+            UiAction::PasteInsert => {
+                let Some(clip) = &self.clipboard else {
+                    return vec![];
+                };
+
+                let mut last = usize::MAX;
+                let mut rows = clip
+                    .pastes
+                    .iter()
+                    .filter(|&(offset, ..)| replace(&mut last, offset.0) != offset.0)
+                    .map(|(offset, ..)| {
+                        (
+                            *offset,
+                            vwr.new_empty_row_for(EmptyRowCreateContext::InsertNewLine),
+                        )
+                    })
+                    .collect::<BTreeMap<_, _>>();
+
+                let mut cells = 0;
+
+                for (offset, column, slab_id) in &*clip.pastes {
+                    if vwr.is_computed_column(column.0) {
+                        continue;
+                    }
+
+                    cells += 1;
+
+                    vwr.set_cell_value(
+                        &clip.slab[slab_id.0],
+                        rows.get_mut(offset).unwrap(),
+                        column.0,
+                    );
+                }
+
+                // Columns missing from the clipboard take their default values.
+                for (offset, pastes) in &clip.pastes.iter().chunk_by(|(offset, ..)| *offset) {
+                    let given = pastes.map(|(_, column, _)| *column).collect::<Vec<_>>();
+                    Self::fill_default_cells(
+                        vwr,
+                        rows.get_mut(&offset).unwrap(),
+                        self.p.num_columns,
+                        &given,
+                        EmptyRowCreateContext::InsertNewLine,
+                    );
+                }
+
+                let pos = self.insertion_pos(table, ic_r);
+
+                let summary = PasteSummary {
+                    cells,
+                    rows: rows.len(),
+                    skipped_rows: clip.skipped_rows,
+                    insert: true,
+                };
+
+                let row_values = rows.into_values().collect();
+                // Source cells are cleared before the insertion shifts them.
+                let commands = Vec::from_iter(
+                    (self.take_cut(vwr).into_iter()).chain([Command::InsertRows(pos, row_values)]),
+                );
+
+                self.gate_paste(vwr, summary, commands, None)
+            }
+            UiAction::DuplicateRow => {
+                let rows = self
+                    .collect_selected_rows()
+                    .into_iter()
+                    .map(|x| self.cc_rows[x.0])
+                    .map(|r| vwr.clone_row_for_insertion(&table.rows[r.0]))
+                    .collect();
+
+                let pos = self.insertion_pos(table, ic_r);
+
+                vec![Command::InsertRows(pos, rows)]
+            }
+            UiAction::InsertRowBelow => {
+                let Some(&source) = self.cc_rows.get(ic_r.0) else {
+                    return vec![];
+                };
+
+                let row = vwr.new_empty_row_for(EmptyRowCreateContext::InsertBelow {
+                    source_row: &table.rows[source.0],
+                });
+
+                let pos = if self.p.sort.is_empty() {
+                    RowIdx(source.0 + 1)
+                } else {
+                    RowIdx(table.rows.len())
+                };
+
+                vec![Command::InsertRows(pos, [row].into())]
+            }
+            UiAction::DeleteSelection => {
+                let default = vwr.new_empty_row_for(EmptyRowCreateContext::DeletionDefault);
+                let sels = self.collect_selection();
+                let slab = vec![default].into_boxed_slice();
+
+                vec![Command::CcSetCells {
+                    slab,
+                    values: sels
+                        .into_iter()
+                        .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0], RowSlabIndex(0)))
+                        .collect(),
+                    context: CellWriteContext::Clear,
+                }]
+            }
+            UiAction::DeleteRow => {
+                let mut rows: Vec<_> = self
+                    .collect_selected_rows()
+                    .into_iter()
+                    .map(|x| self.cc_rows[x.0])
+                    .filter(|row| vwr.confirm_row_deletion_by_ui(&table.rows[row.0]))
+                    .collect();
+
+                // Visual order differs from the data order while sorted.
+                rows.sort_unstable();
+                vec![self.remove_rows(rows)]
+            }
+            UiAction::CancelCut => {
+                self.cc_cut = None;
+                vec![]
+            }
+            UiAction::RestoreTrash => {
+                let rows = table.trash.iter().copied().map(RowIdx).collect();
+                vec![Command::RestoreRows(rows)]
+            }
+            action @ (UiAction::MoveRowsUp | UiAction::MoveRowsDown) => {
+                // Visual order differs from the data order while sorted, or while rows are
+                // pinned or nested; nothing to do.
+                if !self.p.sort.is_empty()
+                    || self.cc_num_pinned_rows > 0
+                    || !self.cc_tree.is_empty()
+                {
+                    return vec![];
+                }
+
+                let vis_rows = self.collect_selected_rows();
+                let (Some(top), Some(bottom)) = (vis_rows.first(), vis_rows.last()) else {
+                    return vec![];
+                };
+
+                // Only a block of rows contiguous in data order moves as is; otherwise the
+                // unselected or hidden rows in between would move along.
+                let rows = vis_rows
+                    .iter()
+                    .map(|x| self.cc_rows[x.0])
+                    .collect::<Vec<_>>();
+                let Some(range) = Self::contiguous_range(&rows) else {
+                    return vec![];
+                };
+
+                // Jump over the filtered rows, by moving next to the adjacent visible row.
+                let (from, len) = (range.start, range.end.0 - range.start.0);
+                let to = if action == UiAction::MoveRowsUp {
+                    let Some(prev) = top.0.checked_sub(1) else {
+                        return vec![];
+                    };
+
+                    self.cc_rows[prev]
+                } else {
+                    let Some(&next) = self.cc_rows.get(bottom.0 + 1) else {
+                        return vec![];
+                    };
+
+                    RowIdx(next.0 + 1 - len)
+                };
+
+                vec![Command::MoveRows { from, len, to }]
+            }
+            action @ (UiAction::MoveColumnLeft | UiAction::MoveColumnRight) => {
+                let ncol = self.p.vis_cols.len();
+                let (to, new_c) = if action == UiAction::MoveColumnLeft {
+                    let Some(prev) = ic_c.0.checked_sub(1) else {
+                        return vec![];
+                    };
+
+                    (prev, prev)
+                } else {
+                    if ic_c.0 + 1 >= ncol {
+                        return vec![];
+                    }
+
+                    // Reorder inserts before the target position.
+                    (ic_c.0 + 2, ic_c.0 + 1)
+                };
+
+                let idx = ic_r.linear_index(ncol, VisColumnPos(new_c));
+                vec![
+                    Command::CcReorderColumn {
+                        from: ic_c,
+                        to: VisColumnPos(to),
+                    },
+                    Command::CcSetSelection(vec![VisSelection(idx, idx)]),
+                ]
+            }
+            UiAction::HideCurrentColumn => {
+                vec![Command::CcHideColumn(self.p.vis_cols[ic_c.0])]
+            }
+            UiAction::SelectAllIncludingFiltered => {
+                let mut commands = if self.cc_rows.is_empty() {
+                    vec![]
+                } else {
+                    self.try_apply_ui_action(table, vwr, UiAction::SelectAll)
+                };
+
+                commands.push(Command::CcSelectAllData);
+                commands
+            }
+            UiAction::SelectAll => {
+                if self.cc_rows.is_empty() {
+                    return vec![];
+                }
+
+                vec![Command::CcSetSelection(vec![VisSelection(
+                    VisLinearIdx(0),
+                    VisRowPos(self.cc_rows.len().saturating_sub(1)).linear_index(
+                        self.p.vis_cols.len(),
+                        VisColumnPos(self.p.vis_cols.len() - 1),
+                    ),
+                )])]
             }
-            Command::SetVisibleColumns(ref value) => {
-                if self.p.vis_cols.iter().eq(value.iter()) {
-                    return;
-                }
 
-                vec![Command::SetVisibleColumns(self.p.vis_cols.clone())]
-            }
-            Command::CcReorderColumn { from, to } => {
-                if from == to || to.0 > self.p.vis_cols.len() {
-                    // Reorder may deliver invalid parameter if there's multiple data
-                    // tables present at the same time; as the drag drop payload are
-                    // compatible between different tables...
-                    return;
-                }
+            action @ (UiAction::NextMarkedRow(kind) | UiAction::PrevMarkedRow(kind)) => {
+                let n_rows = self.cc_rows.len();
+                let is_next = matches!(action, UiAction::NextMarkedRow(..));
 
-                let mut vis_cols = self.p.vis_cols.clone();
-                if from.0 < to.0 {
-                    vis_cols.insert(to.0, vis_cols[from.0]);
-                    vis_cols.remove(from.0);
-                } else {
-                    vis_cols.remove(from.0).pipe(|x| vis_cols.insert(to.0, x));
+                if kind == MarkKind::Match && self.next_search_match(is_next).is_some() {
+                    return self.search_move_commands(is_next);
                 }
 
-                self.push_new_command(table, vwr, Command::SetVisibleColumns(vis_cols), capacity);
-                return;
+                // Search from the row next to interactive cell, wrapping around.
+                let found = (1..=n_rows)
+                    .map(|ofst| match is_next {
+                        true => (ic_r.0 + ofst) % n_rows,
+                        false => (ic_r.0 + n_rows - ofst % n_rows) % n_rows,
+                    })
+                    .find(|&vis_r| {
+                        let row_id = self.cc_rows[vis_r];
+                        match kind {
+                            MarkKind::Modified => self.is_modified_row(row_id),
+                            MarkKind::Invalid if self.is_invalid_row(row_id) => true,
+                            MarkKind::Duplicate => self
+                                .duplicates
+                                .as_ref()
+                                .is_some_and(|x| x.rows.contains(&row_id)),
+                            kind => vwr.is_row_marked(&table.rows[row_id.0], kind),
+                        }
+                    });
+
+                let Some(vis_r) = found else {
+                    return vec![];
+                };
+
+                let pos = VisRowPos(vis_r).linear_index(self.p.vis_cols.len(), ic_c);
+                vec![Command::CcSetSelection(vec![VisSelection(pos, pos)])]
             }
-            Command::CcEditStart(row_id, column_pos, current) => {
-                // EditStart command is directly applied.
-                self.cc_cursor = CursorState::Edit {
-                    edition: *current,
-                    next_focus: true,
-                    last_focus: column_pos,
-                    row: row_id,
+
+            action @ (UiAction::NavPageDown
+            | UiAction::NavPageUp
+            | UiAction::NavTop
+            | UiAction::NavBottom) => {
+                let ofst = match action {
+                    UiAction::NavPageDown => self.cci_page_row_count as isize,
+                    UiAction::NavPageUp => -(self.cci_page_row_count as isize),
+                    UiAction::NavTop => isize::MIN,
+                    UiAction::NavBottom => isize::MAX,
+                    _ => unreachable!(),
                 };
 
-                // Update interactive cell.
+                let new_ic_r = (ic_r.0 as isize)
+                    .saturating_add(ofst)
+                    .clamp(0, self.cc_rows.len().saturating_sub(1) as _);
                 self.cc_interactive_cell =
-                    self.cc_row_id_to_vis[&row_id].linear_index(self.p.vis_cols.len(), column_pos);
+                    VisLinearIdx(new_ic_r as usize * self.p.vis_cols.len() + ic_c.0);
 
-                // No redo argument is generated.
-                return;
+                self.validate_interactive_cell(self.p.vis_cols.len());
+                vec![Command::CcSetSelection(vec![VisSelection(
+                    self.cc_interactive_cell,
+                    self.cc_interactive_cell,
+                )])]
             }
-            ref cmd @ (Command::CcCancelEdit | Command::CcCommitEdit) => {
-                // This edition state become selection. Restorat
-                let Some((row_id, edition, _)) = self.try_take_edition() else {
-                    return;
-                };
+        }
+    }
 
-                if matches!(cmd, Command::CcCancelEdit) {
-                    // Cancellation does not affect to any state.
-                    return;
-                }
+    /// Recompute matching cells of the search bar, if they're stale.
+    pub fn validate_search<V: RowViewer<R> + ?Sized>(&mut self, table: &DataTable<R>, vwr: &mut V) {
+        let Some(search) = self.search.as_mut().filter(|x| x.stale) else {
+            return;
+        };
 
-                // Change command type of self.
-                self.push_new_command(
-                    table,
-                    vwr,
-                    Command::SetRowValue(row_id, edition.into()),
-                    capacity,
-                );
+        search.stale = false;
+        search.matches.clear();
+        self.cc_minimap = None;
 
-                return;
-            }
+        if search.query.is_empty() {
+            return;
+        }
 
-            Command::SetRowValue(row_id, _) => {
-                vec![Command::SetRowValue(
-                    row_id,
-                    vwr.clone_row(&table.rows[row_id.0]).into(),
-                )]
+        let ncol = self.p.vis_cols.len();
+        let mut matcher = vwr.cell_matcher(&search.query);
+        for (vis_row, row_id) in self.cc_rows.iter().enumerate() {
+            for (vis_col, col) in self.p.vis_cols.iter().enumerate() {
+                if matcher(&table.rows[row_id.0], col.0) {
+                    let idx = VisRowPos(vis_row).linear_index(ncol, VisColumnPos(vis_col));
+                    search.matches.push(idx);
+                }
             }
+        }
+    }
 
-            Command::CcSetCells {
-                context,
-                slab,
-                values,
-            } => {
-                let mut values = values.to_vec();
+    /// Match the search against given rows again, e.g. after their cells are edited in place;
+    /// the matches of the other rows are kept.
+    fn rematch_search_rows<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+        rows: impl IntoIterator<Item = RowIdx>,
+    ) {
+        let Some(search) = self.search.as_mut().filter(|x| !x.stale) else {
+            return;
+        };
 
-                values.retain(|(row, col, slab_id)| {
-                    vwr.confirm_cell_write_by_ui(
-                        &table.rows[row.0],
-                        &slab[slab_id.0],
-                        col.0,
-                        context,
-                    )
-                });
+        if search.query.is_empty() {
+            return;
+        }
 
-                return self.push_new_command(
-                    table,
-                    vwr,
-                    Command::SetCells {
-                        slab,
-                        values: values.into_boxed_slice(),
-                    },
-                    capacity,
-                );
-            }
+        let ncol = self.p.vis_cols.len();
+        let mut matcher = vwr.cell_matcher(&search.query);
+        for row_id in rows {
+            let Some(&vis_row) = self.cc_row_id_to_vis.get(&row_id) else {
+                continue;
+            };
 
-            Command::SetCells { ref values, .. } => {
-                let mut keys = Vec::from_iter(values.iter().map(|(r, ..)| *r));
-                keys.dedup();
+            let start = vis_row.linear_index(ncol, VisColumnPos(0));
+            let lo = search.matches.partition_point(|x| *x < start);
+            let hi = search.matches.partition_point(|x| x.0 < start.0 + ncol);
+            let row = &table.rows[row_id.0];
+            let found: Vec<_> = self
+                .p
+                .vis_cols
+                .iter()
+                .enumerate()
+                .filter(|(_, col)| matcher(row, col.0))
+                .map(|(vis_col, _)| vis_row.linear_index(ncol, VisColumnPos(vis_col)))
+                .collect();
 
-                keys.iter()
-                    .map(|row_id| {
-                        Command::SetRowValue(*row_id, vwr.clone_row(&table.rows[row_id.0]).into())
-                    })
-                    .collect()
-            }
+            search.matches.splice(lo..hi, found);
+        }
 
-            Command::SetColumnSort(ref sort) => {
-                if self.p.sort.iter().eq(sort.iter()) {
-                    return;
-                }
+        self.cc_minimap = None;
+    }
 
-                vec![Command::SetColumnSort(self.p.sort.clone())]
-            }
-            Command::CcSetSelection(sel) => {
-                if !sel.is_empty() {
-                    self.cc_interactive_cell = sel[0].0;
-                }
+    /// Minimap markers of the visible rows, bucketed into `n_buckets` in visual order. Rebuilt
+    /// only when the rows, the selection or the search has changed.
+    pub fn minimap_markers<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+        n_buckets: usize,
+    ) -> &[MinimapBucket] {
+        let selection = match &self.cc_cursor {
+            CursorState::Select(sel) => sel.as_slice(),
+            CursorState::Edit { .. } => &[],
+        };
 
-                let (highlighted, unhighlighted) = self.get_highlight_changes(table, &sel);
-                vwr.on_highlight_change(&highlighted, &unhighlighted);
-                self.cc_cursor = CursorState::Select(sel);
-                return;
+        let is_valid = self
+            .cc_minimap
+            .as_ref()
+            .is_some_and(|x| x.buckets.len() == n_buckets && x.selection == selection);
+
+        if !is_valid {
+            let selection = selection.to_vec();
+            let selected_rows = self.collect_selected_rows();
+            let n_rows = self.cc_rows.len().max(1);
+            let bucket_of = |vis_row: usize| (vis_row * n_buckets / n_rows).min(n_buckets - 1);
+            let mut buckets = vec![MinimapBucket::default(); n_buckets];
+
+            for (vis_row, row_id) in self.cc_rows.iter().enumerate() {
+                let bucket = &mut buckets[bucket_of(vis_row)];
+                bucket.modified |= self.cc_modified_rows.contains(row_id);
+                bucket.selected |= selected_rows.contains(&VisRowPos(vis_row));
+
+                if let Some(color) = vwr.minimap_row_marker(&table.rows[row_id.0]) {
+                    bucket.marker = Some(color);
+                }
             }
-            Command::InsertRows(pivot, ref values) => {
-                let values = (pivot.0..pivot.0 + values.len()).map(RowIdx).collect();
-                vec![Command::RemoveRow(values)]
+
+            let ncol = self.p.vis_cols.len().max(1);
+            for idx in self.search.iter().flat_map(|x| &x.matches) {
+                buckets[bucket_of(idx.0 / ncol)].search_match = true;
             }
-            Command::RemoveRow(ref indices) => {
-                if indices.is_empty() {
-                    // From various sources, it can be just 'empty' removal command
-                    return;
-                }
 
-                // Ensure indices are sorted.
-                debug_assert!(indices.windows(2).all(|x| x[0] < x[1]));
+            self.cc_minimap = Some(MinimapMarkers { buckets, selection });
+        }
 
-                // Collect contiguous chunks.
-                let mut chunks = vec![vec![indices[0]]];
+        &self.cc_minimap.as_ref().unwrap().buckets
+    }
 
-                for index in indices.windows(2) {
-                    if index[0].0 + 1 == index[1].0 {
-                        chunks.last_mut().unwrap().push(index[1]);
-                    } else {
-                        chunks.push(vec![index[1]]);
-                    }
-                }
+    pub fn heat_column(&self) -> Option<ColumnIdx> {
+        self.heat.as_ref().map(|x| x.column)
+    }
 
-                chunks
-                    .into_iter()
-                    .map(|x| {
-                        Command::InsertRows(
-                            x[0],
-                            x.into_iter()
-                                .map(|x| vwr.clone_row(&table.rows[x.0]))
-                                .collect(),
-                        )
-                    })
-                    .collect()
-            }
-            Command::CcUpdateSystemClipboard(..) => {
-                // This command MUST've be consumed before calling this.
-                unreachable!()
-            }
+    /// Tint the rows by the rank of given column's value; `None` disables the heat coloring.
+    pub fn set_heat_column(&mut self, column: Option<ColumnIdx>) {
+        if self.heat_column() != column {
+            self.heat = column.map(|column| HeatState {
+                column,
+                ranks: HashMap::new(),
+                stale: true,
+            });
+        }
+    }
+
+    /// Recompute ranks of the heat coloring, if they're stale.
+    pub fn validate_heat<V: RowViewer<R> + ?Sized>(&mut self, table: &DataTable<R>, vwr: &mut V) {
+        if self
+            .heat
+            .as_ref()
+            .is_some_and(|x| x.column.0 >= self.p.num_columns)
+        {
+            self.heat = None;
+        }
+
+        let Some(heat) = self.heat.as_mut().filter(|x| x.stale) else {
+            return;
         };
 
-        // Discard all redos after this point.
-        self.undo_queue.drain(0..self.undo_cursor);
+        heat.stale = false;
+        heat.ranks.clear();
 
-        // Discard all undos that exceed the capacity.
-        let new_len = capacity.saturating_sub(1).min(self.undo_queue.len());
-        self.undo_queue.drain(new_len..);
+        let Some(model) = vwr.model() else {
+            return;
+        };
 
-        // Now it's the foremost element of undo queue.
-        self.undo_cursor = 0;
+        let rows = &table.rows;
+        let column = heat.column.0;
+        let compare = |a: &RowIdx, b: &RowIdx| model.compare_cell(&rows[a.0], &rows[b.0], column);
 
-        // Apply the command.
-        self.cmd_apply(table, vwr, &command);
+        let mut order = self.cc_rows.clone();
+        order.sort_by(compare);
 
-        // Push the command to the queue.
-        self.undo_queue.push_front(UndoArg {
-            apply: command,
-            restore,
-        });
+        let max_rank = order.len().saturating_sub(1).max(1) as f32;
+        let mut rank = 0;
+
+        for (pos, row) in order.iter().enumerate() {
+            if pos > 0 && compare(&order[pos - 1], row).is_ne() {
+                rank = pos;
+            }
+
+            heat.ranks.insert(*row, rank as f32 / max_rank);
+        }
+    }
+
+    /// Normalized rank of the row for the heat coloring, if it's enabled.
+    pub fn heat_rank(&self, row: RowIdx) -> Option<f32> {
+        self.heat.as_ref()?.ranks.get(&row).copied()
     }
 
-    fn cmd_apply<V: RowViewer<R>>(
+    /// Recompute rows with duplicate values, if they're stale.
+    pub fn validate_duplicates<V: RowViewer<R> + ?Sized>(
         &mut self,
-        table: &mut DataTable<R>,
+        table: &DataTable<R>,
         vwr: &mut V,
-        cmd: &Command<R>,
     ) {
-        match cmd {
-            Command::SetVisibleColumns(cols) => {
-                self.validate_interactive_cell(cols.len());
-                self.p.vis_cols.clear();
-                self.p.vis_cols.extend(cols.iter().cloned());
-                self.cc_dirty = true;
-            }
-            Command::SetColumnSort(new_sort) => {
-                self.p.sort.clear();
-                self.p.sort.extend(new_sort.iter().cloned());
-                self.cc_dirty = true;
-            }
-            Command::SetRowValue(row_id, value) => {
-                self.cc_num_frame_from_last_edit = 0;
-                table.dirty_flag = true;
-                table.rows[row_id.0] = vwr.clone_row(value);
-            }
-            Command::SetCells { slab, values } => {
-                self.cc_num_frame_from_last_edit = 0;
-                table.dirty_flag = true;
+        if self
+            .duplicates
+            .as_ref()
+            .is_some_and(|x| x.column.0 >= self.p.num_columns)
+        {
+            self.duplicates = None;
+        }
 
-                for (row, col, value_id) in values.iter() {
-                    vwr.set_cell_value(&slab[value_id.0], &mut table.rows[row.0], col.0);
-                }
-            }
-            Command::InsertRows(pos, values) => {
-                self.cc_dirty = true; // It invalidates all current `RowId` occurences.
-                table.dirty_flag = true;
+        let Some(duplicates) = self.duplicates.as_mut().filter(|x| x.stale) else {
+            return;
+        };
 
-                table
-                    .rows
-                    .splice(pos.0..pos.0, values.iter().map(|x| vwr.clone_row(x)));
+        duplicates.stale = false;
+        duplicates.rows.clear();
 
-                self.queue_select_rows((pos.0..pos.0 + values.len()).map(RowIdx));
-            }
-            Command::RemoveRow(values) => {
-                debug_assert!(values.windows(2).all(|x| x[0] < x[1]));
-                self.cc_dirty = true; // It invalidates all current `RowId` occurences.
-                table.dirty_flag = true;
+        let mut encode = text_encoder(vwr);
+        let mut first_of = HashMap::<String, RowIdx>::new();
+        let mut text = String::new();
 
-                let mut index = 0;
-                table.rows.retain(|_| {
-                    let idx_now = index.tap(|_| index += 1);
-                    values.binary_search(&RowIdx(idx_now)).is_err()
-                });
+        for row_id in &self.cc_rows {
+            text.clear();
 
-                self.queue_select_rows([]);
+            if !encode(&table.rows[row_id.0], duplicates.column.0, &mut text) {
+                continue;
             }
-            Command::CcHideColumn(..)
-            | Command::CcShowColumn { .. }
-            | Command::CcReorderColumn { .. }
-            | Command::CcEditStart(..)
-            | Command::CcCommitEdit
-            | Command::CcCancelEdit
-            | Command::CcSetSelection(..)
-            | Command::CcSetCells { .. }
-            | Command::CcUpdateSystemClipboard(..) => unreachable!(),
+
+            if let Some(first) = first_of.get(&text) {
+                duplicates.rows.insert(*first);
+                duplicates.rows.insert(*row_id);
+            } else {
+                first_of.insert(text.clone(), *row_id);
+            }
+        }
+    }
+
+    /// Open the group-by summary preview, grouped by given column.
+    pub fn open_pivot(&mut self, group: ColumnIdx) {
+        self.set_pivot_columns(group, self.pivot.as_ref().and_then(|x| x.value));
+    }
+
+    pub fn close_pivot(&mut self) {
+        self.pivot = None;
+    }
+
+    pub fn pivot_columns(&self) -> Option<(ColumnIdx, Option<ColumnIdx>)> {
+        self.pivot.as_ref().map(|x| (x.group, x.value))
+    }
+
+    pub fn set_pivot_columns(&mut self, group: ColumnIdx, value: Option<ColumnIdx>) {
+        if self.pivot_columns() != Some((group, value)) {
+            self.pivot = Some(PivotState {
+                group,
+                value,
+                rows: Vec::new(),
+                stale: true,
+            });
         }
     }
 
-    fn queue_select_rows(&mut self, rows: impl IntoIterator<Item = RowIdx>) {
-        self.cc_desired_selection = Some(rows.into_iter().map(|r| (r, default())).collect());
+    pub fn pivot_rows(&self) -> &[PivotRow] {
+        self.pivot.as_ref().map_or(&[], |x| &x.rows)
     }
 
-    fn validate_interactive_cell(&mut self, new_num_column: usize) {
-        let (r, c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
-        let rmax = self.cc_rows.len().saturating_sub(1);
-        let clen = self.p.vis_cols.len();
+    /// Recompute the group-by summary over the visible rows, if it's stale.
+    pub fn validate_pivot<V: RowViewer<R> + ?Sized>(&mut self, table: &DataTable<R>, vwr: &mut V) {
+        if self.pivot.as_ref().is_some_and(|x| {
+            x.group.0 >= self.p.num_columns || x.value.is_some_and(|c| c.0 >= self.p.num_columns)
+        }) {
+            self.pivot = None;
+        }
 
-        self.cc_interactive_cell =
-            VisLinearIdx(r.0.min(rmax) * clen + c.0.min(new_num_column.saturating_sub(1)));
+        let Some(pivot) = self.pivot.as_mut().filter(|x| x.stale) else {
+            return;
+        };
+
+        pivot.stale = false;
+
+        let mut encode = text_encoder(vwr);
+        let items = self.cc_rows.iter().map(|row_id| {
+            let row = &table.rows[row_id.0];
+            let mut group = String::new();
+            encode(row, pivot.group.0, &mut group);
+
+            let value = pivot.value.and_then(|column| {
+                let mut text = String::new();
+                encode(row, column.0, &mut text);
+                text.trim().parse::<f64>().ok()
+            });
+
+            (group, value)
+        });
+
+        pivot.rows = pivot::aggregate(items);
     }
 
-    pub fn has_clipboard_contents(&self) -> bool {
-        self.clipboard.is_some()
+    pub fn duplicates_column(&self) -> Option<ColumnIdx> {
+        self.duplicates.as_ref().map(|x| x.column)
     }
 
-    pub fn has_undo(&self) -> bool {
-        self.undo_cursor < self.undo_queue.len()
+    pub fn has_duplicates(&self) -> bool {
+        self.duplicates.as_ref().is_some_and(|x| !x.rows.is_empty())
     }
 
-    pub fn has_redo(&self) -> bool {
-        self.undo_cursor > 0
+    pub fn is_duplicate_cell(&self, row: RowIdx, column: ColumnIdx) -> bool {
+        self.duplicates
+            .as_ref()
+            .is_some_and(|x| x.column == column && x.rows.contains(&row))
     }
 
-    pub fn cursor_as_selection(&self) -> Option<&[VisSelection]> {
-        match &self.cc_cursor {
-            CursorState::Select(x) => Some(x),
-            CursorState::Edit { .. } => None,
-        }
+    pub fn is_search_match(&self, row: VisRowPos, col: VisColumnPos) -> bool {
+        self.search.as_ref().is_some_and(|search| {
+            let idx = row.linear_index(self.p.vis_cols.len(), col);
+            search.matches.binary_search(&idx).is_ok()
+        })
     }
 
-    fn try_take_edition(&mut self) -> Option<(RowIdx, R, VisColumnPos)> {
-        if matches!(self.cc_cursor, CursorState::Edit { .. }) {
-            match replace(&mut self.cc_cursor, CursorState::Select(Vec::default())) {
-                CursorState::Edit {
-                    row,
-                    edition,
-                    last_focus,
-                    ..
-                } => Some((row, edition, last_focus)),
-                _ => unreachable!(),
-            }
+    /// Ordinal of the search match at the interactive cell, if it's a match.
+    pub fn current_search_match(&self) -> Option<usize> {
+        let search = self.search.as_ref()?;
+        search.matches.binary_search(&self.cc_interactive_cell).ok()
+    }
+
+    /// Next search match from the interactive cell, wrapping around.
+    fn next_search_match(&self, forward: bool) -> Option<VisLinearIdx> {
+        let matches = &self.search.as_ref()?.matches;
+        let (Some(first), Some(last)) = (matches.first(), matches.last()) else {
+            return None;
+        };
+
+        let ic = self.cc_interactive_cell;
+        Some(if forward {
+            let pos = matches.partition_point(|x| *x <= ic);
+            matches.get(pos).unwrap_or(first)
         } else {
-            None
-        }
+            let pos = matches.partition_point(|x| *x < ic);
+            pos.checked_sub(1).map_or(last, |x| &matches[x])
+        })
+        .copied()
     }
 
-    pub fn ui_action_context(&self) -> UiActionContext {
-        UiActionContext {
-            cursor: match &self.cc_cursor {
-                CursorState::Select(x) => {
-                    if x.is_empty() {
-                        UiCursorState::Idle
-                    } else if x.len() == 1 && x[0].0 == x[0].1 {
-                        UiCursorState::SelectOne
-                    } else {
-                        UiCursorState::SelectMany
-                    }
-                }
-                CursorState::Edit { .. } => UiCursorState::Editing,
-            },
-        }
+    /// Commands that move the interactive cell onto the next search match.
+    pub fn search_move_commands(&mut self, forward: bool) -> Vec<Command<R>> {
+        let Some(pos) = self.next_search_match(forward) else {
+            return vec![];
+        };
+
+        self.cci_want_move_scroll = true;
+        vec![Command::CcSetSelection(vec![VisSelection(pos, pos)])]
     }
 
-    pub fn undo<V: RowViewer<R>>(&mut self, table: &mut DataTable<R>, vwr: &mut V) -> bool {
-        if self.undo_cursor == self.undo_queue.len() {
-            return false;
-        }
+    /// Commands that replace the search match at the interactive cell, then move onto the next
+    /// match. If `all` is set, every match is replaced at once as a single undoable command.
+    pub fn search_replace_commands<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+        all: bool,
+    ) -> Vec<Command<R>> {
+        let Some(search) = &self.search else {
+            return vec![];
+        };
 
-        let queue = take(&mut self.undo_queue);
-        {
-            let item = &queue[self.undo_cursor];
-            for cmd in item.restore.iter() {
-                self.cmd_apply(table, vwr, cmd);
+        let targets = if all {
+            search.matches.clone()
+        } else if let Some(idx) = self.current_search_match() {
+            vec![search.matches[idx]]
+        } else {
+            // Nothing to replace at the interactive cell; just move to the next match.
+            return self.search_move_commands(true);
+        };
+
+        let ncol = self.p.vis_cols.len();
+        let mut slab = Vec::new();
+        let mut values = Vec::new();
+
+        for (vis_row, cells) in &targets.iter().chunk_by(|x| x.row_col(ncol).0) {
+            let row_id = self.cc_rows[vis_row.0];
+            let slab_id = RowSlabIndex(slab.len());
+            let mut row = vwr.clone_row(&table.rows[row_id.0]);
+
+            for (_, vis_col) in cells.map(|x| x.row_col(ncol)) {
+                let col = self.p.vis_cols[vis_col.0];
+                if vwr.replace_in_cell(&mut row, col.0, &search.query, &search.replacement) {
+                    values.push((row_id, col, slab_id));
+                }
             }
-            self.undo_cursor += 1;
+
+            slab.push(row);
         }
-        self.undo_queue = queue;
 
-        true
+        let mut commands = if all {
+            vec![]
+        } else {
+            self.search_move_commands(true)
+        };
+
+        commands.insert(
+            0,
+            Command::CcSetCells {
+                slab: slab.into_boxed_slice(),
+                values: values.into_boxed_slice(),
+                context: CellWriteContext::Replace,
+            },
+        );
+
+        commands
     }
 
-    pub fn redo<V: RowViewer<R>>(&mut self, table: &mut DataTable<R>, vwr: &mut V) -> bool {
-        if self.undo_cursor == 0 {
-            return false;
+    /// If the editor is moving left/right within the same row, moves the editor onto the
+    /// column without committing the edition. Returns `None` if the action doesn't apply.
+    pub fn try_move_row_editor<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        vwr: &mut V,
+        action: UiAction,
+    ) -> Option<Vec<Command<R>>> {
+        let UiAction::CommitEditionAndMove(dir @ (MoveDirection::Left | MoveDirection::Right)) =
+            action
+        else {
+            return None;
+        };
+
+        let ncol = self.p.vis_cols.len();
+        let (ic_r, _) = self.interactive_cell();
+        let mut pos = self.moved_position(self.cc_interactive_cell, dir);
+
+        // Computed columns aren't editable; the editor skips over them.
+        for _ in 0..ncol {
+            let (r, c) = pos.row_col(ncol);
+            if r != ic_r || !vwr.is_computed_column(self.p.vis_cols[c.0].0) {
+                break;
+            }
+
+            pos = self.moved_position(pos, dir);
         }
 
-        let queue = take(&mut self.undo_queue);
-        {
-            self.undo_cursor -= 1;
-            self.cmd_apply(table, vwr, &queue[self.undo_cursor].apply);
+        let (r, c) = pos.row_col(ncol);
+        let is_computed = vwr.is_computed_column(self.p.vis_cols[c.0].0);
+
+        // Leaving the row commits the edition as usual.
+        let CursorState::Edit {
+            last_focus,
+            next_focus,
+            ..
+        } = &mut self.cc_cursor
+        else {
+            return None;
+        };
+
+        if r != ic_r || c == *last_focus || is_computed {
+            return None;
         }
-        self.undo_queue = queue;
 
-        true
-    }
+        *last_focus = c;
+        *next_focus = true;
+        self.cc_interactive_cell = pos;
+        self.cci_want_move_scroll = true;
 
-    pub fn set_interactive_cell(&mut self, row: VisRowPos, col: VisColumnPos) {
-        self.cc_interactive_cell = row.linear_index(self.p.vis_cols.len(), col);
+        Some(vec![])
     }
 
-    pub fn try_apply_ui_action(
-        &mut self,
-        table: &mut DataTable<R>,
-        vwr: &mut impl RowViewer<R>,
-        action: UiAction,
-    ) -> Vec<Command<R>> {
-        fn empty<T, R>(_: T) -> Vec<Command<R>> {
-            default()
+    /// If the interactive cell is moving toward a hidden column which is adjacent in data
+    /// order, returns commands that reveal the column and move onto it.
+    pub fn try_reveal_hidden_column(&mut self, action: UiAction) -> Option<Vec<Command<R>>> {
+        let UiAction::MoveSelection(dir @ (MoveDirection::Left | MoveDirection::Right)) = action
+        else {
+            return None;
+        };
+
+        let (ic_r, ic_c) = self.interactive_cell();
+        let col = *self.p.vis_cols.get(ic_c.0)?;
+        let target = match dir {
+            MoveDirection::Left => ColumnIdx(col.0.checked_sub(1)?),
+            _ => ColumnIdx(col.0 + 1),
+        };
+
+        if target.0 >= self.p.num_columns || self.p.vis_cols.contains(&target) {
+            return None;
         }
 
+        let at = VisColumnPos(ic_c.0 + (dir == MoveDirection::Right) as usize);
+        let pos = ic_r.linear_index(self.p.vis_cols.len() + 1, at);
         self.cci_want_move_scroll = true;
 
-        let (ic_r, ic_c) = self.cc_interactive_cell.row_col(self.p.vis_cols.len());
-        match action {
-            UiAction::SelectionStartEditing => {
-                let row_id = self.cc_rows[ic_r.0];
-                let row = vwr.clone_row(&table.rows[row_id.0]);
-                vec![Command::CcEditStart(row_id, ic_c, Box::new(row))]
-            }
-            UiAction::CancelEdition => vec![Command::CcCancelEdit],
-            UiAction::CommitEdition => vec![Command::CcCommitEdit],
-            UiAction::CommitEditionAndMove(dir) => {
-                let pos = self.moved_position(self.cc_interactive_cell, dir);
-                let (r, c) = pos.row_col(self.p.vis_cols.len());
-                let row_id = self.cc_rows[r.0];
-                let row_value = if self.is_editing() && ic_r == r {
-                    vwr.clone_row(self.unwrap_editing_row_data())
-                } else {
-                    vwr.clone_row(&table.rows[row_id.0])
-                };
+        Some(vec![
+            Command::CcShowColumn { what: target, at },
+            Command::CcSetSelection(vec![VisSelection(pos, pos)]),
+        ])
+    }
 
-                vec![
-                    Command::CcCommitEdit,
-                    Command::CcEditStart(row_id, c, row_value.into()),
-                ]
-            }
-            UiAction::MoveSelection(dir) => {
-                let pos = self.moved_position(self.cc_interactive_cell, dir);
-                vec![Command::CcSetSelection(vec![VisSelection(pos, pos)])]
+    /// Pastes of the clipboard moved onto the columns starting from `ic_c`, if it's different
+    /// from the copied columns. Values are carried across the columns through
+    /// [`RowViewer::get_cell`], [`RowViewer::convert_cell`] and [`RowViewer::set_cell`]; `None`
+    /// if the viewer doesn't support them, then the clipboard is pasted onto the copied columns.
+    #[allow(clippy::type_complexity)]
+    fn shifted_column_pastes<V: RowViewer<R> + ?Sized>(
+        &self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+        clip: &Clipboard<R>,
+        ic_c: VisColumnPos,
+    ) -> Option<(Vec<R>, Vec<(RowIdx, ColumnIdx, RowSlabIndex)>)> {
+        let vis_col_of = |col: ColumnIdx| self.p.vis_cols.iter().position(|x| *x == col);
+        let (_, first_col, first_slab) = clip.pastes.first()?;
+        let src_left = clip.pastes.iter().filter_map(|x| vis_col_of(x.1)).min()?;
+
+        if src_left == ic_c.0
+            || vwr
+                .get_cell(&clip.slab[first_slab.0], first_col.0)
+                .is_none()
+        {
+            return None;
+        }
+
+        let (ic_r, _) = self.interactive_cell();
+        let mut slab = Vec::new();
+        let mut slab_of_row = HashMap::new();
+        let mut values = Vec::new();
+
+        for (offset, col, slab_id) in clip.pastes.iter() {
+            let Some(row_id) = self.cc_rows.get(ic_r.0 + offset.0).copied() else {
+                continue;
+            };
+
+            let Some(dst) = vis_col_of(*col)
+                .map(|x| x - src_left + ic_c.0)
+                .and_then(|x| self.p.vis_cols.get(x).copied())
+            else {
+                continue;
+            };
+
+            let Some(value) = vwr
+                .get_cell(&clip.slab[slab_id.0], col.0)
+                .and_then(|x| vwr.convert_cell(col.0, dst.0, x))
+            else {
+                continue;
+            };
+
+            let slab_id = *slab_of_row.entry(row_id).or_insert_with(|| {
+                slab.push(vwr.clone_row(&table.rows[row_id.0]));
+                RowSlabIndex(slab.len() - 1)
+            });
+
+            if vwr.set_cell(&mut slab[slab_id.0], dst.0, value) {
+                values.push((row_id, dst, slab_id));
             }
-            UiAction::Undo => self.undo(table, vwr).pipe(empty),
-            UiAction::Redo => self.redo(table, vwr).pipe(empty),
-            UiAction::CopySelection | UiAction::CutSelection => {
-                let sels = self.collect_selection();
-                self.clipboard = None;
+        }
 
-                if sels.is_empty() {
-                    return vec![]; // we do nothing.
-                }
+        Some((slab, values))
+    }
 
-                // Copy contents to clipboard
-                let offset = sels.first().unwrap().0;
-                let mut slab = Vec::with_capacity(10);
-                let mut vis_map = HashMap::with_capacity(10);
+    fn collect_selection(&self) -> BTreeSet<(VisRowPos, VisColumnPos)> {
+        let mut set = BTreeSet::new();
 
-                for vis_row in self.collect_selected_rows() {
-                    vis_map.insert(vis_row, slab.len());
-                    slab.push(vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[vis_row.0].0]));
-                }
+        if let CursorState::Select(selections) = &self.cc_cursor {
+            for sel in selections.iter() {
+                let (top, left) = sel.0.row_col(self.p.vis_cols.len());
+                let (bottom, right) = sel.1.row_col(self.p.vis_cols.len());
 
-                let clipboard = Clipboard {
-                    slab: slab.into_boxed_slice(),
-                    pastes: sels
-                        .iter()
-                        .map(|(v_r, v_c)| {
-                            (
-                                VisRowOffset(v_r.0 - offset.0),
-                                self.p.vis_cols[v_c.0],
-                                RowSlabIndex(vis_map[v_r]),
-                            )
-                        })
-                        .collect(),
+                for r in top.0..=bottom.0 {
+                    for c in left.0..=right.0 {
+                        set.insert((VisRowPos(r), VisColumnPos(c)));
+                    }
                 }
-                .tap_mut(Clipboard::sort);
+            }
+        }
 
-                let sys_clip = Self::try_dump_clipboard_content(&clipboard, vwr);
-                self.clipboard = Some(clipboard);
+        set
+    }
 
-                if action == UiAction::CutSelection {
-                    self.try_apply_ui_action(table, vwr, UiAction::DeleteSelection)
-                } else {
-                    vec![]
+    /// Rows and columns to export in visual order. If `selected_only`, only the rows containing
+    /// any selected cell are returned.
+    pub fn export_layout<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+        selected_only: bool,
+    ) -> (Vec<RowIdx>, Vec<ColumnIdx>) {
+        self.validate_cc(table, vwr);
+
+        let rows = if selected_only {
+            let selected = self.collect_selected_rows();
+            selected.into_iter().map(|x| self.cc_rows[x.0]).collect()
+        } else {
+            self.cc_rows.clone()
+        };
+
+        (rows, self.p.vis_cols.clone())
+    }
+
+    /// Takes out the deferred cut, as the command clearing its source cells.
+    fn take_cut<V: RowViewer<R> + ?Sized>(&mut self, vwr: &mut V) -> Option<Command<R>> {
+        let cut = self.cc_cut.take()?;
+        let default = vwr.new_empty_row_for(EmptyRowCreateContext::DeletionDefault);
+
+        Some(Command::CcSetCells {
+            slab: vec![default].into_boxed_slice(),
+            values: (cut.cells.into_iter())
+                .map(|(r, c)| (r, c, RowSlabIndex(0)))
+                .collect(),
+            context: CellWriteContext::Clear,
+        })
+    }
+
+    /// Applies, defers or drops the paste by [`RowViewer::confirm_paste`].
+    fn gate_paste<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        vwr: &mut V,
+        summary: PasteSummary,
+        commands: Vec<Command<R>>,
+        desired_selection: Option<Vec<(RowIdx, Vec<ColumnIdx>)>>,
+    ) -> Vec<Command<R>> {
+        match vwr.confirm_paste(&summary) {
+            PasteDecision::Apply => {
+                if desired_selection.is_some() {
+                    self.cc_desired_selection = desired_selection;
                 }
-                .tap_mut(|v| {
-                    // We only overwrite system clipboard when codec support is active.
-                    if let Some(clip) = sys_clip {
-                        v.push(Command::CcUpdateSystemClipboard(clip));
-                    }
-                })
+
+                commands
             }
-            UiAction::SelectionDuplicateValues => {
-                let pivot_row = vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[ic_r.0].0]);
-                let sels = self.collect_selection();
+            PasteDecision::Confirm => {
+                self.cc_pending_paste = Some(PendingPaste {
+                    summary,
+                    commands,
+                    desired_selection,
+                });
+
+                vec![]
+            }
+            PasteDecision::Cancel => vec![],
+        }
+    }
+
+    /// Action on the selection spanning hidden rows, waiting for the user's confirmation, with
+    /// the number of the hidden rows.
+    pub fn pending_hidden_selection(&self) -> Option<(UiAction, usize)> {
+        self.cc_pending_hidden
+            .map(|x| (x, self.cc_hidden_selection.len()))
+    }
+
+    /// Takes out the pending action on the selection spanning hidden rows; it's applied to the
+    /// visible rows only if `apply` is true.
+    pub fn resolve_pending_hidden_selection(
+        &mut self,
+        table: &mut DataTable<R>,
+        vwr: &mut (impl RowViewer<R> + ?Sized),
+        apply: bool,
+    ) -> Vec<Command<R>> {
+        let Some(action) = self.cc_pending_hidden.take().filter(|_| apply) else {
+            return vec![];
+        };
+
+        self.cc_hidden_selection.clear();
+        self.try_apply_ui_action(table, vwr, action)
+    }
 
-                vec![Command::CcSetCells {
-                    slab: [pivot_row].into(),
-                    values: sels
-                        .into_iter()
-                        .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0], RowSlabIndex(0)))
-                        .collect(),
-                    context: CellWriteContext::Paste,
-                }]
-            }
-            UiAction::PasteInPlace => {
-                let Some(clip) = &self.clipboard else {
-                    return vec![];
-                };
+    pub fn set_manual_resort(&mut self, manual: bool) {
+        self.manual_resort = manual;
+    }
 
-                let values =
-                    Vec::from_iter(clip.pastes.iter().filter_map(|(offset, col, slab_id)| {
-                        let vis_r = VisRowPos(ic_r.0 + offset.0);
-                        (vis_r.0 < self.cc_rows.len())
-                            .then(|| (self.cc_rows[vis_r.0], *col, *slab_id))
-                    }));
+    /// Edited rows are left out of the sort order, waiting for [`Self::resort_stale_rows_now`].
+    pub fn is_order_stale(&self) -> bool {
+        self.manual_resort
+            && !self.p.sort.is_empty()
+            && !self.cc_stale_rows.is_empty()
+            && !self.is_editing()
+    }
 
-                let desired = self.cc_desired_selection.get_or_insert(default());
-                desired.clear();
+    /// Repositions the edited rows into the sort order on the next validation.
+    pub fn resort_stale_rows_now(&mut self) {
+        self.cc_resort_stale = true;
+    }
 
-                for (row, group) in &values.iter().chunk_by(|(row, ..)| *row) {
-                    desired.push((row, group.map(|(_, c, ..)| *c).collect()))
-                }
+    pub fn set_deferred_cut(&mut self, deferred: bool) {
+        self.deferred_cut = deferred;
 
-                vec![Command::CcSetCells {
-                    slab: clip.slab.iter().map(|x| vwr.clone_row(x)).collect(),
-                    values: values.into_boxed_slice(),
-                    context: CellWriteContext::Paste,
-                }]
-            }
-            UiAction::PasteInsert => {
-                let Some(clip) = &self.clipboard else {
-                    return vec![];
-                };
+        if !deferred {
+            self.cc_cut = None;
+        }
+    }
 
-                let mut last = usize::MAX;
-                let mut rows = clip
-                    .pastes
-                    .iter()
-                    .filter(|&(offset, ..)| replace(&mut last, offset.0) != offset.0)
-                    .map(|(offset, ..)| {
-                        (
-                            *offset,
-                            vwr.new_empty_row_for(EmptyRowCreateContext::InsertNewLine),
-                        )
-                    })
-                    .collect::<BTreeMap<_, _>>();
+    pub fn set_confirm_hidden_selection(&mut self, confirm: bool) {
+        self.confirm_hidden_selection = confirm;
+    }
 
-                for (offset, column, slab_id) in &*clip.pastes {
-                    vwr.set_cell_value(
-                        &clip.slab[slab_id.0],
-                        rows.get_mut(offset).unwrap(),
-                        column.0,
-                    );
-                }
+    /// Statistics of the current selection, recomputed only after it's changed.
+    pub fn selection_status<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+    ) -> &SelectionStatus {
+        if self.cc_selection_status.is_none() {
+            let cells = self.collect_selection();
+            let rows = self.collect_selected_rows();
+            let columns: BTreeSet<_> = cells.iter().map(|(_, c)| *c).collect();
+
+            let summary = if cells.is_empty() {
+                vec![]
+            } else {
+                let rows = (rows.iter())
+                    .map(|x| &table.rows[self.cc_rows[x.0].0])
+                    .collect_vec();
+                let columns = (columns.into_iter())
+                    .map(|x| self.p.vis_cols[x.0].0)
+                    .collect_vec();
+
+                vwr.selection_summary(&rows, &columns)
+            };
 
-                let pos = if self.p.sort.is_empty() {
-                    self.cc_rows[ic_r.0]
-                } else {
-                    RowIdx(table.rows.len())
-                };
+            self.cc_selection_status = Some(SelectionStatus {
+                cells: cells.len(),
+                rows: rows.len(),
+                summary,
+            });
+        }
 
-                let row_values = rows.into_values().collect();
-                vec![Command::InsertRows(pos, row_values)]
-            }
-            UiAction::DuplicateRow => {
-                let rows = self
-                    .collect_selected_rows()
-                    .into_iter()
-                    .map(|x| self.cc_rows[x.0])
-                    .map(|r| vwr.clone_row_for_insertion(&table.rows[r.0]))
-                    .collect();
+        self.cc_selection_status.as_ref().unwrap()
+    }
 
-                let pos = if self.p.sort.is_empty() {
-                    self.cc_rows[ic_r.0]
-                } else {
-                    RowIdx(table.rows.len())
-                };
+    /// Selected cells grouped by their rows.
+    fn selected_cells_by_row(&self) -> Vec<(RowIdx, Vec<ColumnIdx>)> {
+        let selection = self.collect_selection().into_iter();
+        let rows = selection.chunk_by(|(r, _)| *r);
+        let rows = rows.into_iter().map(|(vis_row, cells)| {
+            let columns = cells.map(|(_, c)| self.p.vis_cols[c.0]).collect();
+            (self.cc_rows[vis_row.0], columns)
+        });
 
-                vec![Command::InsertRows(pos, rows)]
-            }
-            UiAction::DeleteSelection => {
-                let default = vwr.new_empty_row_for(EmptyRowCreateContext::DeletionDefault);
-                let sels = self.collect_selection();
-                let slab = vec![default].into_boxed_slice();
+        rows.collect()
+    }
+
+    /// Action over the whole data, waiting for the user's confirmation.
+    pub fn pending_all_data(&self) -> Option<UiAction> {
+        self.cc_pending_all_data
+    }
+
+    /// Takes out the pending action over the whole data; its commands are returned only if
+    /// `apply` is true.
+    pub fn resolve_pending_all_data(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut (impl RowViewer<R> + ?Sized),
+        apply: bool,
+    ) -> Vec<Command<R>> {
+        let Some(action) = self.cc_pending_all_data.take().filter(|_| apply) else {
+            return vec![];
+        };
+
+        // Trashed rows are not part of the data from the user's view.
+        let rows: Vec<_> = (0..table.rows.len())
+            .filter(|i| !table.trash.contains(i))
+            .map(RowIdx)
+            .collect();
+        let cells = || {
+            let columns = &self.p.vis_cols;
+            let rows = rows.iter().enumerate();
+            rows.flat_map(move |(i, r)| columns.iter().map(move |c| (i, *r, *c)))
+        };
 
+        let mut commands = match action {
+            UiAction::CutSelection | UiAction::DeleteSelection => {
+                let default = vwr.new_empty_row_for(EmptyRowCreateContext::DeletionDefault);
                 vec![Command::CcSetCells {
-                    slab,
-                    values: sels
-                        .into_iter()
-                        .map(|(r, c)| (self.cc_rows[r.0], self.p.vis_cols[c.0], RowSlabIndex(0)))
-                        .collect(),
+                    slab: vec![default].into_boxed_slice(),
+                    values: cells().map(|(_, r, c)| (r, c, RowSlabIndex(0))).collect(),
                     context: CellWriteContext::Clear,
                 }]
             }
             UiAction::DeleteRow => {
-                let rows = self
-                    .collect_selected_rows()
-                    .into_iter()
-                    .map(|x| self.cc_rows[x.0])
+                let rows = (rows.iter().copied())
                     .filter(|row| vwr.confirm_row_deletion_by_ui(&table.rows[row.0]))
                     .collect();
 
-                vec![Command::RemoveRow(rows)]
+                vec![self.remove_rows(rows)]
             }
-            UiAction::SelectAll => {
-                if self.cc_rows.is_empty() {
-                    return vec![];
-                }
+            _ => vec![],
+        };
 
-                vec![Command::CcSetSelection(vec![VisSelection(
-                    VisLinearIdx(0),
-                    VisRowPos(self.cc_rows.len().saturating_sub(1)).linear_index(
-                        self.p.vis_cols.len(),
-                        VisColumnPos(self.p.vis_cols.len() - 1),
-                    ),
-                )])]
+        if matches!(action, UiAction::CopySelection | UiAction::CutSelection) {
+            let clipboard = Clipboard {
+                slab: (rows.iter())
+                    .map(|x| vwr.clone_row_as_copied_base(&table.rows[x.0]))
+                    .collect(),
+                pastes: cells()
+                    .map(|(i, _, c)| (VisRowOffset(i), c, RowSlabIndex(i)))
+                    .collect(),
+                skipped_rows: 0,
             }
+            .tap_mut(Clipboard::sort);
 
-            action @ (UiAction::NavPageDown
-            | UiAction::NavPageUp
-            | UiAction::NavTop
-            | UiAction::NavBottom) => {
-                let ofst = match action {
-                    UiAction::NavPageDown => self.cci_page_row_count as isize,
-                    UiAction::NavPageUp => -(self.cci_page_row_count as isize),
-                    UiAction::NavTop => isize::MIN,
-                    UiAction::NavBottom => isize::MAX,
-                    _ => unreachable!(),
-                };
+            // We only overwrite system clipboard when there's any text to dump.
+            if let Some(clip) = Self::try_dump_clipboard_content(&clipboard, vwr) {
+                commands.push(Command::CcUpdateSystemClipboard(clip));
+            }
 
-                let new_ic_r = (ic_r.0 as isize)
-                    .saturating_add(ofst)
-                    .clamp(0, self.cc_rows.len().saturating_sub(1) as _);
-                self.cc_interactive_cell =
-                    VisLinearIdx(new_ic_r as usize * self.p.vis_cols.len() + ic_c.0);
+            self.clipboard = Some(clipboard);
+        }
 
-                self.validate_interactive_cell(self.p.vis_cols.len());
-                vec![Command::CcSetSelection(vec![VisSelection(
-                    self.cc_interactive_cell,
-                    self.cc_interactive_cell,
-                )])]
-            }
+        commands
+    }
+
+    pub fn pending_paste(&self) -> Option<&PasteSummary> {
+        self.cc_pending_paste.as_ref().map(|x| &x.summary)
+    }
+
+    /// Takes out the pending paste; its commands are returned only if `apply` is true.
+    pub fn resolve_pending_paste(&mut self, apply: bool) -> Vec<Command<R>> {
+        let Some(paste) = self.cc_pending_paste.take().filter(|_| apply) else {
+            return vec![];
+        };
+
+        if paste.desired_selection.is_some() {
+            self.cc_desired_selection = paste.desired_selection;
         }
+
+        paste.commands
     }
 
-    fn collect_selection(&self) -> BTreeSet<(VisRowPos, VisColumnPos)> {
-        let mut set = BTreeSet::new();
+    pub fn compared_rows(&self) -> Option<[RowIdx; 2]> {
+        self.cc_compared_rows
+    }
 
-        if let CursorState::Select(selections) = &self.cc_cursor {
-            for sel in selections.iter() {
-                let (top, left) = sel.0.row_col(self.p.vis_cols.len());
-                let (bottom, right) = sel.1.row_col(self.p.vis_cols.len());
+    pub fn close_row_comparison(&mut self) {
+        self.cc_compared_rows = None;
+    }
 
-                for r in top.0..=bottom.0 {
-                    for c in left.0..=right.0 {
-                        set.insert((VisRowPos(r), VisColumnPos(c)));
-                    }
-                }
+    /// Columns of which values differ between the rows, compared by the encoded text or by
+    /// [`TableModel::compare_cell`] if the cell has no text.
+    pub fn differing_columns<V: RowViewer<R> + ?Sized>(
+        &self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+        [a, b]: [RowIdx; 2],
+    ) -> Vec<ColumnIdx> {
+        let (a, b) = (&table.rows[a.0], &table.rows[b.0]);
+        let mut texts = Vec::new();
+
+        {
+            let mut encode = text_encoder(vwr);
+            for column in 0..self.p.num_columns {
+                let (mut ta, mut tb) = (String::new(), String::new());
+                let has_text = encode(a, column, &mut ta) && encode(b, column, &mut tb);
+                texts.push(has_text.then_some((ta, tb)));
             }
         }
 
-        set
+        let model = vwr.model();
+        (texts.into_iter().enumerate())
+            .filter(|(column, text)| match text {
+                Some((ta, tb)) => ta != tb,
+                None => model
+                    .as_ref()
+                    .is_some_and(|m| m.compare_cell(a, b, *column).is_ne()),
+            })
+            .map(|(column, _)| ColumnIdx(column))
+            .collect()
     }
 
-    fn collect_selected_rows(&self) -> BTreeSet<VisRowPos> {
+    pub fn collect_selected_rows(&self) -> BTreeSet<VisRowPos> {
         let mut rows = BTreeSet::new();
 
         if let CursorState::Select(selections) = &self.cc_cursor {
@@ -1500,8 +5263,135 @@ impl<R> UiState<R> {
         VisLinearIdx(nr * self.p.vis_cols.len() + nc)
     }
 
+    /// Selection which can be extended by the fill handle; the only rectangle selected.
+    pub fn fill_source(&self) -> Option<VisSelection> {
+        match self.cursor_as_selection()? {
+            [sel] => Some(*sel),
+            _ => None,
+        }
+    }
+
+    pub fn is_filling(&self) -> bool {
+        self.cci_fill.is_some()
+    }
+
+    pub fn cci_fill_update(&mut self, current: VisLinearIdx) {
+        self.cci_fill = Some(current);
+    }
+
+    /// Rectangle the fill handle is dragged to; the source selection extended toward the cell
+    /// under the pointer, along the axis of the larger distance.
+    pub fn cci_fill_target(&self) -> Option<VisSelection> {
+        let ncol = self.p.vis_cols.len();
+        let source = self.fill_source()?;
+        let (pr, pc) = self.cci_fill?.row_col(ncol);
+        let (top, left) = source.0.row_col(ncol);
+        let (bottom, right) = source.1.row_col(ncol);
+
+        let distance =
+            |x: usize, min: usize, max: usize| x.saturating_sub(max).max(min.saturating_sub(x));
+
+        let point = if distance(pr.0, top.0, bottom.0) >= distance(pc.0, left.0, right.0) {
+            pr.linear_index(ncol, left)
+        } else {
+            top.linear_index(ncol, pc)
+        };
+
+        Some(source.union(ncol, VisSelection(point, point)))
+    }
+
+    /// Ends the fill handle dragging; returns commands which repeat the values of the source
+    /// selection over the target rectangle, and select it.
+    pub fn cci_take_fill<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        table: &DataTable<R>,
+        vwr: &mut V,
+    ) -> Vec<Command<R>> {
+        let (Some(source), Some(target)) = (self.fill_source(), self.cci_fill_target()) else {
+            self.cci_fill = None;
+            return vec![];
+        };
+
+        self.cci_fill = None;
+        if source == target {
+            return vec![];
+        }
+
+        let ncol = self.p.vis_cols.len();
+        let (top, left) = source.0.row_col(ncol);
+        let (bottom, right) = source.1.row_col(ncol);
+        let (t_top, t_left) = target.0.row_col(ncol);
+        let (t_bottom, t_right) = target.1.row_col(ncol);
+        let (height, width) = (bottom.0 - top.0 + 1, right.0 - left.0 + 1);
+
+        let mut slab = Vec::new();
+        let mut values = Vec::new();
+
+        if t_left == left && t_right == right {
+            // Vertical fill; each column takes the value of the same column.
+            slab.extend(
+                (top.0..=bottom.0)
+                    .map(|r| vwr.clone_row_as_copied_base(&table.rows[self.cc_rows[r].0])),
+            );
+
+            for r in (t_top.0..=t_bottom.0).filter(|r| !(top.0..=bottom.0).contains(r)) {
+                let slab_id =
+                    RowSlabIndex((r as isize - top.0 as isize).rem_euclid(height as _) as _);
+                for c in left.0..=right.0 {
+                    values.push((self.cc_rows[r], self.p.vis_cols[c], slab_id));
+                }
+            }
+        } else {
+            // Horizontal fill; values move across the columns, through the codec.
+            let mut cells = Vec::new();
+            if let Some(mut codec) = vwr.try_create_codec(true) {
+                for r in top.0..=bottom.0 {
+                    let row = &table.rows[self.cc_rows[r].0];
+                    for c in (t_left.0..=t_right.0).filter(|c| !(left.0..=right.0).contains(c)) {
+                        let src =
+                            left.0 + (c as isize - left.0 as isize).rem_euclid(width as _) as usize;
+                        let (src, dst) = (self.p.vis_cols[src].0, self.p.vis_cols[c].0);
+
+                        if !codec.supports_column(src) || !codec.supports_column(dst) {
+                            continue;
+                        }
+
+                        let mut data = String::new();
+                        codec.encode_column(row, src, &mut data);
+                        cells.push((r, c, data));
+                    }
+                }
+            }
+
+            let Some(mut codec) = vwr.try_create_codec(false) else {
+                return vec![];
+            };
+
+            for (r, c, data) in cells {
+                let row_id = self.cc_rows[r];
+                let col = self.p.vis_cols[c];
+                let mut row = codec.create_empty_decoded_row();
+
+                if codec.decode_column(&data, col.0, &mut row).is_ok() {
+                    values.push((row_id, col, RowSlabIndex(slab.len())));
+                    slab.push(row);
+                }
+            }
+        }
+
+        vec![
+            Command::CcSetCells {
+                slab: slab.into(),
+                values: values.into(),
+                context: CellWriteContext::Paste,
+            },
+            Command::CcSetSelection(vec![target]),
+        ]
+    }
+
     pub fn cci_take_selection(&mut self, mods: egui::Modifiers) -> Option<Vec<VisSelection>> {
         let ncol = self.p.vis_cols.len();
+        let is_rows = take(&mut self.cci_sel_rows);
         let cci_sel = self
             .cci_selection
             .take()
@@ -1518,6 +5408,42 @@ impl<R> UiState<R> {
             return Some(sel);
         }
 
+        if is_rows && mods.command_only() {
+            let (top, _) = cci_sel.0.row_col(ncol);
+            let (bottom, _) = cci_sel.1.row_col(ncol);
+            let selected = self.collect_selected_rows();
+
+            if !(top.0..=bottom.0).all(|r| selected.contains(&VisRowPos(r))) {
+                sel.push(cci_sel);
+                return Some(sel);
+            }
+
+            // Deselect the rows, splitting the selections which span over them.
+            let sel = sel.into_iter().flat_map(|x| {
+                let (x_top, x_left) = x.0.row_col(ncol);
+                let (x_bottom, x_right) = x.1.row_col(ncol);
+                let above = (x_top < top).then(|| {
+                    let end = VisRowPos(x_bottom.0.min(top.0 - 1));
+                    VisSelection(x.0, end.linear_index(ncol, x_right))
+                });
+                let below = (x_bottom > bottom).then(|| {
+                    let start = VisRowPos(x_top.0.max(bottom.0 + 1));
+                    VisSelection(start.linear_index(ncol, x_left), x.1)
+                });
+
+                above.into_iter().chain(below)
+            });
+
+            return Some(sel.collect());
+        }
+
+        if is_rows && mods.cmd_ctrl_matches(Modifiers::SHIFT) {
+            // Extend the last selection up to the clicked rows.
+            let last = sel.last_mut().unwrap();
+            *last = last.union(ncol, cci_sel);
+            return Some(sel);
+        }
+
         if mods.command_only() {
             if let Some(idx) = idx_contains {
                 sel.remove(idx);
@@ -1559,6 +5485,14 @@ pub(crate) enum Command<R> {
 
     CcSetSelection(Vec<VisSelection>), // Cache - Set Selection
 
+    /// Select the rectangle from the interactive cell to the given extent, keeping the
+    /// interactive cell.
+    CcExtendSelection(VisLinearIdx),
+
+    /// Extend the selection to the rows hidden by the filter; follows the selection of every
+    /// visible cell.
+    CcSelectAllData,
+
     SetRowValue(RowIdx, Box<R>),
     CcSetCells {
         slab: Box<[R]>,
@@ -1571,7 +5505,21 @@ pub(crate) enum Command<R> {
     },
 
     InsertRows(RowIdx, Box<[R]>),
-    RemoveRow(Vec<RowIdx>),
+
+    /// Remove the rows of sorted, disjoint ranges; contiguous rows are kept as single range, to
+    /// stay cheap on mass deletion.
+    RemoveRowRanges(Box<[Range<RowIdx>]>),
+
+    /// Soft-delete the rows; they're hidden, but kept in the table until the trash is emptied.
+    TrashRows(Vec<RowIdx>),
+    RestoreRows(Vec<RowIdx>),
+
+    /// Move `len` rows starting from `from`, so that they start at `to` after the move.
+    MoveRows {
+        from: RowIdx,
+        len: usize,
+        to: RowIdx,
+    },
 
     CcEditStart(RowIdx, VisColumnPos, Box<R>),
     CcCancelEdit,
@@ -1579,3 +5527,182 @@ pub(crate) enum Command<R> {
 
     CcUpdateSystemClipboard(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::viewer::ColumnSpec;
+
+    type Row = [i32; 3];
+
+    fn viewer() -> ColumnSpec<Row> {
+        ColumnSpec::<Row>::new()
+            .drag_i32("A", |x| &x[0], |x| &mut x[0])
+            .drag_i32("B", |x| &x[1], |x| &mut x[1])
+            .drag_i32("C", |x| &x[2], |x| &mut x[2])
+    }
+
+    fn ui_state(table: &DataTable<Row>, vwr: &mut ColumnSpec<Row>) -> UiState<Row> {
+        let mut ui = UiState::default();
+        ui.set_undo_capacity(10);
+        ui.validate_identity(vwr);
+        ui.validate_cc(table, vwr);
+        ui
+    }
+
+    #[test]
+    fn remove_row_ranges() {
+        for compress in [false, true] {
+            let mut vwr = viewer();
+            let mut table = DataTable::from_iter((0..10_000).map(|x| [x, 0, 0]));
+            let mut ui = ui_state(&table, &mut vwr);
+            ui.set_compress_undo_history(compress);
+
+            // Non-contiguous selection, with a range larger than a single undo chunk.
+            let rows = (1..5000).chain([6000]).chain(7000..9999).map(RowIdx);
+            let cmd = ui.remove_rows(rows.collect());
+            assert!(matches!(&cmd, Command::RemoveRowRanges(x) if x.len() == 3));
+
+            ui.push_new_command(&mut table, &mut vwr, cmd, 10);
+            let kept = [0]
+                .into_iter()
+                .chain(5000..6000)
+                .chain(6001..7000)
+                .chain([9999]);
+            assert!(table.iter().map(|x| x[0]).eq(kept));
+
+            let restore = &ui.undo_queue[0].restore;
+            assert_eq!(restore.len(), 4);
+            assert!(restore
+                .iter()
+                .all(|x| matches!(x, UndoCommand::Encoded(..)) == compress));
+
+            assert!(ui.undo(&mut table, &mut vwr));
+            assert!(table.iter().map(|x| x[0]).eq(0..10_000));
+        }
+    }
+
+    /// Visible rows must be in the order of a full stable sort of the filtered rows.
+    fn assert_sorted(ui: &UiState<Row>, table: &DataTable<Row>) {
+        let expected = (0..table.len())
+            .filter(|&x| table[x][0] >= 0)
+            .sorted_by_key(|&x| table[x][0])
+            .map(RowIdx)
+            .collect_vec();
+
+        assert_eq!(ui.cc_rows, expected);
+        assert_eq!(ui.cc_row_id_to_vis.len(), expected.len());
+        for (vis, row) in expected.iter().enumerate() {
+            assert_eq!(ui.cc_row_id_to_vis[row], VisRowPos(vis));
+        }
+    }
+
+    #[test]
+    fn resort_stale_rows() {
+        let mut vwr = viewer();
+        let mut table = DataTable::from_iter((0..64).map(|x| [x * 5 % 8, x, 0]));
+        let mut ui = ui_state(&table, &mut vwr);
+
+        ui.p.sort = vec![(ColumnIdx(0), IsAscending(true))];
+        ui.set_extra_filter(Some((1, Box::new(|x: &Row| x[0] >= 0))));
+        ui.validate_cc(&table, &mut vwr);
+        assert_sorted(&ui, &table);
+
+        // Single edited row is repositioned in place; among the ties, to the first and the last
+        // one, out of the rows as the filter rejects it, and back as it passes again.
+        for (row, value) in [(10, 3), (63, 0), (0, 7), (20, -1), (20, 2)] {
+            ui.apply_edit(&mut table, &mut vwr, &[(row, 0, [value, 0, 0])]);
+            ui.resort_stale_rows_now();
+            assert!(!ui.cc_dirty);
+
+            ui.validate_cc(&table, &mut vwr);
+            assert_sorted(&ui, &table);
+        }
+
+        // Bulk edit falls back to the full rebuild, in the same order.
+        let edits = (0..16)
+            .map(|x| (x * 4, 0, [x as i32 % 3, 0, 0]))
+            .collect_vec();
+        ui.apply_edit(&mut table, &mut vwr, &edits);
+        ui.resort_stale_rows_now();
+        ui.validate_cc(&table, &mut vwr);
+        assert_sorted(&ui, &table);
+    }
+
+    #[test]
+    fn remap_columns() {
+        let mut vwr = viewer();
+        let mut table = DataTable::from_iter([[1, 2, 3], [4, 5, 6]]);
+        let mut ui = ui_state(&table, &mut vwr);
+
+        ui.apply_edit(
+            &mut table,
+            &mut vwr,
+            &[(0, 1, [0, 7, 0]), (0, 2, [0, 0, 8])],
+        );
+        ui.p.sort = vec![(ColumnIdx(2), IsAscending(false))];
+        ui.cc_cursor = CursorState::Select(vec![VisSelection::from_points(
+            3,
+            VisRowPos(0).linear_index(3, VisColumnPos(1)),
+            VisRowPos(1).linear_index(3, VisColumnPos(2)),
+        )]);
+        ui.cc_interactive_cell = VisRowPos(1).linear_index(3, VisColumnPos(2));
+
+        // Column 1 is removed, the others are swapped, and a new column takes the place.
+        ui.remap_columns(3, |col| [Some(2), None, Some(0)][col.0].map(ColumnIdx));
+
+        assert_eq!(ui.p.vis_cols, [2, 0, 1].map(ColumnIdx));
+        assert_eq!(ui.p.sort, [(ColumnIdx(0), IsAscending(false))]);
+
+        // Selection shrinks into the remaining column, at its new visual position.
+        let CursorState::Select(sels) = &ui.cc_cursor else {
+            panic!("selection is lost");
+        };
+        assert_eq!(sels.len(), 1);
+        assert_eq!(sels[0].0.row_col(3), (VisRowPos(0), VisColumnPos(1)));
+        assert_eq!(sels[0].1.row_col(3), (VisRowPos(1), VisColumnPos(1)));
+        assert_eq!(ui.interactive_cell(), (VisRowPos(1), VisColumnPos(1)));
+
+        // Edits of the removed column are dropped from the history.
+        let UndoCommand::Plain(Command::SetCells { values, .. }) = &ui.undo_queue[0].apply else {
+            panic!("edit is not recorded as plain cells");
+        };
+        assert!(values.iter().map(|x| x.1).eq([ColumnIdx(0)]));
+    }
+
+    #[test]
+    fn row_edit_session() {
+        let mut vwr = viewer();
+        let mut table = DataTable::from_iter([[1, 2, 3], [4, 5, 6]]);
+        let mut ui = ui_state(&table, &mut vwr);
+        let move_right = UiAction::CommitEditionAndMove(MoveDirection::Right);
+
+        let edit = Command::CcEditStart(RowIdx(0), VisColumnPos(0), Box::new(table[0]));
+        ui.push_new_command(&mut table, &mut vwr, edit, 10);
+        ui.unwrap_editing_row_data()[0] = 10;
+
+        // Editor moves within the row, without committing.
+        assert!(ui
+            .try_move_row_editor(&mut vwr, move_right)
+            .is_some_and(|x| x.is_empty()));
+        ui.unwrap_editing_row_data()[1] = 20;
+        assert!(ui.try_move_row_editor(&mut vwr, move_right).is_some());
+        assert_eq!(ui.interactive_cell(), (VisRowPos(0), VisColumnPos(2)));
+        assert_eq!(table[0], [1, 2, 3]);
+
+        // Leaving the row, or the actions other than moving left/right, are left to the usual
+        // commit.
+        assert!(ui.try_move_row_editor(&mut vwr, move_right).is_none());
+        let move_down = UiAction::CommitEditionAndMove(MoveDirection::Down);
+        assert!(ui.try_move_row_editor(&mut vwr, move_down).is_none());
+        assert!(ui.is_editing());
+
+        // Whole row is committed as single undo step.
+        ui.push_new_command(&mut table, &mut vwr, Command::CcCommitEdit, 10);
+        assert_eq!(table[0], [10, 20, 3]);
+        assert_eq!(ui.undo_depth(), 1);
+
+        assert!(ui.undo(&mut table, &mut vwr));
+        assert_eq!(table[0], [1, 2, 3]);
+    }
+}
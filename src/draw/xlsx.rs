@@ -0,0 +1,202 @@
+//! Minimal XLSX writer; a single worksheet packed in an uncompressed ZIP archive.
+
+use std::fmt::Write;
+
+use format as f;
+
+/// Writes a workbook with single sheet, of which the first row is `header`. Cells which parse as
+/// finite numbers are written as numbers, and the others as inline strings.
+pub(crate) fn write_workbook(header: &[String], rows: &[Vec<String>]) -> Vec<u8> {
+    let mut sheet = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+        "<sheetData>"
+    ));
+
+    for (row_index, cells) in std::iter::once(header)
+        .chain(rows.iter().map(|x| &x[..]))
+        .enumerate()
+    {
+        let _ = write!(sheet, r#"<row r="{}">"#, row_index + 1);
+
+        for (column, text) in cells.iter().enumerate() {
+            let cell_ref = f!("{}{}", column_name(column), row_index + 1);
+
+            if row_index > 0 && text.trim().parse::<f64>().is_ok_and(f64::is_finite) {
+                let _ = write!(sheet, r#"<c r="{cell_ref}"><v>{}</v></c>"#, text.trim());
+            } else if !text.is_empty() {
+                let _ = write!(
+                    sheet,
+                    r#"<c r="{cell_ref}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                    escape(text)
+                );
+            }
+        }
+
+        sheet.push_str("</row>");
+    }
+
+    sheet.push_str("</sheetData></worksheet>");
+
+    let files: [(&str, &str); 5] = [
+        (
+            "[Content_Types].xml",
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+                r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+                r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+                r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+                r#"<Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+                "</Types>"
+            ),
+        ),
+        (
+            "_rels/.rels",
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+                r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>"#,
+                "</Relationships>"
+            ),
+        ),
+        (
+            "xl/workbook.xml",
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+                r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#,
+                r#"<sheets><sheet name="Sheet1" sheetId="1" r:id="rId1"/></sheets>"#,
+                "</workbook>"
+            ),
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+                r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/>"#,
+                "</Relationships>"
+            ),
+        ),
+        ("xl/worksheets/sheet1.xml", &sheet),
+    ];
+
+    write_zip(&files)
+}
+
+/// Spreadsheet column name of zero-based index; `A`, ..., `Z`, `AA`, ...
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+
+        if index < 26 {
+            break;
+        }
+
+        index = index / 26 - 1;
+    }
+
+    name.iter().rev().map(|&x| x as char).collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Packs the files into a ZIP archive without compression.
+fn write_zip(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+
+    for (name, data) in files {
+        let offset = out.len() as u32;
+        let crc = crc32(data.as_bytes());
+        let size = data.len() as u32;
+
+        // Version, flags, method(stored), time, date, crc, sizes, name length, extra length.
+        let mut fields = Vec::new();
+        fields.extend(20u16.to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields.extend(0x21u16.to_le_bytes());
+        fields.extend(crc.to_le_bytes());
+        fields.extend(size.to_le_bytes());
+        fields.extend(size.to_le_bytes());
+        fields.extend((name.len() as u16).to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+
+        out.extend(0x04034b50u32.to_le_bytes());
+        out.extend(&fields);
+        out.extend(name.as_bytes());
+        out.extend(data.as_bytes());
+
+        directory.extend(0x02014b50u32.to_le_bytes());
+        directory.extend(20u16.to_le_bytes());
+        directory.extend(&fields);
+
+        // Comment length, disk number, internal and external attributes, offset.
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u32.to_le_bytes());
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+    }
+
+    let directory_offset = out.len() as u32;
+    out.extend(&directory);
+
+    out.extend(0x06054b50u32.to_le_bytes());
+    out.extend(0u16.to_le_bytes());
+    out.extend(0u16.to_le_bytes());
+    out.extend((files.len() as u16).to_le_bytes());
+    out.extend((files.len() as u16).to_le_bytes());
+    out.extend((directory.len() as u32).to_le_bytes());
+    out.extend(directory_offset.to_le_bytes());
+    out.extend(0u16.to_le_bytes());
+
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn column_names() {
+        let name = super::column_name;
+
+        assert_eq!(name(0), "A");
+        assert_eq!(name(25), "Z");
+        assert_eq!(name(26), "AA");
+        assert_eq!(name(701), "ZZ");
+        assert_eq!(name(702), "AAA");
+    }
+
+    #[test]
+    fn crc32() {
+        assert_eq!(super::crc32(b"123456789"), 0xCBF43926);
+    }
+}
@@ -1,11 +1,21 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "serde")]
+pub mod codec;
 pub mod draw;
+pub mod model;
+pub mod source;
 pub mod viewer;
+pub mod widgets;
 
-pub use draw::{Renderer, Style};
+pub use draw::{
+    EditCommitPolicy, HiddenSelectionPolicy, ImageExportOptions, ImportPreview, ImportStatus,
+    LazyRenderer, MacroReplay, Renderer, ResortPolicy, SortCycle, Style,
+};
 pub use viewer::{RowViewer, UiAction};
 
+use std::{collections::BTreeSet, mem::take};
+
 /// You may want to sync egui version with this crate.
 pub extern crate egui;
 
@@ -29,6 +39,12 @@ pub struct DataTable<R> {
 
     dirty_flag: bool,
 
+    /// Indices of soft-deleted rows, which are hidden from the table until restored.
+    trash: BTreeSet<usize>,
+
+    /// Changes made by UI, recorded only while the journal is enabled.
+    journal: Option<Vec<viewer::ChangeEvent<R>>>,
+
     /// Ui
     ui: Option<Box<draw::state::UiState<R>>>,
 }
@@ -47,6 +63,8 @@ impl<R> Default for DataTable<R> {
             rows: Default::default(),
             ui: Default::default(),
             dirty_flag: false,
+            trash: Default::default(),
+            journal: None,
         }
     }
 }
@@ -67,12 +85,14 @@ impl<R> DataTable<R> {
 
     pub fn take(&mut self) -> Vec<R> {
         self.mark_dirty();
+        self.trash.clear();
         std::mem::take(&mut self.rows)
     }
 
     /// Replace the current data with the new one.
     pub fn replace(&mut self, new: Vec<R>) -> Vec<R> {
         self.mark_dirty();
+        self.trash.clear();
         std::mem::replace(&mut self.rows, new)
     }
 
@@ -80,12 +100,27 @@ impl<R> DataTable<R> {
     /// additional dirty flag optimization.
     pub fn retain(&mut self, mut f: impl FnMut(&R) -> bool) {
         let mut removed_any = false;
+        let mut index = 0;
+        let mut n_removed = 0;
+        let mut trash = BTreeSet::new();
+
         self.rows.retain(|row| {
             let retain = f(row);
             removed_any |= !retain;
+
+            // Keep the soft-deleted marks following their rows.
+            if !retain {
+                n_removed += 1;
+            } else if self.trash.contains(&index) {
+                trash.insert(index - n_removed);
+            }
+
+            index += 1;
             retain
         });
 
+        self.trash = trash;
+
         if removed_any {
             self.mark_dirty();
         }
@@ -117,9 +152,325 @@ impl<R> DataTable<R> {
         self.dirty_flag
     }
 
-    /// Clears the user-driven(triggered by UI) modification flag.
+    /// Starts or stops recording the changes made by UI, including undo and redo, into the
+    /// change journal; e.g. to sync the rows to a database incrementally. Stopping discards the
+    /// changes which were not drained yet.
+    ///
+    /// Programmatic changes through `DataTable` methods are not recorded.
+    pub fn set_change_journal(&mut self, enabled: bool) {
+        if enabled != self.journal.is_some() {
+            self.journal = enabled.then(Vec::new);
+        }
+    }
+
+    /// Takes the changes recorded since the last drain, in the order they were applied. Empty
+    /// unless the journal is enabled by [`Self::set_change_journal`].
+    pub fn drain_changes(&mut self) -> Vec<viewer::ChangeEvent<R>> {
+        self.journal.as_mut().map(take).unwrap_or_default()
+    }
+
+    /// Returns the interactive cell as `(row_index, column_index)` in data space; that is, the
+    /// index of underlying row vector and the column index of the viewer, regardless of the
+    /// current sort, filter and column order. While editing, the edited cell is returned.
+    ///
+    /// Returns `None` if the table was never rendered or there's no visible row.
+    pub fn interactive_cell(&self) -> Option<(usize, usize)> {
+        self.ui.as_ref()?.interactive_cell_index()
+    }
+
+    /// Screen rectangle of the cell at `(row_index, column_index)` in data space, as rendered in
+    /// the latest frame; e.g. to anchor popovers or hints to the cell. Returns `None` if the
+    /// cell was not visible. The rectangle may be partially clipped by the table body.
+    pub fn cell_rect(&self, row_index: usize, column_index: usize) -> Option<egui::Rect> {
+        self.ui.as_ref()?.cell_rect(row_index, column_index)
+    }
+
+    /// Takes the cell the user requested to edit as `(row_index, column_index)`, under
+    /// [`Style::detached_editing`]. Returns `None` if there's no new request.
+    pub fn take_edit_request(&mut self) -> Option<(usize, usize)> {
+        self.ui.as_mut()?.take_edit_request()
+    }
+
+    /// Cells reported as invalid by [`RowViewer::validate_cell`], as
+    /// `(row_index, column_index, error)` in data space. Empty if the table was never rendered.
+    pub fn invalid_cells(&self) -> impl Iterator<Item = (usize, usize, &viewer::ValidationError)> {
+        self.ui.iter().flat_map(|ui| ui.invalid_cells())
+    }
+
+    /// Visual position of the row at `row_index`, reflecting the table's current sort and
+    /// filter. Returns `None` if the row is filtered out, or the table was never rendered.
+    pub fn visual_position_of(&self, row_index: usize) -> Option<usize> {
+        self.ui.as_ref()?.visual_position_of(row_index)
+    }
+
+    /// Index of the row displayed at visual position `pos`; inverse of
+    /// [`Self::visual_position_of`].
+    pub fn row_at_visual(&self, pos: usize) -> Option<usize> {
+        self.ui.as_ref()?.row_at_visual(pos)
+    }
+
+    /// Horizontal scroll offset of the table in points, as of the latest frame; e.g. to restore
+    /// it later with [`Self::set_horizontal_scroll_offset`]. Returns `None` if the table was
+    /// never rendered.
+    pub fn horizontal_scroll_offset(&self) -> Option<f32> {
+        Some(self.ui.as_ref()?.cci_scroll_x)
+    }
+
+    /// Scrolls the table horizontally to `offset` points on the next frame. Does nothing if no
+    /// table was rendered yet.
+    pub fn set_horizontal_scroll_offset(&mut self, offset: f32) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.cci_want_scroll_x = Some(offset);
+        }
+    }
+
+    /// Returns true if keyboard macro is being recorded.
+    pub fn is_recording_macro(&self) -> bool {
+        self.ui.as_ref().is_some_and(|ui| ui.is_recording_macro())
+    }
+
+    /// Returns true if there's any recorded keyboard macro to replay.
+    pub fn has_recorded_macro(&self) -> bool {
+        self.ui.as_ref().is_some_and(|ui| ui.has_recorded_macro())
+    }
+
+    /// Queue replay of the recorded keyboard macro, which is performed on the next frame
+    /// this table is rendered. Does nothing if no table was rendered yet.
+    pub fn queue_macro_replay(&mut self, replay: MacroReplay) {
+        if let Some(ui) = self.ui.as_mut() {
+            ui.queue_macro_replay(replay);
+        }
+    }
+
+    /// Mutates each selected row through `f`, as a single undoable command. Returns the number
+    /// of affected rows.
+    ///
+    /// Every column of the row is written back, regardless of the selected columns. Use
+    /// [`Self::map_selection`] to write back only the selected cells.
+    pub fn for_each_selected_mut<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        viewer: &mut V,
+        mut f: impl FnMut(&mut R),
+    ) -> usize {
+        self.with_ui_state(|ui, table| ui.map_selection(table, viewer, true, |row, _| f(row)))
+            .unwrap_or(0)
+    }
+
+    /// Mutates each selected cell through `f`, as a single undoable command. `f` is called
+    /// with the row and the column of the selected cell, then only the selected cells are
+    /// written back. Returns the number of affected rows.
+    pub fn map_selection<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        viewer: &mut V,
+        f: impl FnMut(&mut R, usize),
+    ) -> usize {
+        self.with_ui_state(|ui, table| ui.map_selection(table, viewer, false, f))
+            .unwrap_or(0)
+    }
+
+    /// Writes each `(row_index, column_index, source)` edit through
+    /// [`RowViewer::set_cell_value`], copying the column of `source` into the row. Edits are
+    /// recorded as a single undoable command, like the ones made by UI; unlike modifications
+    /// through `DerefMut`, which bypass the undo history. Returns the number of written cells.
+    ///
+    /// Edits out of range, or of [`RowViewer::is_computed_column`], are skipped. If the table was
+    /// never rendered, the cells are written directly without undo history.
+    pub fn apply_edit<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        viewer: &mut V,
+        edits: &[(usize, usize, R)],
+    ) -> usize {
+        if let Some(n) = self.with_ui_state(|ui, table| ui.apply_edit(table, viewer, edits)) {
+            return n;
+        }
+
+        let num_columns = viewer.num_columns();
+        let mut n_written = 0;
+
+        for (row, column, src) in edits {
+            if *column >= num_columns || viewer.is_computed_column(*column) {
+                continue;
+            }
+
+            if let Some(dst) = self.rows.get_mut(*row) {
+                viewer.set_cell_value(src, dst, *column);
+                n_written += 1;
+            }
+        }
+
+        if n_written > 0 {
+            self.mark_dirty();
+        }
+
+        n_written
+    }
+
+    /// Inserts `rows` before `index`, or at the end if it's out of range, as a single undoable
+    /// command. Returns the number of inserted rows.
+    ///
+    /// If the table was never rendered, the rows are inserted directly without undo history.
+    pub fn insert_rows<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        viewer: &mut V,
+        index: usize,
+        rows: Vec<R>,
+    ) -> usize {
+        if self.ui.is_some() {
+            return self
+                .with_ui_state(|ui, table| ui.insert_rows(table, viewer, index, rows))
+                .unwrap_or(0);
+        }
+
+        let num_rows = rows.len();
+        let index = index.min(self.rows.len());
+        self.rows.splice(index..index, rows);
+
+        // Keep the soft-deleted marks following their rows.
+        let shifted = self.trash.split_off(&index);
+        self.trash.extend(shifted.into_iter().map(|x| x + num_rows));
+
+        if num_rows > 0 {
+            self.mark_dirty();
+        }
+
+        num_rows
+    }
+
+    /// Values of the selected cells as `(row_index, column_index, value)`, in visual order; e.g.
+    /// to aggregate them. Cells whose [`RowViewer::get_cell`] returns `None` are skipped.
+    pub fn selected_cell_values<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        viewer: &mut V,
+    ) -> Vec<(usize, usize, V::CellValue)> {
+        self.with_ui_state(|ui, table| ui.selected_cell_values(table, viewer))
+            .unwrap_or_default()
+    }
+
+    /// Renders the rows off-screen into a single SVG document, e.g. for printing or embedding in
+    /// reports. Cells are drawn through [`RowViewer::show_cell_view`] in a non-interactive
+    /// pass, in the current sort, filter and column order of the table.
+    ///
+    /// Rasterization is left to the caller, as this crate does not depend on any renderer.
+    pub fn render_to_image<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        viewer: &mut V,
+        options: &ImageExportOptions,
+    ) -> String {
+        draw::render_table(self, viewer, options)
+    }
+
+    /// Renders the table as box-drawn text aligned by columns, e.g. for debug logs, bug reports
+    /// and snapshot tests. Cells are encoded with the viewer's codec, in the current sort,
+    /// filter and column order; rows after `max_rows` are summarized in the last line.
+    ///
+    /// Without codec, [`RowViewer::cell_display_text`] is used instead. Returns `None` if
+    /// neither gives any text.
+    pub fn to_pretty_string<V: RowViewer<R> + ?Sized>(
+        &mut self,
+        viewer: &mut V,
+        max_rows: usize,
+    ) -> Option<String> {
+        draw::pretty_table(self, viewer, max_rows)
+    }
+
+    /// Exports the table into an `.xlsx` workbook, in the current sort, filter and column order.
+    /// Cells are encoded with the viewer's codec, or [`RowViewer::cell_display_text`] without
+    /// codec; those which look like numbers are written as numbers.
+    ///
+    /// See [`UiAction::ExportSelection`] to export the selection only.
+    #[cfg(feature = "xlsx")]
+    pub fn export_xlsx<V: RowViewer<R> + ?Sized>(&mut self, viewer: &mut V) -> Vec<u8> {
+        draw::table_xlsx(self, viewer)
+    }
+
+    /// Undoes the latest step, as [`UiAction::Undo`] does. Returns false if there's nothing to
+    /// undo, or the table was never rendered.
+    pub fn undo<V: RowViewer<R> + ?Sized>(&mut self, viewer: &mut V) -> bool {
+        self.with_ui_state(|ui, table| ui.undo(table, viewer))
+            .unwrap_or(false)
+    }
+
+    /// Redoes the latest undone step, as [`UiAction::Redo`] does. Returns false if there's
+    /// nothing to redo, or the table was never rendered.
+    pub fn redo<V: RowViewer<R> + ?Sized>(&mut self, viewer: &mut V) -> bool {
+        self.with_ui_state(|ui, table| ui.redo(table, viewer))
+            .unwrap_or(false)
+    }
+
+    /// Number of steps which can be undone.
+    pub fn undo_depth(&self) -> usize {
+        self.ui.as_ref().map_or(0, |ui| ui.undo_depth())
+    }
+
+    /// Temporarily checks out the UI state. Returns `None` if the table was never rendered.
+    fn with_ui_state<T>(
+        &mut self,
+        f: impl FnOnce(&mut draw::state::UiState<R>, &mut Self) -> T,
+    ) -> Option<T> {
+        let mut ui = self.ui.take()?;
+        let result = f(&mut ui, self);
+        self.ui = Some(ui);
+        Some(result)
+    }
+
+    /// Returns true if the row at `row_index` is soft-deleted; see [`Style::soft_delete`].
+    pub fn is_trashed(&self, row_index: usize) -> bool {
+        self.trash.contains(&row_index)
+    }
+
+    /// Indices of soft-deleted rows, in ascending order. Soft-deleted rows are kept in the
+    /// underlying vector, hidden, until they're restored or [`Self::empty_trash`] is called.
+    ///
+    /// Mutable access to the rows through `DerefMut` restores every soft-deleted row, as the
+    /// indices can't follow the rows being moved around.
+    pub fn trashed_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.trash.iter().copied()
+    }
+
+    /// Restores given soft-deleted rows. This is not recorded in the undo history. Returns the
+    /// number of restored rows.
+    pub fn restore_trashed(&mut self, rows: impl IntoIterator<Item = usize>) -> usize {
+        let n_restored = rows
+            .into_iter()
+            .filter(|row| self.trash.remove(row))
+            .count();
+
+        if n_restored > 0 {
+            self.mark_dirty();
+        }
+
+        n_restored
+    }
+
+    /// Permanently removes every soft-deleted row, returning them in ascending index order.
+    pub fn empty_trash(&mut self) -> Vec<R> {
+        if self.trash.is_empty() {
+            return Vec::new();
+        }
+
+        let trash = std::mem::take(&mut self.trash);
+        let mut removed = Vec::with_capacity(trash.len());
+
+        for (index, row) in std::mem::take(&mut self.rows).into_iter().enumerate() {
+            if trash.contains(&index) {
+                removed.push(row);
+            } else {
+                self.rows.push(row);
+            }
+        }
+
+        self.mark_dirty();
+        removed
+    }
+
+    /// Clears the user-driven(triggered by UI) modification flag. This also clears the
+    /// modified row markers.
     pub fn clear_user_modification_flag(&mut self) {
         self.dirty_flag = false;
+
+        if let Some(ui) = self.ui.as_mut() {
+            ui.clear_modified_rows();
+        }
     }
 }
 
@@ -147,6 +498,7 @@ impl<R> std::ops::Deref for DataTable<R> {
 impl<R> std::ops::DerefMut for DataTable<R> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.mark_dirty();
+        self.trash.clear();
         &mut self.rows
     }
 }
@@ -158,6 +510,128 @@ impl<R: Clone> Clone for DataTable<R> {
             // UI field is treated as cache.
             ui: None,
             dirty_flag: self.dirty_flag,
+            trash: self.trash.clone(),
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::viewer::{ColumnSpec, DecodeErrorBehavior, RowCodec, RowViewer};
+    use crate::DataTable;
+
+    pub(crate) type TestRow = (i32, bool);
+
+    /// Viewer of `(value, flag)` rows shared by tests. The `computed` column is neither written
+    /// nor encoded by the codec.
+    pub(crate) struct TestViewer {
+        pub spec: ColumnSpec<TestRow>,
+        pub computed: Option<usize>,
+    }
+
+    impl Default for TestViewer {
+        fn default() -> Self {
+            Self {
+                spec: ColumnSpec::<TestRow>::new()
+                    .drag_i32("Value", |x| &x.0, |x| &mut x.0)
+                    .bool("Flag", |x| &x.1, |x| &mut x.1),
+                computed: None,
+            }
+        }
+    }
+
+    impl RowViewer<TestRow> for TestViewer {
+        type CellValue = ();
+
+        fn num_columns(&mut self) -> usize {
+            self.spec.num_columns()
+        }
+
+        fn is_computed_column(&mut self, column: usize) -> bool {
+            self.computed == Some(column)
+        }
+
+        fn try_create_codec(&mut self, _: bool) -> Option<Box<dyn RowCodec<TestRow> + '_>> {
+            Some(Box::new(TestCodec(&self.spec, self.computed)))
         }
+
+        fn show_cell_view(&mut self, _: &mut egui::Ui, _: &TestRow, _: usize) {}
+
+        fn show_cell_editor(
+            &mut self,
+            _: &mut egui::Ui,
+            _: &mut TestRow,
+            _: usize,
+        ) -> Option<egui::Response> {
+            None
+        }
+
+        fn set_cell_value(&mut self, src: &TestRow, dst: &mut TestRow, column: usize) {
+            self.spec.set_cell_value(src, dst, column);
+        }
+
+        fn new_empty_row(&mut self) -> TestRow {
+            TestRow::default()
+        }
+    }
+
+    /// Codec of the column spec, which leaves the computed column out.
+    struct TestCodec<'a>(&'a ColumnSpec<TestRow>, Option<usize>);
+
+    impl RowCodec<TestRow> for TestCodec<'_> {
+        fn supports_column(&self, column: usize) -> bool {
+            self.1 != Some(column)
+        }
+
+        fn create_empty_decoded_row(&mut self) -> TestRow {
+            TestRow::default()
+        }
+
+        fn encode_column(&mut self, src_row: &TestRow, column: usize, dst: &mut String) {
+            self.0.encode_column(src_row, column, dst);
+        }
+
+        fn decode_column(
+            &mut self,
+            src_data: &str,
+            column: usize,
+            dst_row: &mut TestRow,
+        ) -> Result<(), DecodeErrorBehavior> {
+            self.0.decode_column(src_data, column, dst_row)
+        }
+    }
+
+    #[test]
+    fn apply_edit() {
+        let mut viewer = TestViewer::default();
+        let mut table = DataTable::from_iter([(1, false), (2, false)]);
+
+        // Written directly, before the table is rendered.
+        assert_eq!(table.apply_edit(&mut viewer, &[(0, 1, (0, true))]), 1);
+        assert_eq!(table[0], (1, true));
+
+        drop(crate::Renderer::new(&mut table, &mut viewer));
+
+        let edits = [(1, 0, (5, true)), (2, 0, (6, true))];
+        assert_eq!(table.apply_edit(&mut viewer, &edits), 1);
+        assert_eq!(table[1], (5, false));
+
+        assert!(table.undo(&mut viewer));
+        assert_eq!(table[1], (2, false));
+
+        // Computed columns are never written, nor counted.
+        viewer.computed = Some(1);
+        let mut table = DataTable::from_iter([(1, false), (2, false)]);
+        let edits = [(0, 1, (0, true)), (1, 0, (5, true))];
+
+        assert_eq!(table.apply_edit(&mut viewer, &edits), 1);
+        assert_eq!(&table[..], &[(1, false), (5, false)]);
+
+        drop(crate::Renderer::new(&mut table, &mut viewer));
+
+        assert_eq!(table.apply_edit(&mut viewer, &[(0, 1, (0, true))]), 0);
+        assert_eq!(table.apply_edit(&mut viewer, &edits), 1);
+        assert_eq!(table[0], (1, false));
     }
 }
@@ -0,0 +1,162 @@
+//! Filtering and sorting of rows, separated from the presentation so that one model can be
+//! shared by multiple presentations (table, list, export) and tested without egui.
+
+use std::{cmp::Ordering, hash::Hasher};
+
+/// Which rows are shown, and in which order. Provided to the table through
+/// [`crate::RowViewer::model`]; without a model, every row is shown in the original order.
+pub trait TableModel<R> {
+    /// Compare two column contents for sort.
+    fn compare_cell(&self, row_a: &R, row_b: &R, column: usize) -> Ordering {
+        let _ = (row_a, row_b, column);
+        Ordering::Equal
+    }
+
+    /// Feeds the filter state into `state`. This is used to determine if the filter has changed.
+    ///
+    /// ```ignore
+    /// fn row_filter_hash(&mut self, mut state: &mut dyn std::hash::Hasher) {
+    ///     self.filter.hash(&mut state);
+    /// }
+    /// ```
+    fn row_filter_hash(&mut self, state: &mut dyn Hasher) {
+        let _ = state;
+    }
+
+    /// Filter single row. If this returns false, the row will be hidden.
+    fn filter_row(&mut self, row: &R) -> bool {
+        let _ = row;
+        true
+    }
+}
+
+/// [`TableModel`] of which rows are filtered and sorted on multiple threads; provided through
+/// [`crate::RowViewer::parallel_model`]. [`TableModel::compare_cell`] must be pure, as it's
+/// called from multiple threads at once.
+#[cfg(feature = "rayon")]
+pub trait ParallelTableModel<R>: TableModel<R> + Sync {
+    /// Filter single row, like [`TableModel::filter_row`] but without mutation.
+    fn filter_row_pure(&self, row: &R) -> bool {
+        let _ = row;
+        true
+    }
+}
+
+#[cfg(feature = "rayon")]
+type FilterAndSort<R> =
+    fn(&dyn ParallelTableModel<R>, &[R], Vec<usize>, &[(usize, bool)]) -> Vec<usize>;
+
+/// [`ParallelTableModel`] of rows which can be shared between threads.
+#[cfg(feature = "rayon")]
+pub struct ParallelModel<'a, R> {
+    model: &'a dyn ParallelTableModel<R>,
+
+    /// Instantiated where `R: Sync` is known, so that the table itself needs no such bound.
+    run: FilterAndSort<R>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, R: Sync> ParallelModel<'a, R> {
+    pub fn new(model: &'a dyn ParallelTableModel<R>) -> Self {
+        Self {
+            model,
+            run: filter_and_sort::<R>,
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<R> ParallelModel<'_, R> {
+    /// Filters the `candidates`, then sorts them by `sort` keys of `(column, is_ascending)` in
+    /// priority, then by the index.
+    pub(crate) fn filter_and_sort(
+        &self,
+        rows: &[R],
+        candidates: Vec<usize>,
+        sort: &[(usize, bool)],
+    ) -> Vec<usize> {
+        (self.run)(self.model, rows, candidates, sort)
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn filter_and_sort<R: Sync>(
+    model: &dyn ParallelTableModel<R>,
+    rows: &[R],
+    candidates: Vec<usize>,
+    sort: &[(usize, bool)],
+) -> Vec<usize> {
+    use rayon::prelude::*;
+
+    let mut visible: Vec<_> = candidates
+        .into_par_iter()
+        .filter(|&i| model.filter_row_pure(&rows[i]))
+        .collect();
+
+    if !sort.is_empty() {
+        visible.par_sort_by(|&a, &b| {
+            sort.iter()
+                .map(|&(column, asc)| {
+                    let ord = model.compare_cell(&rows[a], &rows[b], column);
+                    if asc {
+                        ord
+                    } else {
+                        ord.reverse()
+                    }
+                })
+                .find(|x| x.is_ne())
+                .unwrap_or_else(|| a.cmp(&b))
+        });
+    }
+
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::TableModel;
+
+    struct Even;
+
+    impl TableModel<i32> for Even {
+        fn compare_cell(&self, row_a: &i32, row_b: &i32, _: usize) -> Ordering {
+            row_b.cmp(row_a)
+        }
+
+        fn filter_row(&mut self, row: &i32) -> bool {
+            row % 2 == 0
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    impl super::ParallelTableModel<i32> for Even {
+        fn filter_row_pure(&self, row: &i32) -> bool {
+            row % 2 == 0
+        }
+    }
+
+    #[test]
+    fn model_without_ui() {
+        let model: &mut dyn TableModel<i32> = &mut Even;
+        let mut rows: Vec<_> = (0..6).filter(|x| model.filter_row(x)).collect();
+        rows.sort_by(|a, b| model.compare_cell(a, b, 0));
+
+        assert_eq!(rows, [4, 2, 0]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_model() {
+        use super::ParallelModel;
+
+        let rows: Vec<i32> = (0..1000).map(|x| x % 10).collect();
+        let model = ParallelModel::new(&Even);
+        let visible = model.filter_and_sort(&rows, (0..rows.len()).collect(), &[(0, true)]);
+
+        assert_eq!(visible.len(), 500);
+        assert_eq!(&visible[..3], [8, 18, 28]);
+        assert_eq!(visible.last(), Some(&990));
+    }
+}
@@ -0,0 +1,251 @@
+//! Rows fetched on demand, for tables which can't hold every row in memory; e.g. millions of
+//! rows in a database or behind the network. Rendered by [`crate::LazyRenderer`].
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Range,
+};
+
+/// Source of rows which are fetched lazily. The renderer requests only the visible window of
+/// rows plus a prefetch margin, and renders a placeholder for rows which are not available yet.
+pub trait RowSource<R> {
+    /// Total number of rows, including the ones not fetched yet.
+    fn num_rows(&mut self) -> usize;
+
+    /// Row at given index, or `None` if it's not fetched yet.
+    fn row(&self, index: usize) -> Option<&R>;
+
+    /// Called every frame with the range of rows to be displayed soon. Start fetching the rows
+    /// which are not available yet; this must not block the rendering.
+    fn request_rows(&mut self, range: Range<usize>);
+
+    /// Returns true if the source sorts rows by itself, e.g. on the server. Then clicking the
+    /// header of a sortable column calls [`Self::sort`].
+    fn supports_sorting(&mut self) -> bool {
+        false
+    }
+
+    /// Current sort as `(column, ascending)`, to render the sort indicator.
+    fn sorted_by(&self) -> Option<(usize, bool)> {
+        None
+    }
+
+    /// Sorts rows by `column`. Rows already fetched are not in the new order anymore; drop them
+    /// and serve the rows of the new order on later requests.
+    fn sort(&mut self, column: usize, ascending: bool) {
+        let _ = (column, ascending);
+    }
+}
+
+/* ------------------------------------------ Paged Rows ----------------------------------------- */
+
+/// Page cache implementation of [`RowSource`]. Requested pages are queued; the application takes
+/// them with [`PagedRows::take_requests`], fetches them in its own way, then delivers the
+/// result with [`PagedRows::insert_rows`]. Pages far from the requested window are evicted
+/// once the cache exceeds its capacity.
+///
+/// ```
+/// use egui_data_table::source::{PagedRows, RowSource};
+///
+/// let mut rows = PagedRows::<u32>::new(1000, 100);
+/// rows.request_rows(150..250);
+///
+/// let requests = rows.take_requests();
+/// assert_eq!(requests, [100..200, 200..300]);
+///
+/// for range in requests {
+///     rows.insert_rows(range.start, range.map(|x| x as u32).collect());
+/// }
+///
+/// assert_eq!(rows.row(150), Some(&150));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PagedRows<R> {
+    num_rows: usize,
+    page_size: usize,
+
+    /// Maximum number of cached pages.
+    capacity: usize,
+    pages: HashMap<usize, Vec<R>>,
+
+    /// Pages requested but not delivered yet.
+    pending: BTreeSet<usize>,
+
+    /// Pending pages which are not taken by the application yet.
+    queued: Vec<usize>,
+
+    /// Pages of the latest request, which are kept over the others on eviction.
+    recent: Range<usize>,
+
+    /// Whether the application sorts rows on fetch; see [`Self::with_sorting`].
+    sortable: bool,
+    sort: Option<(usize, bool)>,
+}
+
+impl<R> PagedRows<R> {
+    pub fn new(num_rows: usize, page_size: usize) -> Self {
+        Self {
+            num_rows,
+            page_size: page_size.max(1),
+            capacity: 64,
+            pages: HashMap::new(),
+            pending: BTreeSet::new(),
+            queued: Vec::new(),
+            recent: 0..0,
+            sortable: false,
+            sort: None,
+        }
+    }
+
+    /// Lets the table sort the rows by clicking headers. The application fetches the rows in
+    /// the order of [`RowSource::sorted_by`]; every cached page is dropped on sort.
+    pub fn with_sorting(mut self) -> Self {
+        self.sortable = true;
+        self
+    }
+
+    /// Maximum number of cached pages. Defaults to 64.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Changes the total number of rows. Cached pages are kept; call [`Self::clear`] if the
+    /// contents are changed as well.
+    pub fn set_num_rows(&mut self, num_rows: usize) {
+        self.num_rows = num_rows;
+    }
+
+    /// Drops every cached page and pending requests.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+        self.pending.clear();
+        self.queued.clear();
+    }
+
+    /// Returns row ranges of the pages to fetch, which were requested since the last call.
+    pub fn take_requests(&mut self) -> Vec<Range<usize>> {
+        std::mem::take(&mut self.queued)
+            .into_iter()
+            .map(|page| self.page_range(page))
+            .collect()
+    }
+
+    /// Delivers fetched rows starting from `start`, which should be a start of page returned by
+    /// [`Self::take_requests`].
+    pub fn insert_rows(&mut self, start: usize, rows: Vec<R>) {
+        let page = start / self.page_size;
+        self.pending.remove(&page);
+        self.pages.insert(page, rows);
+        self.evict();
+    }
+
+    /// Returns true if any requested page is not delivered yet.
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    fn page_range(&self, page: usize) -> Range<usize> {
+        let start = page * self.page_size;
+        start..(start + self.page_size).min(self.num_rows)
+    }
+
+    /// Evicts pages farthest from the recently requested pages, until the cache fits in the
+    /// capacity.
+    fn evict(&mut self) {
+        if self.pages.len() <= self.capacity {
+            return;
+        }
+
+        let near = &self.recent;
+        let distance = |page: usize| {
+            if page < near.start {
+                near.start - page
+            } else {
+                page.saturating_sub(near.end.saturating_sub(1))
+            }
+        };
+
+        let mut pages: Vec<_> = self.pages.keys().copied().collect();
+        pages.sort_by_key(|page| std::cmp::Reverse(distance(*page)));
+
+        for page in pages.into_iter().take(self.pages.len() - self.capacity) {
+            self.pages.remove(&page);
+        }
+    }
+}
+
+impl<R> RowSource<R> for PagedRows<R> {
+    fn num_rows(&mut self) -> usize {
+        self.num_rows
+    }
+
+    fn row(&self, index: usize) -> Option<&R> {
+        self.pages
+            .get(&(index / self.page_size))?
+            .get(index % self.page_size)
+    }
+
+    fn request_rows(&mut self, range: Range<usize>) {
+        let range = range.start.min(self.num_rows)..range.end.min(self.num_rows);
+        if range.is_empty() {
+            return;
+        }
+
+        let pages = range.start / self.page_size..(range.end - 1) / self.page_size + 1;
+        for page in pages.clone() {
+            if !self.pages.contains_key(&page) && self.pending.insert(page) {
+                self.queued.push(page);
+            }
+        }
+
+        self.recent = pages;
+        self.evict();
+    }
+
+    fn supports_sorting(&mut self) -> bool {
+        self.sortable
+    }
+
+    fn sorted_by(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+
+    fn sort(&mut self, column: usize, ascending: bool) {
+        self.sort = Some((column, ascending));
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PagedRows, RowSource};
+
+    #[test]
+    fn paged_rows_eviction() {
+        let mut rows = PagedRows::<usize>::new(100, 10).with_capacity(2);
+
+        for start in [0, 10, 20] {
+            rows.request_rows(start..start + 10);
+            for range in rows.take_requests() {
+                rows.insert_rows(range.start, range.collect());
+            }
+        }
+
+        // The farthest page from the latest request is evicted.
+        assert_eq!(rows.row(5), None);
+        assert_eq!(rows.row(15), Some(&15));
+        assert_eq!(rows.row(25), Some(&25));
+
+        // Already cached or pending pages are not requested again.
+        rows.request_rows(15..45);
+        assert_eq!(rows.take_requests(), [30..40, 40..50]);
+        assert!(rows.is_loading());
+        rows.request_rows(40..50);
+        assert!(rows.take_requests().is_empty());
+    }
+}
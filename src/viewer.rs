@@ -1,9 +1,11 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, cell::RefCell, collections::VecDeque, rc::Rc};
 
 use egui::{Key, KeyboardShortcut, Modifiers};
 pub use egui_extras::Column as TableColumnConfig;
 use tap::prelude::Pipe;
 
+use crate::model::TableModel;
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DecodeErrorBehavior {
     /// Skip the cell and continue decoding.
@@ -17,16 +19,94 @@ pub enum DecodeErrorBehavior {
     Abort,
 }
 
+/// Delimited text format of the system clipboard contents.
+///
+/// ```
+/// use egui_data_table::viewer::ClipboardFormat;
+///
+/// // Semicolon separated values, which is common in locales using decimal comma.
+/// let format = ClipboardFormat::delimited(';');
+/// assert_eq!(format.quote, Some('"'));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClipboardFormat {
+    /// Cell separator.
+    pub delimiter: char,
+
+    /// Quote character, as described in RFC 4180. Quoted cells may contain delimiters and
+    /// newlines, and quote characters within are doubled. [`None`] disables quoting.
+    pub quote: Option<char>,
+
+    /// Escape tabs, newlines and backslashes with backslash, e.g. `\t`, `\n`.
+    pub backslash_escape: bool,
+
+    /// Encode the copied cells as JSON array of objects keyed by [`RowViewer::column_key`],
+    /// where numbers and booleans keep their types. Pasted JSON records are decoded regardless
+    /// of this, and the other fields describe the delimited text to decode otherwise.
+    pub json: bool,
+}
+
+impl ClipboardFormat {
+    /// Tab separated values with backslash escapes. Quoted cells are not recognized.
+    pub const TSV: Self = Self {
+        delimiter: '\t',
+        quote: None,
+        backslash_escape: true,
+        json: false,
+    };
+
+    /// Comma separated values; RFC 4180.
+    pub const CSV: Self = Self::delimited(',');
+
+    /// Tab separated values with RFC 4180 quoting, which is what spreadsheet applications like
+    /// Excel or LibreOffice put into the clipboard.
+    pub const SPREADSHEET: Self = Self::delimited('\t');
+
+    /// JSON records, which keep embedded tabs and newlines intact. Delimited text pasted from
+    /// elsewhere is decoded as [`Self::SPREADSHEET`].
+    pub const JSON: Self = Self {
+        json: true,
+        ..Self::SPREADSHEET
+    };
+
+    /// RFC 4180 quoted values separated by `delimiter`.
+    pub const fn delimited(delimiter: char) -> Self {
+        Self {
+            delimiter,
+            quote: Some('"'),
+            backslash_escape: false,
+            json: false,
+        }
+    }
+}
+
+impl Default for ClipboardFormat {
+    fn default() -> Self {
+        Self::TSV
+    }
+}
+
 /// A trait for encoding/decoding row data. Any valid UTF-8 string can be used for encoding,
-/// however, as csv is used for clipboard operations, it is recommended to serialize data in simple
-/// string format as possible.
+/// however, as delimited text is used for clipboard operations, it is recommended to serialize
+/// data in simple string format as possible.
 pub trait RowCodec<R> {
-    type DeserializeError;
+    /// Format of the clipboard contents to encode into, and decode from.
+    fn clipboard_format(&self) -> ClipboardFormat {
+        ClipboardFormat::TSV
+    }
+
+    /// Returns false if the column can't round-trip through text, e.g. binary or complex data.
+    /// Such columns are encoded as empty text and never decoded, while the other columns still
+    /// go through the system clipboard.
+    fn supports_column(&self, column: usize) -> bool {
+        let _ = column;
+        true
+    }
 
     /// Creates a new empty row for decoding
     fn create_empty_decoded_row(&mut self) -> R;
 
-    /// Tries encode column data of given row into a string. As the cell for CSV row is already
+    /// Tries encode column data of given row into a string. As the cell for the row is already
     /// occupied, if any error or unsupported data is found for that column, just empty out the
     /// destination string buffer.
     fn encode_column(&mut self, src_row: &R, column: usize, dst: &mut String);
@@ -42,8 +122,6 @@ pub trait RowCodec<R> {
 
 /// A placeholder codec for row viewers that not require serialization.
 impl<R> RowCodec<R> for () {
-    type DeserializeError = ();
-
     fn create_empty_decoded_row(&mut self) -> R {
         unimplemented!()
     }
@@ -65,12 +143,47 @@ impl<R> RowCodec<R> for () {
 }
 
 /// The primary trait for the spreadsheet viewer.
+///
+/// The trait is object safe; `&mut dyn RowViewer<R, CellValue = T>` can be passed to the
+/// renderer, to select the viewer at runtime.
 // TODO: When lifetime for `'static` is stabilized; remove the `static` bound.
-pub trait RowViewer<R>: 'static {
+pub trait RowViewer<R>: std::any::Any {
+    /// Value of a single cell, to carry values across the columns without knowing the row type;
+    /// see [`Self::get_cell`]. Use `()` if not needed.
+    type CellValue;
+
     /// Number of columns. Changing this will completely invalidate the table rendering status,
     /// including undo histories. Therefore, frequently changing this value is discouraged.
     fn num_columns(&mut self) -> usize;
 
+    /// Stable identity of the column, which is used to persist layout, map clipboard headers,
+    /// and migrate states when the column list is changed. Keys are used only when they're
+    /// unique among columns.
+    ///
+    /// Defaults to [`RowViewer::column_name`]; override this if the column name can change.
+    fn column_key(&mut self, column: usize) -> Cow<'static, str> {
+        self.column_name(column)
+    }
+
+    /// Version of the column schema. When this changes, the table remaps its column states, e.g.
+    /// undo histories, selections, sort and visibility, through [`RowViewer::remap_column`]
+    /// instead of resetting them. Persisted layout of different version is remapped as well.
+    ///
+    /// Changing `num_columns` without changing this version still resets the table state.
+    fn column_schema_version(&mut self) -> u64 {
+        0
+    }
+
+    /// Maps the column index of the given previous schema version into the current column
+    /// index. Return `None` if the column was removed in the current schema.
+    ///
+    /// Columns of current schema which are not mapped from any previous column are regarded
+    /// as newly added, and become visible.
+    fn remap_column(&mut self, prev_version: u64, column: usize) -> Option<usize> {
+        let _ = (prev_version, column);
+        None
+    }
+
     /// Name of the column. This can be dynamically changed.
     fn column_name(&mut self, column: usize) -> Cow<'static, str> {
         Cow::Borrowed(
@@ -88,9 +201,9 @@ pub trait RowViewer<R>: 'static {
     /// It is just okay to choose not to implement both encoding and decoding; returning `None`
     /// conditionally based on `is_encoding` parameter is also valid. It is guaranteed that created
     /// codec will be used only for the same mode during its lifetime.
-    fn try_create_codec(&mut self, is_encoding: bool) -> Option<impl RowCodec<R>> {
+    fn try_create_codec(&mut self, is_encoding: bool) -> Option<Box<dyn RowCodec<R> + '_>> {
         let _ = is_encoding;
-        None::<()>
+        None
     }
 
     /// Returns the rendering configuration for the column.
@@ -107,27 +220,182 @@ pub trait RowViewer<R>: 'static {
         }
     }
 
+    /// Value of the cell, used to copy cells across different columns and to aggregate values
+    /// with [`crate::DataTable::selected_cell_values`]. If this returns `Some`, internal
+    /// clipboard contents pasted onto other columns than the copied ones are moved onto the
+    /// target columns through [`Self::set_cell`].
+    fn get_cell(&mut self, row: &R, column: usize) -> Option<Self::CellValue> {
+        let _ = (row, column);
+        None
+    }
+
+    /// Converts the value of column `src` to be pasted onto column `dst`, e.g. integer to string.
+    /// Cells whose value can't be converted are skipped, rather than rejecting the whole paste.
+    ///
+    /// Default implementation passes the value as-is to [`Self::set_cell`].
+    fn convert_cell(
+        &mut self,
+        src: usize,
+        dst: usize,
+        value: Self::CellValue,
+    ) -> Option<Self::CellValue> {
+        let _ = (src, dst);
+        Some(value)
+    }
+
+    /// Writes the value into the cell. Returns false if the column can't take the value.
+    fn set_cell(&mut self, row: &mut R, column: usize, value: Self::CellValue) -> bool {
+        let _ = (row, column, value);
+        false
+    }
+
+    /// Returns true if the column is derived from the other columns, e.g. `total = qty * price`.
+    /// Computed columns are rendered, sorted and filtered as usual, but never edited, decoded
+    /// from clipboard, nor written by [`Self::set_cell_value`].
+    fn is_computed_column(&mut self, column: usize) -> bool {
+        let _ = column;
+        false
+    }
+
     /// Returns if given column is 'sortable'
     fn is_sortable_column(&mut self, column: usize) -> bool {
         let _ = column;
         false
     }
 
-    /// Compare two column contents for sort.
-    fn compare_cell(&self, row_a: &R, row_b: &R, column: usize) -> std::cmp::Ordering {
-        let _ = (row_a, row_b, column);
-        std::cmp::Ordering::Equal
+    /// Returns false if the column should stay at its position, e.g. key columns. It can't be
+    /// dragged by its header, nor can the other columns be moved across it.
+    fn is_reorderable_column(&mut self, column: usize) -> bool {
+        let _ = column;
+        true
+    }
+
+    /// Returns false if the column can't be hidden by the header context menu, the column picker
+    /// or [`UiAction::HideCurrentColumn`].
+    fn is_hidable_column(&mut self, column: usize) -> bool {
+        let _ = column;
+        true
     }
 
-    /// Get hash value of a filter. This is used to determine if the filter has changed.
-    fn row_filter_hash(&mut self) -> &impl std::hash::Hash {
-        &()
+    /// Direction a sortable column is sorted in by the first click on its header, which is
+    /// reversed by the second click and cleared by the third. Return `None` to follow
+    /// [`crate::Style::sort_cycle`].
+    fn default_sort_direction(&mut self, column: usize) -> Option<SortDirection> {
+        let _ = column;
+        None
     }
 
-    /// Filter single row. If this returns false, the row will be hidden.
-    fn filter_row(&mut self, row: &R) -> bool {
-        let _ = row;
-        true
+    /// Model which filters and sorts the rows. Without it, every row is shown in the original
+    /// order.
+    fn model(&mut self) -> Option<&mut dyn TableModel<R>> {
+        None
+    }
+
+    /// Model which filters and sorts the rows on multiple threads, used instead of
+    /// [`RowViewer::model`] when all rows are filtered and sorted again. The latter is still
+    /// used for the filter hash and repositioning of edited rows, so both should agree.
+    ///
+    /// ```ignore
+    /// fn parallel_model(&mut self) -> Option<ParallelModel<'_, MyRow>> {
+    ///     Some(ParallelModel::new(&self.model))
+    /// }
+    /// ```
+    #[cfg(feature = "rayon")]
+    fn parallel_model(&mut self) -> Option<crate::model::ParallelModel<'_, R>> {
+        None
+    }
+
+    /// If true, a filter row is rendered under the header, where
+    /// [`RowViewer::show_column_filter`] is called for every visible column.
+    fn has_column_filters(&mut self) -> bool {
+        false
+    }
+
+    /// Render filter widget of the column in the filter row. The filter state should be
+    /// consulted by [`TableModel::filter_row`] and reflected in [`TableModel::row_filter_hash`];
+    /// rows are re-filtered whenever the hash changes, or the returned response is changed.
+    fn show_column_filter(&mut self, ui: &mut egui::Ui, column: usize) -> Option<egui::Response> {
+        let _ = (ui, column);
+        None
+    }
+
+    /// If true, a summary row sticks to the bottom of the table, where
+    /// [`RowViewer::show_column_summary`] is called for every visible column.
+    fn has_column_summary(&mut self) -> bool {
+        false
+    }
+
+    /// Render the summary of the column in the summary row, e.g. sum, count or average of the
+    /// values. `rows` iterates the rows shown by the current filter, in the current order.
+    fn show_column_summary(
+        &mut self,
+        ui: &mut egui::Ui,
+        column: usize,
+        rows: &mut dyn Iterator<Item = &R>,
+    ) {
+        let _ = (ui, column, rows);
+    }
+
+    /// Create the matcher of the query of the search bar, which checks if the cell at given row
+    /// and column matches; see [`UiAction::OpenSearch`]. One matcher is used for the whole scan
+    /// of the rows.
+    ///
+    /// Default implementation encodes the cell with the codec of
+    /// [`RowViewer::try_create_codec`], or [`RowViewer::cell_display_text`] without codec,
+    /// then looks for the query ignoring ASCII case.
+    fn cell_matcher<'a>(&'a mut self, query: &str) -> CellMatcher<'a, R>
+    where
+        R: 'a,
+    {
+        let query = query.to_ascii_lowercase();
+        let mut encode = text_encoder(self);
+        let mut buf = String::new();
+
+        Box::new(move |row, column| {
+            buf.clear();
+            if !encode(row, column, &mut buf) {
+                return false;
+            }
+
+            buf.make_ascii_lowercase();
+            buf.contains(&query)
+        })
+    }
+
+    /// Replace occurrences of the query in the cell, for the replace mode of the search bar.
+    /// Returns false if nothing was replaced.
+    ///
+    /// Default implementation replaces the query in the encoded cell contents ignoring ASCII
+    /// case, then decodes it back into the row.
+    fn replace_in_cell(
+        &mut self,
+        row: &mut R,
+        column: usize,
+        query: &str,
+        replacement: &str,
+    ) -> bool {
+        let mut buf = String::new();
+        {
+            let Some(mut codec) = self.try_create_codec(true) else {
+                return false;
+            };
+
+            if !codec.supports_column(column) {
+                return false;
+            }
+
+            codec.encode_column(row, column, &mut buf);
+        }
+
+        let Some(replaced) = replace_ignore_ascii_case(&buf, query, replacement) else {
+            return false;
+        };
+
+        let Some(mut codec) = self.try_create_codec(false) else {
+            return false;
+        };
+
+        codec.decode_column(&replaced, column, row).is_ok()
     }
 
     /// Display values of the cell. Any input will be consumed before table renderer;
@@ -138,6 +406,101 @@ pub trait RowViewer<R>: 'static {
     /// containing cell.
     fn show_cell_view(&mut self, ui: &mut egui::Ui, row: &R, column: usize);
 
+    /// Conditional formatting of the cell, e.g. negative numbers in red. Applied by the
+    /// renderer before [`RowViewer::show_cell_view`]; the background is painted beneath the
+    /// selection highlight.
+    fn cell_style(&mut self, row: &R, column: usize) -> Option<CellStyle> {
+        let _ = (row, column);
+        None
+    }
+
+    /// Tooltip shown while the cell is hovered, e.g. the full text of truncated contents or
+    /// metadata of the value. Queried only for the hovered cell; the error of an invalid cell
+    /// takes precedence over this.
+    fn cell_tooltip(&mut self, row: &R, column: usize) -> Option<egui::WidgetText> {
+        let _ = (row, column);
+        None
+    }
+
+    /// If true, the row is listed first regardless of the sort and filter, and sticks to the top
+    /// of the table body while scrolled. Derive it from the row data, or from a stable key of
+    /// the row, as it's queried again whenever the rows are sorted or filtered.
+    ///
+    /// The rows are sorted again when a row is edited. If the pin state is changed elsewhere,
+    /// feed it into [`TableModel::row_filter_hash`].
+    fn is_pinned_row(&mut self, row: &R) -> bool {
+        let _ = row;
+        false
+    }
+
+    /// Number of text lines of the row. With fixed [`crate::Style::table_row_height`], the row
+    /// is given the height of that many lines; e.g. for rows with multiline notes, without
+    /// measuring every row as heterogeneous row heights do.
+    fn row_line_count(&mut self, row: &R) -> usize {
+        let _ = row;
+        1
+    }
+
+    /// Aggregates of the selection shown in the status bar as `(label, value)`, e.g. the sum and
+    /// average of the selected numbers; see [`crate::Style::status_bar`]. `rows` are the rows
+    /// with any selected cell in visual order, and `columns` the columns with any selected cell.
+    fn selection_summary(
+        &mut self,
+        rows: &[&R],
+        columns: &[usize],
+    ) -> Vec<(Cow<'static, str>, String)> {
+        let _ = (rows, columns);
+        Vec::new()
+    }
+
+    /// Badge shown at the leading edge of the row header, e.g. to flag modified or invalid rows
+    /// during an editing session.
+    fn row_status(&mut self, row: &R) -> RowStatus {
+        let _ = row;
+        RowStatus::None
+    }
+
+    /// Column of which text is shown in the row header, next to the row number, e.g. the name
+    /// of the row. The row header then stays pinned to the left edge while the table is
+    /// scrolled horizontally, so that wide rows remain identifiable. Requires
+    /// [`Self::cell_display_text`].
+    ///
+    /// The column is still rendered as a regular column unless it's hidden.
+    fn row_label_column(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// If true, long texts of the column overflow into the adjacent empty cells on the right,
+    /// like spreadsheets do, rather than being clipped. Requires [`Self::cell_text`], which is
+    /// painted in place of [`Self::show_cell_view`].
+    fn is_spillover_column(&mut self, column: usize) -> bool {
+        let _ = column;
+        false
+    }
+
+    /// Plain text of the cell. Painted for [`Self::is_spillover_column`], and a cell whose
+    /// text is empty can be overflowed by its left neighbor.
+    fn cell_text<'a>(&mut self, row: &'a R, column: usize) -> Option<Cow<'a, str>> {
+        let _ = (row, column);
+        None
+    }
+
+    /// Text of the cell for viewers without codec. Copying to the system clipboard, search and
+    /// text export fall back to this in text-only mode, while pasting still requires a codec.
+    ///
+    /// Defaults to [`Self::cell_text`].
+    fn cell_display_text(&mut self, row: &R, column: usize) -> Option<String> {
+        self.cell_text(row, column).map(Cow::into_owned)
+    }
+
+    /// Values of the sparkline to display in place of [`RowViewer::show_cell_view`]. If this
+    /// returns `Some`, the table renders a [`crate::widgets::Sparkline`] sized to the cell,
+    /// which shows the exact value on hover.
+    fn cell_sparkline<'a>(&mut self, row: &'a R, column: usize) -> Option<Cow<'a, [f32]>> {
+        let _ = (row, column);
+        None
+    }
+
     /// Use this to check if given cell is going to take any dropped payload / use as drag
     /// source.
     fn on_cell_view_response(
@@ -158,6 +521,44 @@ pub trait RowViewer<R>: 'static {
         column: usize,
     ) -> Option<egui::Response>;
 
+    /// How the editor of given cell takes the keyboard focus when editing starts. Widgets which
+    /// change their behavior on focus, e.g. [`egui::DragValue`] turning into text input, should
+    /// use [`EditorFocus::None`] to remain draggable.
+    fn cell_editor_focus(&mut self, row: &R, column: usize) -> EditorFocus {
+        let _ = (row, column);
+        EditorFocus::Immediate
+    }
+
+    /// How commits of the editor of given column are recorded into the undo history.
+    fn column_commit_policy(&mut self, column: usize) -> CommitPolicy {
+        let _ = column;
+        CommitPolicy::Separate
+    }
+
+    /// Validates the value of a cell. Called for the rows changed through the UI (edit, paste,
+    /// undo and the like), and for every row once after the table is modified from code, e.g.
+    /// through `DerefMut`; sorting or filtering doesn't re-validate. Invalid cells are outlined,
+    /// showing the error message on hover, and can be queried with
+    /// [`crate::DataTable::invalid_cells`].
+    ///
+    /// This doesn't block the write; use [`Self::confirm_cell_write_by_ui`] to reject values.
+    fn validate_cell(&mut self, row: &R, column: usize) -> Result<(), ValidationError> {
+        let _ = (row, column);
+        Ok(())
+    }
+
+    /// Called on commit when the table was mutated from code (e.g. through `DerefMut`) while
+    /// the edition was in progress, so the row index captured at the start of the edition may
+    /// refer to another row, or to nothing. Returns the index of the row to write `edition`
+    /// into, or `None` to discard the edition.
+    ///
+    /// Locate the row by its key stored in `edition` if rows have one. By default, the edition
+    /// is written to the same index as long as it's in range, and discarded otherwise.
+    fn resolve_stale_edit(&mut self, rows: &[R], row: usize, edition: &R) -> Option<usize> {
+        let _ = edition;
+        (row < rows.len()).then_some(row)
+    }
+
     /// Set the value of a column in a row.
     fn set_cell_value(&mut self, src: &R, dst: &mut R, column: usize);
 
@@ -181,26 +582,90 @@ pub trait RowViewer<R>: 'static {
         true
     }
 
+    /// Receives the `.xlsx` workbook exported by [`UiAction::ExportSelection`], e.g. to save it
+    /// into a file.
+    #[cfg(feature = "xlsx")]
+    fn on_xlsx_export(&mut self, data: Vec<u8>) {
+        let _ = data;
+    }
+
+    /// Called before applying a paste, to decide whether it's applied right away, after the
+    /// user confirms it on an overlay, or dropped; e.g. to confirm large pastes only.
+    fn confirm_paste(&mut self, summary: &PasteSummary) -> PasteDecision {
+        let _ = summary;
+        PasteDecision::Apply
+    }
+
+    /// Depth of the row in the tree, for tree-structured data like nested BOMs or file trees.
+    /// The parent of a row is the nearest preceding row of smaller depth in data order; thus
+    /// children should follow their parent in the data.
+    ///
+    /// Once any row is nested, the first visible column is indented by the depth with a toggle
+    /// to expand or collapse the children. Filtering keeps the ancestors of the matched rows,
+    /// and sorting applies among siblings.
+    fn row_depth(&mut self, row: &R) -> usize {
+        let _ = row;
+        0
+    }
+
     /// Create a new empty row.
     fn new_empty_row(&mut self) -> R;
 
     /// Create a new empty row under the given context.
-    fn new_empty_row_for(&mut self, context: EmptyRowCreateContext) -> R {
+    fn new_empty_row_for(&mut self, context: EmptyRowCreateContext<R>) -> R {
         let _ = context;
         self.new_empty_row()
     }
 
+    /// Default value of the column in a new row, written through [`Self::set_cell`] over the
+    /// row created by [`Self::new_empty_row_for`]. Applied to the columns missing from the
+    /// clipboard contents inserted as new rows, and to every column of the row appended from the
+    /// new row placeholder.
+    fn default_cell_value(
+        &mut self,
+        column: usize,
+        context: EmptyRowCreateContext<R>,
+    ) -> Option<Self::CellValue> {
+        let _ = (column, context);
+        None
+    }
+
+    /// Prototypes of new rows, listed with their labels in the "New row from template" submenu
+    /// of the context menu. Chosen template is inserted as a new row.
+    fn row_templates(&mut self) -> Vec<(Cow<'static, str>, R)> {
+        Vec::new()
+    }
+
     /// Create duplication of existing row.
     ///
     /// You may want to override this method for more efficient duplication.
     fn clone_row(&mut self, row: &R) -> R {
         let mut dst = self.new_empty_row();
         for i in 0..self.num_columns() {
-            self.set_cell_value(row, &mut dst, i);
+            if !self.is_computed_column(i) {
+                self.set_cell_value(row, &mut dst, i);
+            }
         }
         dst
     }
 
+    /// Storage of rows shared with the application, e.g. `Rc<RefCell<T>>` rows which also live
+    /// in another index; see [`SharedRows`]. Then edits and undo write through the stored rows
+    /// rather than replacing them, and rows restored by undo are the same ones removed.
+    ///
+    /// [`Self::clone_row`] is still used for snapshots of the row contents, so it must copy the
+    /// contents rather than the handle. Identity of removed rows isn't kept in the undo history
+    /// compressed by [`crate::Style::compress_undo_history`].
+    fn row_storage(&mut self) -> Option<Box<dyn RowStorage<R> + '_>> {
+        None
+    }
+
+    /// Called for every row whose position is changed by manual reordering, with its new
+    /// index. Store it as an ordering key to save and restore the custom order of rows.
+    fn write_order(&mut self, row: &mut R, index: usize) {
+        let _ = (row, index);
+    }
+
     /// Create duplication of existing row for insertion.
     fn clone_row_for_insertion(&mut self, row: &R) -> R {
         self.clone_row(row)
@@ -212,16 +677,70 @@ pub trait RowViewer<R>: 'static {
         self.clone_row(row)
     }
 
+    /// Appends entries to the context menu of the cell, after the built-in ones. Custom
+    /// entries are handled right away; push built-in actions into `actions` to run them on the
+    /// table. Call [`egui::Ui::close_menu`] to close the menu.
+    fn extend_cell_context_menu(
+        &mut self,
+        ui: &mut egui::Ui,
+        row: &R,
+        column: usize,
+        actions: &mut Vec<UiAction>,
+    ) {
+        let _ = (ui, row, column, actions);
+    }
+
+    /// Appends entries to the context menu of the column header; see
+    /// [`Self::extend_cell_context_menu`].
+    fn extend_header_context_menu(
+        &mut self,
+        ui: &mut egui::Ui,
+        column: usize,
+        actions: &mut Vec<UiAction>,
+    ) {
+        let _ = (ui, column, actions);
+    }
+
     /// Called when a cell is selected/highlighted.
     fn on_highlight_cell(&mut self, row: &R, column: usize) {
         let _ = (row, column);
     }
 
+    /// Called after an undo or redo step is applied, with the changes made to the table; row
+    /// and column indices are in data space, as of right after each change. Read the new values
+    /// from the table to mirror them, e.g. into a persistence layer.
+    fn on_undo_applied(&mut self, kind: UndoKind, changes: &[TableChange]) {
+        let _ = (kind, changes);
+    }
+
     /// Called when a row selected/highlighted status changes.
     fn on_highlight_change(&mut self, highlighted: &[&R], unhighlighted: &[&R]) {
         let (_, _) = (highlighted, unhighlighted);
     }
 
+    /// Marker color of the row on the overview minimap; e.g. invalid or flagged rows. Matches of
+    /// the search bar are marked on their own lane.
+    /// Called only when [`crate::Style::minimap_width`] is set, and the markers are cached; they
+    /// are collected again when the rows are rebuilt or changed, or the selection or the search
+    /// of the table changes.
+    fn minimap_row_marker(&mut self, row: &R) -> Option<egui::Color32> {
+        let _ = row;
+        None
+    }
+
+    /// Paint custom decorations over the table body, after every visible cell is rendered.
+    /// The painter is clipped to the visible body area; use `geometry` to locate cells.
+    fn paint_overlay(&mut self, painter: &egui::Painter, geometry: CellGeometry) {
+        let _ = (painter, geometry);
+    }
+
+    /// Check if the row is marked as given kind, which is used for navigation between marked
+    /// rows. [`MarkKind::Modified`] is tracked by the table itself, so it's never queried.
+    fn is_row_marked(&mut self, row: &R, kind: MarkKind) -> bool {
+        let _ = (row, kind);
+        false
+    }
+
     /// Return hotkeys for the current context.
     fn hotkeys(&mut self, context: &UiActionContext) -> Vec<(egui::KeyboardShortcut, UiAction)> {
         self::default_hotkeys(context)
@@ -235,71 +754,626 @@ pub trait RowViewer<R>: 'static {
     }
 }
 
-/* ------------------------------------------- Context ------------------------------------------ */
+/// Checks if the cell at given row and column matches the query; see
+/// [`RowViewer::cell_matcher`].
+pub type CellMatcher<'a, R> = Box<dyn FnMut(&R, usize) -> bool + 'a>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[non_exhaustive]
-pub enum CellWriteContext {
-    /// Value is being pasted/duplicated from different row.
-    Paste,
+/// Appends the text of a cell to the buffer. Returns false if the cell has no text.
+pub(crate) type TextEncoder<'a, R> = Box<dyn FnMut(&R, usize, &mut String) -> bool + 'a>;
 
-    /// Value is being cleared by cut/delete operation.
-    Clear,
+/// Encodes cells with the codec, or with [`RowViewer::cell_display_text`] in text-only mode if
+/// the viewer has no codec.
+pub(crate) fn text_encoder<'a, R: 'a, V: RowViewer<R> + ?Sized>(
+    vwr: &'a mut V,
+) -> TextEncoder<'a, R> {
+    // Codec borrows the viewer for its whole lifetime; probe it first.
+    if vwr.try_create_codec(true).is_none() {
+        return Box::new(|row, column, dst| {
+            vwr.cell_display_text(row, column)
+                .map(|text| dst.push_str(&text))
+                .is_some()
+        });
+    }
+
+    match vwr.try_create_codec(true) {
+        Some(mut codec) => Box::new(move |row, column, dst| {
+            if !codec.supports_column(column) {
+                return false;
+            }
+
+            codec.encode_column(row, column, dst);
+            true
+        }),
+        None => Box::new(|_, _, _| false),
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[non_exhaustive]
-pub enum EmptyRowCreateContext {
-    /// Row is created to be used as simple default template.
-    Default,
+/// Replace every occurrence of `query` ignoring ASCII case. Returns `None` if there's nothing
+/// to replace.
+fn replace_ignore_ascii_case(text: &str, query: &str, replacement: &str) -> Option<String> {
+    if query.is_empty() {
+        return None;
+    }
 
-    /// Row is created to be used as explicit `empty` value when deletion
-    DeletionDefault,
+    // ASCII case conversion preserves byte offsets.
+    let lower = text.to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
 
-    /// Row is created to be inserted as a new row.
-    InsertNewLine,
+    for (pos, _) in lower.match_indices(&query) {
+        out.push_str(&text[last..pos]);
+        out.push_str(replacement);
+        last = pos + query.len();
+    }
+
+    (last > 0).then(|| out + &text[last..])
 }
 
-/* ------------------------------------------- Hotkeys ------------------------------------------ */
+/* --------------------------------------- Dynamic Columns -------------------------------------- */
 
-/// Base context for determining current input state.
-#[derive(Debug, Clone)]
-#[non_exhaustive]
-pub struct UiActionContext {
-    pub cursor: UiCursorState,
-}
+/// Helper for viewers whose columns are derived from data; e.g. one column per distinct
+/// process name. Tracks column names over rebuilds, so that the table can remap its states by
+/// column name instead of index.
+///
+/// Delegate [`RowViewer::num_columns`], [`RowViewer::column_name`], [`RowViewer::column_key`],
+/// [`RowViewer::column_schema_version`] and [`RowViewer::remap_column`] to this.
+///
+/// ```
+/// let mut columns = egui_data_table::viewer::DynamicColumns::new(["cpu", "mem"]);
+/// let version = columns.version();
+///
+/// assert!(columns.rebuild(["mem", "disk", "cpu"]));
+/// assert_eq!(columns.remap(version, 0), Some(2));
+/// assert_eq!(columns.remap(version, 1), Some(0));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DynamicColumns {
+    names: Vec<String>,
+    version: u64,
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum UiCursorState {
-    Idle,
-    Editing,
-    SelectOne,
-    SelectMany,
+    /// Column names of recent previous versions, to remap from.
+    history: VecDeque<(u64, Vec<String>)>,
 }
 
-impl UiCursorState {
-    pub fn is_idle(&self) -> bool {
-        matches!(self, Self::Idle)
+impl DynamicColumns {
+    /// Number of previous versions kept for remapping.
+    const HISTORY_LEN: usize = 16;
+
+    pub fn new(names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            names: names.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
     }
 
-    pub fn is_editing(&self) -> bool {
-        matches!(self, Self::Editing)
+    /// Replace the column list. Returns true and bumps the version if anything changed.
+    pub fn rebuild(&mut self, names: impl IntoIterator<Item = impl Into<String>>) -> bool {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        if names == self.names {
+            return false;
+        }
+
+        let prev = std::mem::replace(&mut self.names, names);
+        self.history.push_back((self.version, prev));
+        if self.history.len() > Self::HISTORY_LEN {
+            self.history.pop_front();
+        }
+
+        self.version += 1;
+        true
     }
 
-    pub fn is_selecting(&self) -> bool {
-        matches!(self, Self::SelectOne | Self::SelectMany)
+    pub fn version(&self) -> u64 {
+        self.version
     }
-}
 
-/* ----------------------------------------- Ui Actions ----------------------------------------- */
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
 
-/// Represents a user interaction, calculated from the UI input state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-#[non_exhaustive]
-pub enum UiAction {
-    SelectionStartEditing,
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
 
-    CancelEdition,
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn name(&self, column: usize) -> &str {
+        &self.names[column]
+    }
+
+    /// Find current column index of the given name.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|x| x == name)
+    }
+
+    /// Maps a column of previous version into the current one, by its name.
+    pub fn remap(&self, prev_version: u64, column: usize) -> Option<usize> {
+        let names = if prev_version == self.version {
+            &self.names
+        } else {
+            &self.history.iter().find(|(v, _)| *v == prev_version)?.1
+        };
+
+        self.position(names.get(column)?)
+    }
+}
+
+/* ----------------------------------------- Column Spec ---------------------------------------- */
+
+/// Viewer of which columns are plain fields of the row, built from field accessors instead of
+/// implementing [`RowViewer`] by hand. Every column is sortable, and the system clipboard is
+/// supported through a text codec.
+///
+/// ```
+/// use egui_data_table::viewer::ColumnSpec;
+///
+/// #[derive(Default)]
+/// struct Student {
+///     name: String,
+///     age: i32,
+///     enrolled: bool,
+/// }
+///
+/// let columns = ColumnSpec::<Student>::new()
+///     .text("Name", |x| &x.name, |x| &mut x.name)
+///     .drag_i32("Age", |x| &x.age, |x| &mut x.age)
+///     .bool("Student", |x| &x.enrolled, |x| &mut x.enrolled);
+///
+/// assert_eq!(columns.len(), 3);
+/// ```
+///
+/// [`crate::Renderer::with_columns`] builds it in place.
+pub struct ColumnSpec<R> {
+    columns: Vec<(Cow<'static, str>, Field<R>)>,
+}
+
+/// Shared and mutable accessors of a field.
+type Accessor<R, T> = (fn(&R) -> &T, fn(&mut R) -> &mut T);
+
+enum Field<R> {
+    Text(Accessor<R, String>),
+    I32(Accessor<R, i32>),
+    F64(Accessor<R, f64>),
+    Bool(Accessor<R, bool>),
+}
+
+impl<R> Default for ColumnSpec<R> {
+    fn default() -> Self {
+        Self {
+            columns: Vec::new(),
+        }
+    }
+}
+
+impl<R> ColumnSpec<R> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Text column, edited with a single line text editor.
+    pub fn text(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        get: fn(&R) -> &String,
+        get_mut: fn(&mut R) -> &mut String,
+    ) -> Self {
+        self.push(name, Field::Text((get, get_mut)))
+    }
+
+    /// Integer column, edited with a drag value.
+    pub fn drag_i32(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        get: fn(&R) -> &i32,
+        get_mut: fn(&mut R) -> &mut i32,
+    ) -> Self {
+        self.push(name, Field::I32((get, get_mut)))
+    }
+
+    /// Floating point column, edited with a drag value.
+    pub fn drag_f64(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        get: fn(&R) -> &f64,
+        get_mut: fn(&mut R) -> &mut f64,
+    ) -> Self {
+        self.push(name, Field::F64((get, get_mut)))
+    }
+
+    /// Boolean column, edited with a checkbox.
+    pub fn bool(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        get: fn(&R) -> &bool,
+        get_mut: fn(&mut R) -> &mut bool,
+    ) -> Self {
+        self.push(name, Field::Bool((get, get_mut)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    fn push(mut self, name: impl Into<Cow<'static, str>>, field: Field<R>) -> Self {
+        self.columns.push((name.into(), field));
+        self
+    }
+
+    fn field(&self, column: usize) -> &Field<R> {
+        &self.columns[column].1
+    }
+}
+
+impl<R> TableModel<R> for ColumnSpec<R> {
+    fn compare_cell(&self, row_a: &R, row_b: &R, column: usize) -> std::cmp::Ordering {
+        match self.field(column) {
+            Field::Text((get, _)) => get(row_a).cmp(get(row_b)),
+            Field::I32((get, _)) => get(row_a).cmp(get(row_b)),
+            Field::F64((get, _)) => get(row_a).total_cmp(get(row_b)),
+            Field::Bool((get, _)) => get(row_a).cmp(get(row_b)),
+        }
+    }
+}
+
+impl<R: Default> RowCodec<R> for &ColumnSpec<R> {
+    fn create_empty_decoded_row(&mut self) -> R {
+        R::default()
+    }
+
+    fn encode_column(&mut self, src_row: &R, column: usize, dst: &mut String) {
+        use std::fmt::Write;
+
+        let _ = match self.field(column) {
+            Field::Text((get, _)) => write!(dst, "{}", get(src_row)),
+            Field::I32((get, _)) => write!(dst, "{}", get(src_row)),
+            Field::F64((get, _)) => write!(dst, "{}", get(src_row)),
+            Field::Bool((get, _)) => write!(dst, "{}", get(src_row)),
+        };
+    }
+
+    fn decode_column(
+        &mut self,
+        src_data: &str,
+        column: usize,
+        dst_row: &mut R,
+    ) -> Result<(), DecodeErrorBehavior> {
+        fn parse<T: std::str::FromStr>(text: &str) -> Result<T, DecodeErrorBehavior> {
+            text.trim()
+                .parse()
+                .map_err(|_| DecodeErrorBehavior::SkipCell)
+        }
+
+        match self.field(column) {
+            Field::Text((_, get_mut)) => *get_mut(dst_row) = src_data.to_owned(),
+            Field::I32((_, get_mut)) => *get_mut(dst_row) = parse(src_data)?,
+            Field::F64((_, get_mut)) => *get_mut(dst_row) = parse(src_data)?,
+            Field::Bool((_, get_mut)) => *get_mut(dst_row) = parse(src_data)?,
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Default + 'static> RowViewer<R> for ColumnSpec<R> {
+    type CellValue = ();
+
+    fn num_columns(&mut self) -> usize {
+        self.columns.len()
+    }
+
+    fn column_name(&mut self, column: usize) -> Cow<'static, str> {
+        self.columns[column].0.clone()
+    }
+
+    fn try_create_codec(&mut self, _: bool) -> Option<Box<dyn RowCodec<R> + '_>> {
+        Some(Box::new(&*self))
+    }
+
+    fn is_sortable_column(&mut self, _: usize) -> bool {
+        true
+    }
+
+    fn model(&mut self) -> Option<&mut dyn TableModel<R>> {
+        Some(self)
+    }
+
+    fn show_cell_view(&mut self, ui: &mut egui::Ui, row: &R, column: usize) {
+        let _ = match self.field(column) {
+            Field::Text((get, _)) => ui.label(get(row).as_str()),
+            Field::I32((get, _)) => ui.label(get(row).to_string()),
+            Field::F64((get, _)) => ui.label(get(row).to_string()),
+            Field::Bool((get, _)) => ui.checkbox(&mut { *get(row) }, ""),
+        };
+    }
+
+    fn cell_text<'a>(&mut self, row: &'a R, column: usize) -> Option<Cow<'a, str>> {
+        Some(match self.field(column) {
+            Field::Text((get, _)) => get(row).as_str().into(),
+            Field::I32((get, _)) => get(row).to_string().into(),
+            Field::F64((get, _)) => get(row).to_string().into(),
+            Field::Bool((get, _)) => get(row).to_string().into(),
+        })
+    }
+
+    fn show_cell_editor(
+        &mut self,
+        ui: &mut egui::Ui,
+        row: &mut R,
+        column: usize,
+    ) -> Option<egui::Response> {
+        match self.field(column) {
+            Field::Text((_, get_mut)) => ui.text_edit_singleline(get_mut(row)),
+            Field::I32((_, get_mut)) => ui.add(egui::DragValue::new(get_mut(row))),
+            Field::F64((_, get_mut)) => ui.add(egui::DragValue::new(get_mut(row))),
+            Field::Bool((_, get_mut)) => ui.checkbox(get_mut(row), ""),
+        }
+        .into()
+    }
+
+    fn cell_editor_focus(&mut self, _row: &R, column: usize) -> EditorFocus {
+        match self.field(column) {
+            Field::Text(_) => EditorFocus::Immediate,
+            Field::I32(_) | Field::F64(_) | Field::Bool(_) => EditorFocus::None,
+        }
+    }
+
+    fn set_cell_value(&mut self, src: &R, dst: &mut R, column: usize) {
+        match self.field(column) {
+            Field::Text((get, get_mut)) => get_mut(dst).clone_from(get(src)),
+            Field::I32((get, get_mut)) => *get_mut(dst) = *get(src),
+            Field::F64((get, get_mut)) => *get_mut(dst) = *get(src),
+            Field::Bool((get, get_mut)) => *get_mut(dst) = *get(src),
+        }
+    }
+
+    fn new_empty_row(&mut self) -> R {
+        R::default()
+    }
+}
+
+/* ------------------------------------------- Context ------------------------------------------ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CellWriteContext {
+    /// Value is being pasted/duplicated from different row.
+    Paste,
+
+    /// Value is being cleared by cut/delete operation.
+    Clear,
+
+    /// Value is being replaced by the search bar.
+    Replace,
+}
+
+/// Focus policy of the cell editor when editing starts; see [`RowViewer::cell_editor_focus`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EditorFocus {
+    /// Focus the editor widget immediately.
+    #[default]
+    Immediate,
+
+    /// Don't focus the editor widget.
+    None,
+
+    /// Focus the editor widget, selecting all of its text if it's a text editor.
+    SelectAll,
+}
+
+/// Policy of recording editor commits into the undo history; see
+/// [`RowViewer::column_commit_policy`].
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum CommitPolicy {
+    /// Every commit is recorded as a separate undo step.
+    #[default]
+    Separate,
+
+    /// Commits on the same cell within `window` seconds from the previous one are merged into
+    /// the previous undo step, unless any other command took place in between. Useful for
+    /// continuous controls like sliders or drag values, which are committed repeatedly.
+    Coalesce { window: f32 },
+}
+
+/// Summary of a pending paste, given to [`RowViewer::confirm_paste`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PasteSummary {
+    /// Number of cells to be written.
+    pub cells: usize,
+
+    /// Number of rows to be written or inserted.
+    pub rows: usize,
+
+    /// Number of clipboard rows skipped due to decode errors.
+    pub skipped_rows: usize,
+
+    /// Whether the paste inserts new rows instead of overwriting the existing ones.
+    pub insert: bool,
+}
+
+/// Return value of [`RowViewer::confirm_paste`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteDecision {
+    /// Apply the paste right away.
+    #[default]
+    Apply,
+
+    /// Show a confirmation overlay with the summary, then apply the paste if the user accepts.
+    Confirm,
+
+    /// Drop the paste.
+    Cancel,
+}
+
+/// Error reported by [`RowViewer::validate_cell`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Message displayed on hover of the invalid cell.
+    pub message: Cow<'static, str>,
+}
+
+impl ValidationError {
+    pub fn new(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Rows stored behind a shared pointer; see [`RowViewer::row_storage`].
+pub trait RowStorage<R> {
+    /// Another handle to the same row, used when the row moves out of the table and back, e.g.
+    /// removal and its undo.
+    fn share(&mut self, row: &R) -> R;
+
+    /// Overwrites the contents of stored row `dst` with `src`, keeping the identity of `dst`.
+    fn write(&mut self, dst: &mut R, src: &R);
+}
+
+/// [`RowStorage`] of `Rc<RefCell<T>>` rows.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SharedRows;
+
+impl<T: Clone> RowStorage<Rc<RefCell<T>>> for SharedRows {
+    fn share(&mut self, row: &Rc<RefCell<T>>) -> Rc<RefCell<T>> {
+        row.clone()
+    }
+
+    fn write(&mut self, dst: &mut Rc<RefCell<T>>, src: &Rc<RefCell<T>>) {
+        // Writing a row into itself would panic on the double borrow.
+        if !Rc::ptr_eq(dst, src) {
+            dst.borrow_mut().clone_from(&src.borrow());
+        }
+    }
+}
+
+/// Style of a cell returned by [`RowViewer::cell_style`]. `None` keeps the table default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CellStyle {
+    /// Background color of the cell.
+    pub bg: Option<egui::Color32>,
+
+    /// Text color of the widgets shown by [`RowViewer::show_cell_view`].
+    pub fg: Option<egui::Color32>,
+
+    /// Font of the texts shown by [`RowViewer::show_cell_view`].
+    pub font: Option<egui::FontId>,
+}
+
+/// Badge of a row returned by [`RowViewer::row_status`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[non_exhaustive]
+pub enum RowStatus {
+    /// No badge.
+    #[default]
+    None,
+
+    /// Row has changes, shown in the warning color.
+    Modified,
+
+    /// Row has invalid values, shown in the error color.
+    Error,
+
+    /// Custom icon text with its color.
+    Custom(&'static str, egui::Color32),
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EmptyRowCreateContext<'a, R> {
+    /// Row is created to be used as simple default template.
+    Default,
+
+    /// Row is created to be used as explicit `empty` value when deletion
+    DeletionDefault,
+
+    /// Row is created to be inserted as a new row.
+    InsertNewLine,
+
+    /// Row is created to be inserted right below `source_row`; e.g. to inherit stable fields
+    /// like category or date from the neighbor.
+    InsertBelow { source_row: &'a R },
+}
+
+impl<R> Clone for EmptyRowCreateContext<'_, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R> Copy for EmptyRowCreateContext<'_, R> {}
+
+impl<R> PartialEq for EmptyRowCreateContext<'_, R> {
+    /// Source rows are compared by their identity.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::InsertBelow { source_row: a }, Self::InsertBelow { source_row: b }) => {
+                std::ptr::eq(*a, *b)
+            }
+            (a, b) => std::mem::discriminant(a) == std::mem::discriminant(b),
+        }
+    }
+}
+
+impl<R> Eq for EmptyRowCreateContext<'_, R> {}
+
+/* ------------------------------------------- Hotkeys ------------------------------------------ */
+
+/// Base context for determining current input state.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct UiActionContext {
+    pub cursor: UiCursorState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiCursorState {
+    Idle,
+    Editing,
+    SelectOne,
+    SelectMany,
+}
+
+impl UiCursorState {
+    pub fn is_idle(&self) -> bool {
+        matches!(self, Self::Idle)
+    }
+
+    pub fn is_editing(&self) -> bool {
+        matches!(self, Self::Editing)
+    }
+
+    pub fn is_selecting(&self) -> bool {
+        matches!(self, Self::SelectOne | Self::SelectMany)
+    }
+}
+
+/* ----------------------------------------- Ui Actions ----------------------------------------- */
+
+/// Represents a user interaction, calculated from the UI input state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UiAction {
+    SelectionStartEditing,
+
+    CancelEdition,
     CommitEdition,
 
     CommitEditionAndMove(MoveDirection),
@@ -308,9 +1382,17 @@ pub enum UiAction {
     Redo,
 
     MoveSelection(MoveDirection),
+
+    /// Grow or shrink the selection from the interactive cell, moving its opposite corner.
+    ExtendSelection(MoveDirection),
+
     CopySelection,
     CutSelection,
 
+    /// Drop the marks of the deferred cut, leaving its source cells intact. See
+    /// [`crate::Style::deferred_cut`].
+    CancelCut,
+
     PasteInPlace,
     PasteInsert,
 
@@ -324,7 +1406,109 @@ pub enum UiAction {
     NavBottom,
 
     SelectionDuplicateValues,
+
+    /// Fill each column of the selection with a series continuing its first cells, e.g. 1, 2, 3,
+    /// `Item 7`, `Item 8` or dates by day, through the codec. Two first cells of the same kind
+    /// give the step; otherwise, the first cell is incremented by one.
+    FillSeries,
+
     SelectAll,
+
+    /// Select every visible cell, extending the selection to the rows hidden by the filter.
+    /// Copy, cut and deletion then apply to the whole data, after the user's confirmation.
+    SelectAllIncludingFiltered,
+
+    /// Restore every soft-deleted row from the trash. See [`crate::Style::soft_delete`].
+    RestoreTrash,
+
+    /// Open the search bar, or close it if it's already open.
+    OpenSearch,
+
+    /// Insert a new empty row below the interactive row, which is created with
+    /// [`EmptyRowCreateContext::InsertBelow`].
+    InsertRowBelow,
+
+    /// Move selected rows above the previous visible row. Ignored while sorted, while rows are
+    /// pinned or nested, or if the selected rows are not contiguous in data order.
+    MoveRowsUp,
+
+    /// Move selected rows below the next visible row. Ignored in the same cases as
+    /// [`UiAction::MoveRowsUp`].
+    MoveRowsDown,
+
+    /// Move the column of the interactive cell left by one, as dragging its header does.
+    MoveColumnLeft,
+
+    /// Move the column of the interactive cell right by one, as dragging its header does.
+    MoveColumnRight,
+
+    /// Hide the column of the interactive cell. The last visible column is never hidden.
+    HideCurrentColumn,
+
+    /// Move to the next row with given mark; wraps around at the end.
+    NextMarkedRow(MarkKind),
+
+    /// Move to the previous row with given mark; wraps around at the beginning.
+    PrevMarkedRow(MarkKind),
+
+    /// Start recording keyboard macro, or stop the ongoing recording.
+    ToggleMacroRecording,
+
+    /// Replay the recorded macro once, from the interactive cell.
+    ReplayMacro,
+
+    /// Replay the recorded macro once for each selected row.
+    ReplayMacroOverSelection,
+
+    /// Highlight the visible rows which share the value of given column with any other visible
+    /// row, as encoded by the codec or [`RowViewer::cell_display_text`]. Applied again on the
+    /// same column, clears the highlight.
+    HighlightDuplicates(usize),
+
+    /// Select every row highlighted by [`UiAction::HighlightDuplicates`].
+    SelectDuplicates,
+
+    /// Show only the selected rows, hiding the others until toggled again; then the previous view
+    /// is restored, keeping the selected rows.
+    ToggleShowSelectedOnly,
+
+    /// Open a popup listing the columns of which values differ between the two selected rows.
+    /// Ignored unless exactly two rows are selected.
+    CompareRows,
+
+    /// Export the selected rows and columns into an `.xlsx` workbook, which is delivered to
+    /// [`RowViewer::on_xlsx_export`].
+    #[cfg(feature = "xlsx")]
+    ExportSelection,
+}
+
+impl UiAction {
+    /// Returns true if the action may modify rows, e.g. editing, pastes, row insertions and
+    /// undo; these are disabled by [`crate::Style::read_only`].
+    pub fn is_modifying(&self) -> bool {
+        matches!(
+            self,
+            Self::SelectionStartEditing
+                | Self::CommitEdition
+                | Self::CommitEditionAndMove(_)
+                | Self::Undo
+                | Self::Redo
+                | Self::CutSelection
+                | Self::PasteInPlace
+                | Self::PasteInsert
+                | Self::DuplicateRow
+                | Self::DeleteSelection
+                | Self::DeleteRow
+                | Self::SelectionDuplicateValues
+                | Self::FillSeries
+                | Self::RestoreTrash
+                | Self::InsertRowBelow
+                | Self::MoveRowsUp
+                | Self::MoveRowsDown
+                | Self::ReplayMacro
+                | Self::ReplayMacroOverSelection
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -335,6 +1519,166 @@ pub enum MoveDirection {
     Right,
 }
 
+/// Whether the step was undone or redone; see [`RowViewer::on_undo_applied`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UndoKind {
+    Undo,
+    Redo,
+}
+
+/// Change made to the table by an undo or redo step; see [`RowViewer::on_undo_applied`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TableChange {
+    /// Every column of the row is overwritten.
+    RowModified(usize),
+
+    /// Cells as `(row_index, column_index)` are overwritten.
+    CellsModified(Vec<(usize, usize)>),
+
+    /// `count` rows are inserted at `at`.
+    RowsInserted { at: usize, count: usize },
+
+    /// Rows are removed; indices are as of before the removal, in ascending order.
+    RowsRemoved(Vec<usize>),
+
+    /// `len` rows starting from `from` are moved to start at `to`.
+    RowsMoved { from: usize, len: usize, to: usize },
+
+    /// Rows are moved into the trash.
+    RowsTrashed(Vec<usize>),
+
+    /// Rows are restored from the trash.
+    RowsRestored(Vec<usize>),
+
+    /// Sort or column visibility is changed; no row data is affected.
+    Layout,
+}
+
+/// Row-level change recorded into the change journal of the table; see
+/// [`crate::DataTable::drain_changes`]. Indices are as of when the change was applied, so the
+/// events should be replayed in order.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ChangeEvent<R> {
+    /// Row is inserted at `index`.
+    Inserted { index: usize, row: R },
+
+    /// Row at `index` is removed; `row` is the removed contents.
+    Removed { index: usize, row: R },
+
+    /// Row at `index` is modified into `row`. Consecutive modifications of the same row are
+    /// merged into one, as well as into its insertion.
+    Modified { index: usize, row: R },
+
+    /// `len` rows starting from `from` are moved to start at `to`.
+    ///
+    /// Every row in `min(from, to)..max(from, to) + len` after the move is rewritten by
+    /// [`RowViewer::write_order`], which is not journaled as [`ChangeEvent::Modified`]; re-read
+    /// the order keys of those rows to persist them.
+    Moved { from: usize, len: usize, to: usize },
+
+    /// Row at `index` is moved into the trash.
+    Trashed(usize),
+
+    /// Row at `index` is restored from the trash.
+    Restored(usize),
+}
+
+impl<R> ChangeEvent<R> {
+    /// Appends the event to `journal`, merging it into the last event if possible.
+    pub(crate) fn push_into(self, journal: &mut Vec<Self>) {
+        if let Self::Modified { index, row } = self {
+            match journal.last_mut() {
+                Some(Self::Modified {
+                    index: last,
+                    row: dst,
+                })
+                | Some(Self::Inserted {
+                    index: last,
+                    row: dst,
+                }) if *last == index => {
+                    *dst = row;
+                }
+                _ => journal.push(Self::Modified { index, row }),
+            }
+        } else {
+            journal.push(self);
+        }
+    }
+}
+
+/// Kind of row marks to navigate between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MarkKind {
+    /// Row modified by UI since the last clearance of the user modification flag.
+    Modified,
+
+    /// Row which is reported as invalid by the viewer.
+    Invalid,
+
+    /// Row which matches current search of the viewer. While the search bar has any match,
+    /// navigates between the matching cells instead.
+    Match,
+
+    /// Row highlighted by [`UiAction::HighlightDuplicates`].
+    Duplicate,
+}
+
+/// Direction of a column sort.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// Screen geometry of a cell which was rendered in the current frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VisibleCell {
+    /// Index of the row in the table.
+    pub row: usize,
+
+    /// Index of the column, as used by [`RowViewer`].
+    pub column: usize,
+
+    /// Visual position of the row under current sort/filter.
+    pub vis_row: usize,
+
+    /// Screen rectangle of the cell.
+    pub rect: egui::Rect,
+}
+
+/// Geometry of the table body delivered to [`RowViewer::paint_overlay`].
+#[derive(Debug, Clone, Copy)]
+pub struct CellGeometry<'a> {
+    /// Visible area of the table body.
+    pub body_rect: egui::Rect,
+
+    /// Every cell rendered in this frame, ordered by visual row then visual column.
+    pub cells: &'a [VisibleCell],
+}
+
+impl CellGeometry<'_> {
+    /// Screen rectangle of given cell, if it's visible.
+    pub fn cell_rect(&self, row: usize, column: usize) -> Option<egui::Rect> {
+        self.cells
+            .iter()
+            .find(|x| x.row == row && x.column == column)
+            .map(|x| x.rect)
+    }
+
+    /// Screen rectangle covering every visible cell of given row.
+    pub fn row_rect(&self, row: usize) -> Option<egui::Rect> {
+        self.cells
+            .iter()
+            .filter(|x| x.row == row)
+            .map(|x| x.rect)
+            .reduce(|a, b| a.union(b))
+    }
+}
+
 pub fn default_hotkeys(context: &UiActionContext) -> Vec<(KeyboardShortcut, UiAction)> {
     let c = context.cursor;
 
@@ -365,12 +1709,27 @@ pub fn default_hotkeys(context: &UiActionContext) -> Vec<(KeyboardShortcut, UiAc
     } else {
         shortcut(&[
             (ctrl, Key::X, UiAction::CutSelection),
+            (none, Key::Escape, UiAction::CancelCut),
             (ctrl, Key::C, UiAction::CopySelection),
             (ctrl | shift, Key::V, UiAction::PasteInsert),
             (ctrl, Key::V, UiAction::PasteInPlace),
             (ctrl, Key::Y, UiAction::Redo),
             (ctrl, Key::Z, UiAction::Undo),
             (none, Key::Enter, UiAction::SelectionStartEditing),
+            (shift, Key::ArrowUp, UiAction::ExtendSelection(MD::Up)),
+            (shift, Key::ArrowDown, UiAction::ExtendSelection(MD::Down)),
+            (shift, Key::ArrowLeft, UiAction::ExtendSelection(MD::Left)),
+            (shift, Key::ArrowRight, UiAction::ExtendSelection(MD::Right)),
+            (
+                alt,
+                Key::ArrowDown,
+                UiAction::NextMarkedRow(MarkKind::Modified),
+            ),
+            (
+                alt,
+                Key::ArrowUp,
+                UiAction::PrevMarkedRow(MarkKind::Modified),
+            ),
             (none, Key::ArrowUp, UiAction::MoveSelection(MD::Up)),
             (none, Key::ArrowDown, UiAction::MoveSelection(MD::Down)),
             (none, Key::ArrowLeft, UiAction::MoveSelection(MD::Left)),
@@ -379,14 +1738,172 @@ pub fn default_hotkeys(context: &UiActionContext) -> Vec<(KeyboardShortcut, UiAc
             (alt, Key::V, UiAction::PasteInsert),
             (ctrl | shift, Key::D, UiAction::DuplicateRow),
             (ctrl, Key::D, UiAction::SelectionDuplicateValues),
+            (ctrl | shift, Key::A, UiAction::SelectAllIncludingFiltered),
             (ctrl, Key::A, UiAction::SelectAll),
+            (ctrl, Key::F, UiAction::OpenSearch),
             (ctrl, Key::Delete, UiAction::DeleteRow),
+            (none, Key::Insert, UiAction::InsertRowBelow),
             (none, Key::Delete, UiAction::DeleteSelection),
             (none, Key::Backspace, UiAction::DeleteSelection),
             (none, Key::PageUp, UiAction::NavPageUp),
             (none, Key::PageDown, UiAction::NavPageDown),
             (none, Key::Home, UiAction::NavTop),
             (none, Key::End, UiAction::NavBottom),
+            (shift, Key::F8, UiAction::PrevMarkedRow(MarkKind::Invalid)),
+            (none, Key::F8, UiAction::NextMarkedRow(MarkKind::Invalid)),
+            (shift, Key::F3, UiAction::PrevMarkedRow(MarkKind::Match)),
+            (none, Key::F3, UiAction::NextMarkedRow(MarkKind::Match)),
+            (ctrl | shift, Key::ArrowUp, UiAction::MoveRowsUp),
+            (ctrl | shift, Key::ArrowDown, UiAction::MoveRowsDown),
+            (ctrl | shift, Key::ArrowLeft, UiAction::MoveColumnLeft),
+            (ctrl | shift, Key::ArrowRight, UiAction::MoveColumnRight),
+            (ctrl | shift, Key::H, UiAction::HideCurrentColumn),
+            (ctrl | shift, Key::R, UiAction::ToggleMacroRecording),
+            (ctrl | alt, Key::P, UiAction::ReplayMacroOverSelection),
+            (ctrl | shift, Key::P, UiAction::ReplayMacro),
         ])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn replace_ignore_ascii_case() {
+        let replace = super::replace_ignore_ascii_case;
+
+        assert_eq!(replace("Foo foo FOO", "foo", "bar").unwrap(), "bar bar bar");
+        assert_eq!(replace("äFoö", "FO", "x").unwrap(), "äxö");
+        assert_eq!(replace("foo", "baz", "bar"), None);
+        assert_eq!(replace("foo", "", "bar"), None);
+    }
+
+    #[test]
+    fn default_hotkeys_reachable() {
+        use super::{default_hotkeys, UiActionContext, UiCursorState};
+
+        // Shortcuts are consumed in order, ignoring extra Shift and Alt; the ones with more
+        // modifiers must come first.
+        for cursor in [UiCursorState::Editing, UiCursorState::SelectOne] {
+            let hotkeys = default_hotkeys(&UiActionContext { cursor });
+
+            for (i, (earlier, _)) in hotkeys.iter().enumerate() {
+                for (later, action) in &hotkeys[i + 1..] {
+                    assert!(
+                        earlier.logical_key != later.logical_key
+                            || !later.modifiers.matches_logically(earlier.modifiers),
+                        "{action:?} is shadowed",
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dyn_viewer() {
+        use super::RowViewer;
+        use crate::tests::{TestRow, TestViewer};
+
+        let mut viewer: Box<dyn RowViewer<TestRow, CellValue = ()>> = Box::new(TestViewer {
+            computed: Some(1),
+            ..Default::default()
+        });
+        let mut table = crate::DataTable::from_iter([(1, false), (2, true)]);
+
+        assert!(viewer.is_computed_column(1));
+        assert!(viewer.try_create_codec(true).is_some());
+        assert!(!table.undo(&mut *viewer));
+        drop(crate::Renderer::new(&mut table, &mut *viewer));
+    }
+
+    #[test]
+    fn column_spec() {
+        use super::{ColumnSpec, RowViewer};
+        use crate::model::TableModel;
+
+        #[derive(Default)]
+        struct Row(String, f64);
+
+        let mut table = crate::DataTable::from_iter([Row("a".into(), 2.), Row("b".into(), 1.)]);
+        drop(crate::Renderer::with_columns(&mut table, |cols| {
+            cols.text("Name", |x| &x.0, |x| &mut x.0)
+                .drag_f64("Score", |x| &x.1, |x| &mut x.1)
+        }));
+
+        let mut spec = ColumnSpec::<Row>::new()
+            .text("Name", |x| &x.0, |x| &mut x.0)
+            .drag_f64("Score", |x| &x.1, |x| &mut x.1);
+
+        assert!(spec.compare_cell(&table[0], &table[1], 0).is_lt());
+        assert!(spec.compare_cell(&table[0], &table[1], 1).is_gt());
+
+        let mut codec = spec.try_create_codec(false).unwrap();
+        let mut row = codec.create_empty_decoded_row();
+        let mut text = String::new();
+
+        codec.encode_column(&table[0], 1, &mut text);
+        assert!(codec.decode_column(&text, 1, &mut row).is_ok());
+        assert!(codec.decode_column("x", 1, &mut row).is_err());
+        assert_eq!(row.1, 2.);
+    }
+
+    #[test]
+    fn codec_column_opt_out() {
+        use crate::tests::TestViewer;
+
+        // Computed column is out of the codec.
+        let row = (1, true);
+        let mut viewer = TestViewer {
+            computed: Some(1),
+            ..Default::default()
+        };
+        let mut encode = super::text_encoder(&mut viewer);
+        let mut text = String::new();
+
+        assert!(!encode(&row, 1, &mut text));
+        assert!(text.is_empty());
+        assert!(encode(&row, 0, &mut text));
+        assert_eq!(text, "1");
+    }
+
+    #[test]
+    fn change_journal_merge() {
+        use super::ChangeEvent::{self, *};
+
+        let mut journal = Vec::new();
+        let events: [ChangeEvent<i32>; 5] = [
+            Inserted { index: 0, row: 1 },
+            Modified { index: 0, row: 2 },
+            Modified { index: 1, row: 3 },
+            Modified { index: 1, row: 4 },
+            Trashed(1),
+        ];
+
+        for event in events {
+            event.push_into(&mut journal);
+        }
+
+        assert_eq!(
+            journal,
+            [
+                Inserted { index: 0, row: 2 },
+                Modified { index: 1, row: 4 },
+                Trashed(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_rows() {
+        use super::{RowStorage, SharedRows};
+        use std::{cell::RefCell, rc::Rc};
+
+        let mut row = Rc::new(RefCell::new(1));
+        let shared = SharedRows.share(&row);
+
+        SharedRows.write(&mut row, &Rc::new(RefCell::new(2)));
+        SharedRows.write(&mut row, &shared);
+
+        assert!(Rc::ptr_eq(&row, &shared));
+        assert_eq!(*shared.borrow(), 2);
+    }
+}
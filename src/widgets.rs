@@ -0,0 +1,298 @@
+//! Ready-made cell renderers, which can be used inside [`crate::RowViewer::show_cell_view`]
+//! or are rendered by the table itself through dedicated viewer hooks.
+
+use egui::{Color32, Pos2, Response, Sense, Stroke, Ui, Widget};
+
+/* ---------------------------------------------------------------------------------------------- */
+/*                                            SPARKLINE                                           */
+/* ---------------------------------------------------------------------------------------------- */
+
+/// Tiny line chart which fills the cell, showing a trend of the values. Hovering the chart
+/// shows the exact value under the pointer.
+#[derive(Debug, Clone)]
+pub struct Sparkline<'a> {
+    values: &'a [f32],
+    color: Option<Color32>,
+    stroke_width: f32,
+}
+
+impl<'a> Sparkline<'a> {
+    pub fn new(values: &'a [f32]) -> Self {
+        Self {
+            values,
+            color: None,
+            stroke_width: 1.,
+        }
+    }
+
+    /// Line color. Defaults to the strong text color.
+    pub fn color(mut self, color: Color32) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn stroke_width(mut self, width: f32) -> Self {
+        self.stroke_width = width;
+        self
+    }
+}
+
+impl Widget for Sparkline<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let size = ui
+            .available_size()
+            .max(egui::vec2(0., ui.spacing().interact_size.y));
+        let (rect, resp) = ui.allocate_exact_size(size, Sense::hover());
+        let values = self.values;
+
+        if values.is_empty() || !ui.is_rect_visible(rect) {
+            return resp;
+        }
+
+        let rect = rect.shrink2(egui::vec2(2., 3.));
+        let (min, max) = values
+            .iter()
+            .filter(|x| x.is_finite())
+            .fold((f32::MAX, f32::MIN), |(lo, hi), x| (lo.min(*x), hi.max(*x)));
+        let range = (max - min).max(f32::EPSILON);
+        let step = rect.width() / (values.len().max(2) - 1) as f32;
+
+        let point_at = |i: usize| {
+            let v = values[i];
+            let t = if v.is_finite() { (v - min) / range } else { 0. };
+            Pos2::new(
+                rect.left() + step * i as f32,
+                rect.bottom() - rect.height() * t,
+            )
+        };
+
+        let color = self.color.unwrap_or(ui.visuals().strong_text_color());
+        let points: Vec<_> = (0..values.len()).map(point_at).collect();
+
+        if let [single] = points[..] {
+            ui.painter().hline(
+                rect.x_range(),
+                single.y,
+                Stroke::new(self.stroke_width, color),
+            );
+        } else {
+            ui.painter()
+                .line(points, Stroke::new(self.stroke_width, color));
+        }
+
+        let Some(hover) = resp.hover_pos() else {
+            return resp;
+        };
+
+        let index =
+            (((hover.x - rect.left()) / step).round().max(0.) as usize).min(values.len() - 1);
+        ui.painter()
+            .circle_filled(point_at(index), self.stroke_width + 1.5, color);
+
+        resp.on_hover_ui_at_pointer(|ui| {
+            ui.monospace(format!("[{index}] {}", values[index]));
+        })
+    }
+}
+
+/* ---------------------------------------------------------------------------------------------- */
+/*                                            PROGRESS                                            */
+/* ---------------------------------------------------------------------------------------------- */
+
+/// Progress bar filling the cell, labeled with the percentage. Ratio is clamped to `0..=1`.
+#[derive(Debug, Clone)]
+pub struct ProgressCell {
+    ratio: f32,
+    color: Option<Color32>,
+}
+
+impl ProgressCell {
+    pub fn new(ratio: f32) -> Self {
+        Self { ratio, color: None }
+    }
+
+    /// Fill color of the bar. Defaults to the selection background color.
+    pub fn color(mut self, color: Color32) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+impl Widget for ProgressCell {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let size = ui
+            .available_size()
+            .max(egui::vec2(0., ui.spacing().interact_size.y));
+        let (rect, resp) = ui.allocate_exact_size(size, Sense::hover());
+
+        if !ui.is_rect_visible(rect) {
+            return resp;
+        }
+
+        let ratio = if self.ratio.is_finite() {
+            self.ratio.clamp(0., 1.)
+        } else {
+            0.
+        };
+        let visual = ui.visuals();
+        let bar = rect.shrink2(egui::vec2(2., 3.));
+        let rounding = egui::Rounding::same(2.);
+
+        ui.painter()
+            .rect_filled(bar, rounding, visual.extreme_bg_color);
+        ui.painter().rect_filled(
+            bar.with_max_x(bar.left() + bar.width() * ratio),
+            rounding,
+            self.color.unwrap_or(visual.selection.bg_fill),
+        );
+        ui.painter().text(
+            bar.center(),
+            egui::Align2::CENTER_CENTER,
+            format_progress(ratio),
+            egui::TextStyle::Monospace.resolve(ui.style()),
+            visual.strong_text_color(),
+        );
+
+        resp
+    }
+}
+
+/// Formats progress ratio as percentage, e.g. `0.425` → `"42.5%"`.
+pub fn format_progress(ratio: f32) -> String {
+    let percent = (ratio * 1000.).round() / 10.;
+    format!("{percent}%")
+}
+
+/// Parses a percentage(`"42.5%"`) or plain ratio(`"0.425"`) back to ratio; counterpart of
+/// [`format_progress`] for codec implementations.
+pub fn parse_progress(text: &str) -> Option<f32> {
+    let text = text.trim();
+
+    if let Some(percent) = text.strip_suffix('%') {
+        percent.trim().parse::<f32>().ok().map(|x| x / 100.)
+    } else {
+        text.parse().ok()
+    }
+}
+
+/* ---------------------------------------------------------------------------------------------- */
+/*                                            DURATION                                            */
+/* ---------------------------------------------------------------------------------------------- */
+
+const DURATION_UNITS: [(&str, u128); 5] = [
+    ("d", 86_400_000),
+    ("h", 3_600_000),
+    ("m", 60_000),
+    ("s", 1_000),
+    ("ms", 1),
+];
+
+/// Humanizes duration with its two most significant units, e.g. `"3m 42s"`, `"1d 4h"`. This is
+/// lossy, for display only; use [`format_duration_exact`] for codec implementations.
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let mut millis = duration.as_millis();
+    let mut parts = Vec::with_capacity(2);
+
+    for (unit, scale) in DURATION_UNITS {
+        let count = millis / scale;
+        millis %= scale;
+
+        if count > 0 {
+            parts.push(format!("{count}{unit}"));
+        } else if !parts.is_empty() {
+            // Stop at the first gap, e.g. "1h 0m 3s" is displayed as "1h".
+            break;
+        }
+
+        if parts.len() == 2 {
+            break;
+        }
+    }
+
+    if parts.is_empty() {
+        "0s".into()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Formats duration with every non-zero unit down to milliseconds, e.g. `"1h 3s"`, which
+/// [`parse_duration`] reads back without loss.
+pub fn format_duration_exact(duration: std::time::Duration) -> String {
+    let mut millis = duration.as_millis();
+    let mut parts = Vec::new();
+
+    for (unit, scale) in DURATION_UNITS {
+        let count = millis / scale;
+        millis %= scale;
+
+        if count > 0 {
+            parts.push(format!("{count}{unit}"));
+        }
+    }
+
+    if parts.is_empty() {
+        "0s".into()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Parses whitespace separated `<count><unit>` tokens(`d`, `h`, `m`, `s`, `ms`) back to
+/// duration; counterpart of [`format_duration_exact`] for codec implementations.
+pub fn parse_duration(text: &str) -> Option<std::time::Duration> {
+    let mut millis = 0u128;
+    let mut any = false;
+
+    for token in text.split_whitespace() {
+        let split = token.find(|c: char| !c.is_ascii_digit())?;
+        let (count, unit) = token.split_at(split);
+        let count = count.parse::<u128>().ok()?;
+        let (_, scale) = DURATION_UNITS.iter().find(|(u, _)| *u == unit)?;
+
+        millis = millis.checked_add(count.checked_mul(*scale)?)?;
+        any = true;
+    }
+
+    if !any {
+        return None;
+    }
+
+    Some(std::time::Duration::from_millis(millis.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[test]
+    fn duration_format_parse() {
+        use super::{format_duration as fmt, parse_duration as parse};
+
+        assert_eq!(fmt(Duration::from_secs(222)), "3m 42s");
+        assert_eq!(fmt(Duration::from_secs(3603)), "1h");
+        assert_eq!(fmt(Duration::from_secs(100_000)), "1d 3h");
+        assert_eq!(fmt(Duration::from_millis(350)), "350ms");
+        assert_eq!(fmt(Duration::ZERO), "0s");
+
+        assert_eq!(parse("3m 42s"), Some(Duration::from_secs(222)));
+        assert_eq!(parse(" 1d  4h "), Some(Duration::from_secs(100_800)));
+        assert_eq!(parse("350ms"), Some(Duration::from_millis(350)));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("3x"), None);
+        assert_eq!(parse("42"), None);
+
+        // Exact format round trips, where the humanized one drops the minor units.
+        let exact = super::format_duration_exact;
+        assert_eq!(exact(Duration::from_secs(3603)), "1h 3s");
+        assert_eq!(exact(Duration::ZERO), "0s");
+
+        for millis in [0, 350, 3_603_000, 90_061_001, u32::MAX as u64] {
+            let duration = Duration::from_millis(millis);
+            assert_eq!(parse(&exact(duration)), Some(duration));
+        }
+
+        assert_eq!(super::parse_progress("42.5%"), Some(0.425));
+        assert_eq!(super::format_progress(0.425), "42.5%");
+    }
+}